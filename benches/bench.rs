@@ -5,7 +5,7 @@ extern crate test;
 
 use std::collections::BTreeMap;
 
-use handlebars::{Handlebars, Template};
+use handlebars::{Handlebars, Template, Context};
 use serialize::json::{Json, ToJson};
 
 static SOURCE: &'static str = "<html>
@@ -60,3 +60,83 @@ fn render_template(b: &mut test::Bencher) {
     let data = make_data();
     b.iter(|| handlebars.render("table", &data).ok().unwrap())
 }
+
+fn make_large_data() -> Json {
+    let mut data = make_data();
+    let mut teams = Vec::new();
+    for i in 0..10000u32 {
+        let mut t = BTreeMap::new();
+        t.insert("name".to_string(), format!("Team {}", i).to_json());
+        t.insert("score".to_string(), (i % 100).to_json());
+        teams.push(t)
+    }
+    data.insert("teams".to_string(), teams.to_json());
+    data.to_json()
+}
+
+#[bench]
+fn render_many_templates_wraps(b: &mut test::Bencher) {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_template_string("year", "{{year}}").ok().expect("Invalid template format");
+    handlebars.register_template_string("first", "{{teams.[0].name}}")
+        .ok()
+        .expect("Invalid template format");
+
+    let data = make_large_data();
+    b.iter(|| {
+               handlebars.render("year", &data).ok().unwrap();
+               handlebars.render("first", &data).ok().unwrap();
+           })
+}
+
+#[bench]
+fn render_many_templates_borrowed_context(b: &mut test::Bencher) {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_template_string("year", "{{year}}").ok().expect("Invalid template format");
+    handlebars.register_template_string("first", "{{teams.[0].name}}")
+        .ok()
+        .expect("Invalid template format");
+
+    let data = make_large_data();
+    let ctx = Context::borrowed(&data);
+    b.iter(|| {
+               handlebars.render_with_context("year", &ctx).ok().unwrap();
+               handlebars.render_with_context("first", &ctx).ok().unwrap();
+           })
+}
+
+#[bench]
+fn render_repeated_path_params(b: &mut test::Bencher) {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_template_string("t0", "{{#if (eq teams teams teams teams)}}yes{{/if}}")
+        .ok()
+        .expect("Invalid template format");
+
+    let data = make_large_data();
+    b.iter(|| handlebars.render("t0", &data).ok().unwrap())
+}
+
+#[bench]
+fn render_large_each(b: &mut test::Bencher) {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_template_string("t0", "{{#each teams}}{{name}}: {{score}}\n{{/each}}")
+        .ok()
+        .expect("Invalid template format");
+
+    let data = make_large_data();
+    let ctx = Context::borrowed(&data);
+    b.iter(|| handlebars.render_with_context("t0", &ctx).ok().unwrap())
+}
+
+#[bench]
+fn render_large_each_block_param(b: &mut test::Bencher) {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_template_string("t0",
+                                  "{{#each teams as |t|}}{{t.name}}: {{t.score}}\n{{/each}}")
+        .ok()
+        .expect("Invalid template format");
+
+    let data = make_large_data();
+    let ctx = Context::borrowed(&data);
+    b.iter(|| handlebars.render_with_context("t0", &ctx).ok().unwrap())
+}
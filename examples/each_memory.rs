@@ -0,0 +1,75 @@
+//! Memory used to render a large `{{#each}}`, with and without block params
+//!
+//! Prints the bytes allocated in total and the peak of live bytes for each
+//! form, counted by a global allocator. Run it with
+//! `cargo run --release --example each_memory`.
+extern crate handlebars;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use handlebars::{Handlebars, Context};
+
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+        let live = LIVE.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(live, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ITEMS: usize = 10000;
+
+fn measure(handlebars: &Handlebars, name: &str, ctx: &Context) {
+    ALLOCATED.store(0, Ordering::SeqCst);
+    PEAK.store(LIVE.load(Ordering::SeqCst), Ordering::SeqCst);
+    let base = LIVE.load(Ordering::SeqCst);
+
+    let output = handlebars.render_with_context(name, ctx).unwrap();
+
+    println!("{:<20} allocated {:>10} bytes, peak {:>10} bytes, output {} bytes",
+             name,
+             ALLOCATED.load(Ordering::SeqCst),
+             PEAK.load(Ordering::SeqCst) - base,
+             output.len());
+}
+
+fn main() {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_template_string("each", "{{#each teams}}{{name}}: {{score}}\n{{/each}}")
+        .unwrap();
+    handlebars.register_template_string("each_block_param",
+                                  "{{#each teams as |t|}}{{t.name}}: {{t.score}}\n{{/each}}")
+        .unwrap();
+
+    let mut teams = Vec::new();
+    for i in 0..ITEMS {
+        let mut t = BTreeMap::new();
+        t.insert("name".to_string(), format!("Team {}", i));
+        t.insert("score".to_string(), (i % 100).to_string());
+        teams.push(t);
+    }
+    let mut data = BTreeMap::new();
+    data.insert("teams".to_string(), teams);
+    let ctx = Context::wraps(&data);
+
+    println!("each over {} items", ITEMS);
+    measure(&handlebars, "each", &ctx);
+    measure(&handlebars, "each_block_param", &ctx);
+}
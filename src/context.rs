@@ -5,93 +5,171 @@ use serialize::json::{Json, ToJson};
 use serde_json::value::{Value as Json, ToJson, Map};
 
 use pest::prelude::*;
-use std::collections::{VecDeque, BTreeMap};
+use std::borrow::Cow;
+use std::fmt;
+use std::cell::RefCell;
+use std::collections::{VecDeque, BTreeMap, BTreeSet, HashMap};
+use std::rc::Rc;
 
 use grammar::{Rdp, Rule};
 
 static DEFAULT_VALUE: Json = Json::Null;
 
+/// Default max number of segments, including `..`, in a path. Longer paths,
+/// which are likely from untrusted templates, are not navigated and resolve to
+/// null. Rendering uses the limit of `Registry::set_max_path_segments`.
+pub(crate) const DEFAULT_MAX_PATH_SEGMENTS: usize = 256;
+
 pub type Object = BTreeMap<String, Json>;
 
 /// The context wrap data you render on your templates.
 ///
+/// A context either owns its data (`Context::wraps`) or borrows it from the caller
+/// (`Context::borrowed`). Borrowed data is only cloned when it's modified through
+/// `data_mut`, by a decorator for example.
 #[derive(Debug, Clone)]
-pub struct Context {
-    data: Json,
+pub struct Context<'a> {
+    data: Cow<'a, Json>,
 }
 
-#[inline]
-fn parse_json_visitor_inner<'a>(path_stack: &mut VecDeque<&'a str>, path: &'a str) {
+/// A segment of a parsed path
+#[derive(Debug, PartialEq)]
+enum PathSeg {
+    Up,
+    Id(String),
+    // segment in brackets like `[a]`, which is popped with the one before it by `..`
+    RawId(String),
+}
+
+/// Parse result of a path, which only depends on the path string and is
+/// cached by `parse_path`
+#[derive(Debug)]
+struct ParsedPath {
+    valid: bool,
+    // number of leading `..`, which selects a base path from the path context
+    leading_ups: usize,
+    segs: Vec<PathSeg>,
+}
+
+/// Max number of parsed paths kept by a thread. The cache is cleared when it's
+/// full, since base paths like `list.[12]` may not repeat.
+const PATH_CACHE_CAPACITY: usize = 1024;
+
+thread_local! {
+    static PATH_CACHE: RefCell<HashMap<String, Rc<ParsedPath>>> = RefCell::new(HashMap::new());
+}
+
+fn parse_path_uncached(path: &str) -> ParsedPath {
     let path_in = StringInput::new(path);
     let mut parser = Rdp::new(path_in);
 
-    let mut seg_stack: VecDeque<&Token<Rule>> = VecDeque::new();
-    if parser.path() {
-        for seg in parser.queue().iter() {
-            match seg.rule {
-                Rule::path_var | Rule::path_idx | Rule::path_key => {}
-                Rule::path_up => {
-                    path_stack.pop_back();
-                    if let Some(p) = seg_stack.pop_back() {
-                        // also pop array index like [1]
-                        if p.rule == Rule::path_raw_id {
-                            seg_stack.pop_back();
-                        }
-                    }
-                }
-                Rule::path_id |
-                Rule::path_raw_id |
-                Rule::path_num_id => {
-                    seg_stack.push_back(seg);
-                }
-                _ => {}
+    if !parser.path() {
+        return ParsedPath {
+            valid: false,
+            leading_ups: 0,
+            segs: Vec::new(),
+        };
+    }
+
+    let queue = parser.queue();
+    let leading_ups = queue.iter().take_while(|seg| seg.rule == Rule::path_up).count();
+    let segs = queue.iter()
+        .filter_map(|seg| match seg.rule {
+            Rule::path_up => Some(PathSeg::Up),
+            Rule::path_id | Rule::path_num_id => {
+                Some(PathSeg::Id(path[seg.start..seg.end].to_owned()))
             }
+            Rule::path_raw_id => Some(PathSeg::RawId(path[seg.start..seg.end].to_owned())),
+            _ => None,
+        })
+        .collect();
+
+    ParsedPath {
+        valid: true,
+        leading_ups: leading_ups,
+        segs: segs,
+    }
+}
+
+/// Parse a path, reusing the result of an earlier parse of the same string
+fn parse_path(path: &str) -> Rc<ParsedPath> {
+    PATH_CACHE.with(|cache| {
+        if let Some(parsed) = cache.borrow().get(path) {
+            return parsed.clone();
         }
 
-        for i in seg_stack.iter() {
-            let id = &path[i.start..i.end];
-            path_stack.push_back(id);
+        let parsed = Rc::new(parse_path_uncached(path));
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= PATH_CACHE_CAPACITY {
+            cache.clear();
         }
-    }
+        cache.insert(path.to_owned(), parsed.clone());
+        parsed
+    })
 }
 
 #[inline]
-fn parse_json_visitor<'a>(path_stack: &mut VecDeque<&'a str>,
-                          base_path: &'a str,
-                          path_context: &'a VecDeque<String>,
-                          relative_path: &'a str) {
-    let path_in = StringInput::new(relative_path);
-    let mut parser = Rdp::new(path_in);
-
-    if parser.path() {
-        let mut path_context_depth: i64 = -1;
+/// Returns false if the path has more than `max_segments` segments
+fn parse_json_visitor_inner<'a>(path_stack: &mut VecDeque<&'a str>,
+                                parsed: &'a ParsedPath,
+                                max_segments: usize)
+                                -> bool {
+    if !parsed.valid {
+        return true;
+    }
+    if parsed.segs.len() > max_segments {
+        return false;
+    }
 
-        let mut iter = parser.queue().iter();
-        loop {
-            if let Some(sg) = iter.next() {
-                if sg.rule == Rule::path_up {
-                    path_context_depth += 1;
-                } else {
-                    break;
+    let mut seg_stack: VecDeque<&PathSeg> = VecDeque::new();
+    for seg in parsed.segs.iter() {
+        match *seg {
+            PathSeg::Up => {
+                path_stack.pop_back();
+                if let Some(&PathSeg::RawId(_)) = seg_stack.pop_back() {
+                    // also pop array index like [1]
+                    seg_stack.pop_back();
                 }
-            } else {
-                break;
             }
+            _ => seg_stack.push_back(seg),
         }
+    }
 
-        if path_context_depth >= 0 {
-            if let Some(context_base_path) = path_context.get(path_context_depth as usize) {
-                parse_json_visitor_inner(path_stack, context_base_path);
-            } else {
-                parse_json_visitor_inner(path_stack, base_path);
-            }
-        } else {
-            parse_json_visitor_inner(path_stack, base_path);
+    for seg in seg_stack.iter() {
+        match **seg {
+            PathSeg::Id(ref id) | PathSeg::RawId(ref id) => path_stack.push_back(id),
+            PathSeg::Up => {}
         }
+    }
+    true
+}
 
-        parse_json_visitor_inner(path_stack, relative_path);
+/// Resolve an array index of a path segment, where a negative index like `-1`
+/// counts from the end of the array
+fn array_index(len: usize, seg: &str) -> Option<usize> {
+    match seg.parse::<i64>() {
+        Ok(idx) if idx >= 0 => Some(idx as usize),
+        Ok(idx) => {
+            let back = (-(idx + 1)) as usize + 1;
+            if back <= len { Some(len - back) } else { None }
+        }
+        Err(_) => None,
+    }
+}
+
+/// Returns false if the path is too long to navigate
+#[inline]
+fn parse_json_visitor<'a>(path_stack: &mut VecDeque<&'a str>,
+                          base_path: &'a ParsedPath,
+                          relative_path: &'a ParsedPath,
+                          max_segments: usize)
+                          -> bool {
+    if relative_path.valid {
+        return parse_json_visitor_inner(path_stack, base_path, max_segments) &&
+               parse_json_visitor_inner(path_stack, relative_path, max_segments);
     }
     // TODO: report invalid path
+    true
 }
 
 #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
@@ -130,24 +208,127 @@ fn merge_json(base: &Json, addition: &Object) -> Json {
     Json::Object(base_map)
 }
 
-impl Context {
+fn join_path(base: &str, seg: &str) -> String {
+    if base.is_empty() {
+        seg.to_owned()
+    } else {
+        format!("{}.{}", base, seg)
+    }
+}
+
+fn glob_json<'a>(data: &'a Json, segs: &[&str], matches: &mut Vec<&'a Json>) {
+    let (seg, rest) = match segs.split_first() {
+        Some((seg, rest)) => (*seg, rest),
+        None => {
+            matches.push(data);
+            return;
+        }
+    };
+
+    if seg == "*" {
+        match *data {
+            Json::Array(ref l) => {
+                for v in l.iter() {
+                    glob_json(v, rest, matches);
+                }
+            }
+            Json::Object(ref m) => {
+                for v in m.values() {
+                    glob_json(v, rest, matches);
+                }
+            }
+            _ => {}
+        }
+    } else {
+        let key = if seg.starts_with('[') && seg.ends_with(']') {
+            &seg[1..seg.len() - 1]
+        } else {
+            seg
+        };
+        let child = match *data {
+            Json::Array(ref l) => array_index(l.len(), key).and_then(|idx| l.get(idx)),
+            Json::Object(ref m) => m.get(key),
+            _ => None,
+        };
+        if let Some(v) = child {
+            glob_json(v, rest, matches);
+        }
+    }
+}
+
+fn diff_json(path: &str, a: &Json, b: &Json, diffs: &mut Vec<String>) {
+    if a == b {
+        return;
+    }
+
+    match (a, b) {
+        (&Json::Object(ref ma), &Json::Object(ref mb)) => {
+            if !path.is_empty() {
+                diffs.push(path.to_owned());
+            }
+            let keys: BTreeSet<&String> = ma.keys().chain(mb.keys()).collect();
+            for k in keys {
+                let child_path = join_path(path, k);
+                match (ma.get(k), mb.get(k)) {
+                    (Some(va), Some(vb)) => diff_json(&child_path, va, vb, diffs),
+                    _ => diffs.push(child_path),
+                }
+            }
+        }
+        (&Json::Array(ref la), &Json::Array(ref lb)) => {
+            if !path.is_empty() {
+                diffs.push(path.to_owned());
+            }
+            let len = if la.len() > lb.len() { la.len() } else { lb.len() };
+            for i in 0..len {
+                let child_path = join_path(path, &format!("[{}]", i));
+                match (la.get(i), lb.get(i)) {
+                    (Some(va), Some(vb)) => diff_json(&child_path, va, vb, diffs),
+                    _ => diffs.push(child_path),
+                }
+            }
+        }
+        _ => {
+            if path.is_empty() {
+                diffs.push("this".to_owned());
+            } else {
+                diffs.push(path.to_owned());
+            }
+        }
+    }
+}
+
+impl<'a> Context<'a> {
     /// Create a context with null data
-    pub fn null() -> Context {
-        Context { data: Json::Null }
+    pub fn null() -> Context<'a> {
+        Context { data: Cow::Owned(Json::Null) }
     }
 
     /// Create a context with given data
-    pub fn wraps<T: ToJson>(e: &T) -> Context {
-        Context { data: to_json(e) }
+    pub fn wraps<T: ToJson>(e: &T) -> Context<'a> {
+        Context { data: Cow::Owned(to_json(e)) }
+    }
+
+    /// Create a context that borrows given data instead of converting or cloning it
+    ///
+    /// Useful when a large, read-only dataset is rendered many times.
+    pub fn borrowed(data: &'a Json) -> Context<'a> {
+        Context { data: Cow::Borrowed(data) }
+    }
+
+    /// Create a context of an object with given keys and values, which are moved
+    /// into it instead of cloned
+    pub(crate) fn from_pairs(pairs: Vec<(String, Json)>) -> Context<'a> {
+        Context { data: Cow::Owned(Json::Object(pairs.into_iter().collect())) }
     }
 
     /// Extend current context with another JSON object
     /// If current context is a JSON object, it's identical to a normal merge
     /// Otherwise, the current value will be stored in new JSON object with key `this`, and merged
     /// keys are also available.
-    pub fn extend(&self, hash: &Object) -> Context {
+    pub fn extend(&self, hash: &Object) -> Context<'a> {
         let new_data = merge_json(&self.data, hash);
-        Context { data: new_data }
+        Context { data: Cow::Owned(new_data) }
     }
 
     /// Navigate the context with base path and relative path
@@ -155,29 +336,80 @@ impl Context {
     /// and set relative path to helper argument or so.
     ///
     /// If you want to navigate from top level, set the base path to `"."`
+    ///
+    /// Paths with more than 256 segments, counting `..`, resolve to null.
+    /// Templates are rendered with the limit of `Registry::set_max_path_segments`
+    /// instead.
     pub fn navigate(&self,
                     base_path: &str,
                     path_context: &VecDeque<String>,
                     relative_path: &str)
                     -> &Json {
+        self.navigate_limited(base_path,
+                              path_context,
+                              relative_path,
+                              DEFAULT_MAX_PATH_SEGMENTS)
+    }
+
+    /// Navigate like `navigate`, with a max number of path segments
+    pub(crate) fn navigate_limited(&self,
+                                   base_path: &str,
+                                   path_context: &VecDeque<String>,
+                                   relative_path: &str,
+                                   max_segments: usize)
+                                   -> &Json {
+        self.navigate_opt_limited(base_path, path_context, relative_path, max_segments)
+            .unwrap_or(&DEFAULT_VALUE)
+    }
+
+    /// Navigate the context like `navigate`, but return `None` if any segment of
+    /// the path can't be resolved in an object or array, so a missing value can
+    /// be told apart from an explicit null
+    pub fn navigate_opt(&self,
+                        base_path: &str,
+                        path_context: &VecDeque<String>,
+                        relative_path: &str)
+                        -> Option<&Json> {
+        self.navigate_opt_limited(base_path,
+                                  path_context,
+                                  relative_path,
+                                  DEFAULT_MAX_PATH_SEGMENTS)
+    }
+
+    /// Navigate like `navigate_opt`, with a max number of path segments
+    pub(crate) fn navigate_opt_limited(&self,
+                                       base_path: &str,
+                                       path_context: &VecDeque<String>,
+                                       relative_path: &str,
+                                       max_segments: usize)
+                                       -> Option<&Json> {
+        let relative_path = parse_path(relative_path);
+        // `../` selects a base path from the path context
+        let context_base_path = if relative_path.leading_ups > 0 {
+            path_context.get(relative_path.leading_ups - 1)
+        } else {
+            None
+        };
+        let base_path = parse_path(context_base_path.map(|p| p.as_str()).unwrap_or(base_path));
+
         let mut path_stack: VecDeque<&str> = VecDeque::new();
-        parse_json_visitor(&mut path_stack, base_path, path_context, relative_path);
+        if !parse_json_visitor(&mut path_stack, &base_path, &relative_path, max_segments) {
+            return None;
+        }
 
         let paths: Vec<&str> = path_stack.iter().map(|x| *x).collect();
-        let mut data: &Json = &self.data;
+        let mut data: Option<&Json> = Some(&self.data);
         for p in paths.iter() {
-            if *p == "this" && data.as_object().and_then(|m| m.get("this")).is_none() {
-                continue;
-            }
-            data = match *data {
-                Json::Array(ref l) => {
-                    p.parse::<usize>()
-                        .and_then(|idx_u| Ok(l.get(idx_u).unwrap_or(&DEFAULT_VALUE)))
-                        .unwrap_or(&DEFAULT_VALUE)
+            data = data.and_then(|d| {
+                if *p == "this" && d.as_object().and_then(|m| m.get("this")).is_none() {
+                    return Some(d);
                 }
-                Json::Object(ref m) => m.get(*p).unwrap_or(&DEFAULT_VALUE),
-                _ => &DEFAULT_VALUE,
-            }
+                match *d {
+                    Json::Array(ref l) => array_index(l.len(), p).and_then(|idx_u| l.get(idx_u)),
+                    Json::Object(ref m) => m.get(*p),
+                    _ => None,
+                }
+            });
         }
         data
     }
@@ -187,13 +419,89 @@ impl Context {
     }
 
     pub fn data_mut(&mut self) -> &mut Json {
-        &mut self.data
+        self.data.to_mut()
+    }
+
+    /// Compare with another context and return paths of values that are changed,
+    /// added or removed, like `["address", "address.city", "tags.[2]"]`
+    ///
+    /// When a nested value changes, paths of all its parents are included too,
+    /// so a caller can decide whether a region rendered from a parent path
+    /// needs to be re-rendered. A change of the root value, which is not an
+    /// object or array on both sides, is reported as `"this"`.
+    pub fn diff(&self, other: &Context) -> Vec<String> {
+        let mut diffs = Vec::new();
+        diff_json("", self.data(), other.data(), &mut diffs);
+        diffs
+    }
+
+    /// Collect values matching a dotted path from top level, where a `*`
+    /// segment matches every item of an array or every value of an object
+    ///
+    /// For example, `items.*.name` returns names of all items. Values are in
+    /// array order, or key order for objects. Missing values are skipped.
+    pub fn navigate_glob(&self, pattern: &str) -> Vec<&Json> {
+        let segs: Vec<&str> = pattern.split('.').filter(|s| !s.is_empty()).collect();
+        let mut matches = Vec::new();
+        glob_json(self.data(), &segs, &mut matches);
+        matches
+    }
+}
+
+/// Type of a Json value, for error messages like "expected an array but got object"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Null,
+    Boolean,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let name = match *self {
+            ValueType::Null => "null",
+            ValueType::Boolean => "boolean",
+            ValueType::Number => "number",
+            ValueType::String => "string",
+            ValueType::Array => "array",
+            ValueType::Object => "object",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Returns type of the Json value
+pub fn value_type(v: &Json) -> ValueType {
+    if v.is_boolean() {
+        ValueType::Boolean
+    } else if v.is_number() {
+        ValueType::Number
+    } else if v.is_string() {
+        ValueType::String
+    } else if v.is_array() {
+        ValueType::Array
+    } else if v.is_object() {
+        ValueType::Object
+    } else {
+        ValueType::Null
     }
 }
 
 /// Render Json data with default format
 pub trait JsonRender {
+    /// Render for template output. Arrays are rendered like `[1, 2, 3]`,
+    /// objects as `[object]` and null as empty string.
     fn render(&self) -> String;
+
+    /// Render as serialized JSON, like `{"a":[1,2]}`
+    ///
+    /// Defaults to `render`, so existing implementations keep compiling.
+    fn render_json(&self) -> String {
+        self.render()
+    }
 }
 
 pub trait JsonTruthy {
@@ -220,9 +528,11 @@ impl JsonRender for Json {
             Json::Array(ref a) => {
                 let mut buf = String::new();
                 buf.push('[');
-                for i in a.iter() {
+                for (idx, i) in a.iter().enumerate() {
+                    if idx > 0 {
+                        buf.push_str(", ");
+                    }
                     buf.push_str(i.render().as_ref());
-                    buf.push_str(", ");
                 }
                 buf.push(']');
                 buf
@@ -230,6 +540,10 @@ impl JsonRender for Json {
             Json::Object(_) => "[object]".to_owned(),
         }
     }
+
+    fn render_json(&self) -> String {
+        self.to_string()
+    }
 }
 
 #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
@@ -258,6 +572,16 @@ pub fn as_string(src: &Json) -> Option<&str> {
     src.as_str()
 }
 
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+pub fn as_bool(src: &Json) -> Option<bool> {
+    src.as_boolean()
+}
+
+#[cfg(feature = "serde_type")]
+pub fn as_bool(src: &Json) -> Option<bool> {
+    src.as_bool()
+}
+
 impl JsonTruthy for Json {
     fn is_truthy(&self) -> bool {
         match *self {
@@ -284,8 +608,9 @@ impl JsonTruthy for Json {
 #[cfg(test)]
 #[cfg(feature = "serde_type")]
 mod test {
-    use context::{self, JsonRender, Context};
+    use context::{self, JsonRender, Context, PathSeg, to_json};
     use std::collections::{VecDeque, BTreeMap};
+    use std::rc::Rc;
     use serde_json::error::Error;
     use serde_json::value::{Value as Json, ToJson, Map};
 
@@ -295,6 +620,15 @@ mod test {
         let thing = Json::String(raw.to_string());
 
         assert_eq!(raw, thing.render());
+
+        let list = to_json(&vec![to_json(&1), to_json(&"a".to_owned()), to_json(&vec![2, 3])]);
+        assert_eq!(list.render(), "[1, a, [2, 3]]");
+        assert_eq!(to_json(&Vec::<String>::new()).render(), "[]");
+        assert_eq!(list.render_json(), "[1,\"a\",[2,3]]");
+
+        let obj = to_json(&btreemap! {"a".to_string() => vec![1, 2]});
+        assert_eq!(obj.render(), "[object]");
+        assert_eq!(obj.render_json(), "{\"a\":[1,2]}");
     }
 
     struct Address {
@@ -395,6 +729,87 @@ mod test {
                    "4".to_owned());
     }
 
+    #[test]
+    fn test_navigate_opt() {
+        let ctx = Context::wraps(&btreemap! {
+            "a".to_string() => to_json(&None::<String>),
+            "list".to_string() => to_json(&vec![1]),
+            "obj".to_string() => to_json(&btreemap! {"b".to_string() => 1})
+        });
+        let empty = VecDeque::new();
+
+        assert!(ctx.navigate_opt(".", &empty, "a").unwrap().is_null());
+        assert!(ctx.navigate_opt(".", &empty, "b").is_none());
+        assert_eq!(ctx.navigate_opt(".", &empty, "list.[0]").unwrap().render(), "1");
+        assert!(ctx.navigate_opt(".", &empty, "list.[1]").is_none());
+        assert_eq!(ctx.navigate_opt(".", &empty, "list.[-1]").unwrap().render(), "1");
+        assert!(ctx.navigate_opt(".", &empty, "list.[-2]").is_none());
+        assert!(ctx.navigate_opt(".", &empty, "list.a").is_none());
+        assert_eq!(ctx.navigate_opt("obj", &empty, "b").unwrap().render(), "1");
+        assert!(ctx.navigate_opt(".", &empty, "obj.b.c").is_none());
+        assert!(ctx.navigate(".", &empty, "obj.b.c").is_null());
+    }
+
+    #[test]
+    fn test_parse_path_cache() {
+        let parsed = context::parse_path("list.[0].name");
+        assert!(Rc::ptr_eq(&parsed, &context::parse_path("list.[0].name")));
+        assert_eq!(parsed.segs,
+                   vec![PathSeg::Id("list".to_owned()),
+                        PathSeg::RawId("0".to_owned()),
+                        PathSeg::Id("name".to_owned())]);
+        assert!(!context::parse_path("a..").valid);
+
+        // cached paths navigate like fresh ones
+        let ctx = Context::wraps(&btreemap! {
+            "obj".to_string() => to_json(&btreemap! {"b".to_string() => 1}),
+            "b".to_string() => to_json(&2)
+        });
+        let mut path_context = VecDeque::new();
+        path_context.push_front(".".to_owned());
+        for _ in 0..2 {
+            assert_eq!(ctx.navigate("obj", &path_context, "b").render(), "1");
+            assert_eq!(ctx.navigate("obj", &path_context, "../b").render(), "2");
+        }
+    }
+
+    #[test]
+    fn test_long_path() {
+        let mut map = Map::new();
+        map.insert("age".to_string(), context::to_json(&4usize));
+        let ctx = Context::wraps(&map);
+
+        let up_path = format!("{}age", "../".repeat(10));
+        assert_eq!(ctx.navigate(".", &VecDeque::new(), &up_path).render(),
+                   "4".to_owned());
+
+        let long_up_path = format!("{}age", "../".repeat(1000));
+        assert!(ctx.navigate_opt(".", &VecDeque::new(), &long_up_path).is_none());
+        assert!(ctx.navigate(".", &VecDeque::new(), &long_up_path).is_null());
+
+        let long_path = vec!["age"; 1000].join(".");
+        assert!(ctx.navigate_opt(".", &VecDeque::new(), &long_path).is_none());
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let mut map = Map::new();
+        map.insert("age".to_string(), context::to_json(&4usize));
+        let data = Json::Object(map);
+
+        let mut ctx = Context::borrowed(&data);
+        assert_eq!(ctx.navigate(".", &VecDeque::new(), "age").render(),
+                   "4".to_owned());
+
+        if let Some(m) = ctx.data_mut().as_object_mut() {
+            m.insert("age".to_string(), context::to_json(&5usize));
+        }
+        assert_eq!(ctx.navigate(".", &VecDeque::new(), "age").render(),
+                   "5".to_owned());
+        assert_eq!(Context::borrowed(&data).navigate(".", &VecDeque::new(), "age").render(),
+                   "4".to_owned());
+    }
+
     #[test]
     fn test_extend() {
         let mut map = Map::new();
@@ -419,13 +834,72 @@ mod test {
         assert_eq!(ctx_a2.navigate(".", &VecDeque::new(), "tag").render(),
                    "h1".to_owned());
     }
+
+    #[test]
+    fn test_diff() {
+        let ctx1 = Context::wraps(&btreemap! {
+            "name".to_string() => to_json(&"Ning".to_owned()),
+            "age".to_string() => to_json(&27),
+            "phone".to_string() => to_json(&"1234".to_owned()),
+            "address".to_string() => to_json(&btreemap! {
+                "city".to_string() => "Beijing".to_string(),
+                "country".to_string() => "China".to_string()
+            }),
+            "tags".to_string() => to_json(&vec!["a".to_string(), "b".to_string()])
+        });
+        let ctx2 = Context::wraps(&btreemap! {
+            "name".to_string() => to_json(&"Ning".to_owned()),
+            "age".to_string() => to_json(&28),
+            "email".to_string() => to_json(&"ning@example.com".to_owned()),
+            "address".to_string() => to_json(&btreemap! {
+                "city".to_string() => "Shanghai".to_string(),
+                "country".to_string() => "China".to_string()
+            }),
+            "tags".to_string() => to_json(&vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        });
+
+        assert!(ctx1.diff(&ctx1).is_empty());
+        assert_eq!(ctx1.diff(&ctx2),
+                   vec!["address", "address.city", "age", "email", "phone", "tags", "tags.[2]"]);
+
+        let ctx3 = Context::wraps(&"hello".to_owned());
+        assert_eq!(ctx1.diff(&ctx3), vec!["this"]);
+    }
+
+    #[test]
+    fn test_navigate_glob() {
+        let ctx = Context::wraps(&btreemap! {
+            "items".to_string() => to_json(&vec![
+                btreemap! {"name".to_string() => "a".to_string()},
+                btreemap! {"id".to_string() => "2".to_string()},
+                btreemap! {"name".to_string() => "c".to_string()}
+            ]),
+            "users".to_string() => to_json(&btreemap! {
+                "u2".to_string() => btreemap! {"name".to_string() => "Bob".to_string()},
+                "u1".to_string() => btreemap! {"name".to_string() => "Alice".to_string()}
+            })
+        });
+
+        let names: Vec<String> =
+            ctx.navigate_glob("items.*.name").iter().map(|v| v.render()).collect();
+        assert_eq!(names, vec!["a", "c"]);
+
+        let names: Vec<String> =
+            ctx.navigate_glob("users.*.name").iter().map(|v| v.render()).collect();
+        assert_eq!(names, vec!["Alice", "Bob"]);
+
+        assert_eq!(ctx.navigate_glob("items.*").len(), 3);
+        assert_eq!(ctx.navigate_glob("items.[2].name")[0].render(), "c");
+        assert!(ctx.navigate_glob("users.*.age").is_empty());
+    }
 }
 
 #[cfg(test)]
 #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
 mod test {
-    use context::{JsonRender, Context};
+    use context::{self, JsonRender, Context, PathSeg, to_json};
     use std::collections::{VecDeque, BTreeMap};
+    use std::rc::Rc;
     use serialize::json::{Json, ToJson};
 
     #[test]
@@ -434,6 +908,15 @@ mod test {
         let thing = Json::String(raw.to_string());
 
         assert_eq!(raw, thing.render());
+
+        let list = to_json(&vec![to_json(&1), to_json(&"a".to_owned()), to_json(&vec![2, 3])]);
+        assert_eq!(list.render(), "[1, a, [2, 3]]");
+        assert_eq!(to_json(&Vec::<String>::new()).render(), "[]");
+        assert_eq!(list.render_json(), "[1,\"a\",[2,3]]");
+
+        let obj = to_json(&btreemap! {"a".to_string() => vec![1, 2]});
+        assert_eq!(obj.render(), "[object]");
+        assert_eq!(obj.render_json(), "{\"a\":[1,2]}");
     }
 
     struct Address {
@@ -544,6 +1027,87 @@ mod test {
                    "4".to_owned());
     }
 
+    #[test]
+    fn test_navigate_opt() {
+        let ctx = Context::wraps(&btreemap! {
+            "a".to_string() => to_json(&None::<String>),
+            "list".to_string() => to_json(&vec![1]),
+            "obj".to_string() => to_json(&btreemap! {"b".to_string() => 1})
+        });
+        let empty = VecDeque::new();
+
+        assert!(ctx.navigate_opt(".", &empty, "a").unwrap().is_null());
+        assert!(ctx.navigate_opt(".", &empty, "b").is_none());
+        assert_eq!(ctx.navigate_opt(".", &empty, "list.[0]").unwrap().render(), "1");
+        assert!(ctx.navigate_opt(".", &empty, "list.[1]").is_none());
+        assert_eq!(ctx.navigate_opt(".", &empty, "list.[-1]").unwrap().render(), "1");
+        assert!(ctx.navigate_opt(".", &empty, "list.[-2]").is_none());
+        assert!(ctx.navigate_opt(".", &empty, "list.a").is_none());
+        assert_eq!(ctx.navigate_opt("obj", &empty, "b").unwrap().render(), "1");
+        assert!(ctx.navigate_opt(".", &empty, "obj.b.c").is_none());
+        assert!(ctx.navigate(".", &empty, "obj.b.c").is_null());
+    }
+
+    #[test]
+    fn test_parse_path_cache() {
+        let parsed = context::parse_path("list.[0].name");
+        assert!(Rc::ptr_eq(&parsed, &context::parse_path("list.[0].name")));
+        assert_eq!(parsed.segs,
+                   vec![PathSeg::Id("list".to_owned()),
+                        PathSeg::RawId("0".to_owned()),
+                        PathSeg::Id("name".to_owned())]);
+        assert!(!context::parse_path("a..").valid);
+
+        // cached paths navigate like fresh ones
+        let ctx = Context::wraps(&btreemap! {
+            "obj".to_string() => to_json(&btreemap! {"b".to_string() => 1}),
+            "b".to_string() => to_json(&2)
+        });
+        let mut path_context = VecDeque::new();
+        path_context.push_front(".".to_owned());
+        for _ in 0..2 {
+            assert_eq!(ctx.navigate("obj", &path_context, "b").render(), "1");
+            assert_eq!(ctx.navigate("obj", &path_context, "../b").render(), "2");
+        }
+    }
+
+    #[test]
+    fn test_long_path() {
+        let mut map = BTreeMap::new();
+        map.insert("age".to_string(), 4usize.to_json());
+        let ctx = Context::wraps(&map);
+
+        let up_path = format!("{}age", "../".repeat(10));
+        assert_eq!(ctx.navigate(".", &VecDeque::new(), &up_path).render(),
+                   "4".to_owned());
+
+        let long_up_path = format!("{}age", "../".repeat(1000));
+        assert!(ctx.navigate_opt(".", &VecDeque::new(), &long_up_path).is_none());
+        assert!(ctx.navigate(".", &VecDeque::new(), &long_up_path).is_null());
+
+        let long_path = vec!["age"; 1000].join(".");
+        assert!(ctx.navigate_opt(".", &VecDeque::new(), &long_path).is_none());
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let mut map = BTreeMap::new();
+        map.insert("age".to_string(), 4usize.to_json());
+        let data = Json::Object(map);
+
+        let mut ctx = Context::borrowed(&data);
+        assert_eq!(ctx.navigate(".", &VecDeque::new(), "age").render(),
+                   "4".to_owned());
+
+        if let Some(m) = ctx.data_mut().as_object_mut() {
+            m.insert("age".to_string(), 5usize.to_json());
+        }
+        assert_eq!(ctx.navigate(".", &VecDeque::new(), "age").render(),
+                   "5".to_owned());
+        assert_eq!(Context::borrowed(&data).navigate(".", &VecDeque::new(), "age").render(),
+                   "4".to_owned());
+    }
+
     #[test]
     fn test_extend() {
         let mut map = BTreeMap::new();
@@ -568,4 +1132,62 @@ mod test {
         assert_eq!(ctx_a2.navigate(".", &VecDeque::new(), "tag").render(),
                    "h1".to_owned());
     }
+
+    #[test]
+    fn test_diff() {
+        let ctx1 = Context::wraps(&btreemap! {
+            "name".to_string() => to_json(&"Ning".to_owned()),
+            "age".to_string() => to_json(&27),
+            "phone".to_string() => to_json(&"1234".to_owned()),
+            "address".to_string() => to_json(&btreemap! {
+                "city".to_string() => "Beijing".to_string(),
+                "country".to_string() => "China".to_string()
+            }),
+            "tags".to_string() => to_json(&vec!["a".to_string(), "b".to_string()])
+        });
+        let ctx2 = Context::wraps(&btreemap! {
+            "name".to_string() => to_json(&"Ning".to_owned()),
+            "age".to_string() => to_json(&28),
+            "email".to_string() => to_json(&"ning@example.com".to_owned()),
+            "address".to_string() => to_json(&btreemap! {
+                "city".to_string() => "Shanghai".to_string(),
+                "country".to_string() => "China".to_string()
+            }),
+            "tags".to_string() => to_json(&vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        });
+
+        assert!(ctx1.diff(&ctx1).is_empty());
+        assert_eq!(ctx1.diff(&ctx2),
+                   vec!["address", "address.city", "age", "email", "phone", "tags", "tags.[2]"]);
+
+        let ctx3 = Context::wraps(&"hello".to_owned());
+        assert_eq!(ctx1.diff(&ctx3), vec!["this"]);
+    }
+
+    #[test]
+    fn test_navigate_glob() {
+        let ctx = Context::wraps(&btreemap! {
+            "items".to_string() => to_json(&vec![
+                btreemap! {"name".to_string() => "a".to_string()},
+                btreemap! {"id".to_string() => "2".to_string()},
+                btreemap! {"name".to_string() => "c".to_string()}
+            ]),
+            "users".to_string() => to_json(&btreemap! {
+                "u2".to_string() => btreemap! {"name".to_string() => "Bob".to_string()},
+                "u1".to_string() => btreemap! {"name".to_string() => "Alice".to_string()}
+            })
+        });
+
+        let names: Vec<String> =
+            ctx.navigate_glob("items.*.name").iter().map(|v| v.render()).collect();
+        assert_eq!(names, vec!["a", "c"]);
+
+        let names: Vec<String> =
+            ctx.navigate_glob("users.*.name").iter().map(|v| v.render()).collect();
+        assert_eq!(names, vec!["Alice", "Bob"]);
+
+        assert_eq!(ctx.navigate_glob("items.*").len(), 3);
+        assert_eq!(ctx.navigate_glob("items.[2].name")[0].render(), "c");
+        assert!(ctx.navigate_glob("users.*.age").is_empty());
+    }
 }
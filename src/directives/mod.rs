@@ -2,6 +2,7 @@ use render::{RenderContext, RenderError, Directive};
 use registry::Registry;
 
 pub use self::inline::INLINE_DIRECTIVE;
+pub use self::partial::PARTIAL_DIRECTIVE;
 
 /// Decorator Definition
 ///
@@ -62,6 +63,7 @@ impl<F: Send + Sync + for<'b, 'c, 'd, 'e> Fn(&'b Directive, &'c Registry, &'d mu
 }
 
 mod inline;
+mod partial;
 
 #[cfg(test)]
 mod test {
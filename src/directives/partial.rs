@@ -0,0 +1,88 @@
+use directives::DirectiveDef;
+use registry::Registry;
+use context::as_string;
+use render::{RenderError, RenderContext, Directive};
+use template::Template;
+
+/// Defines a partial from a template string in render data, like
+/// `{{*partial "snippet" page.snippet}}`
+///
+/// The partial is available from the position of the decorator to the end of
+/// the template, like one defined with `{{#*inline}}`. A partial including
+/// itself is an error.
+///
+/// Be careful with user supplied snippets: they are rendered with the same
+/// data, helpers and partials as the template defining them, so a snippet can
+/// output any value in the render data.
+#[derive(Clone, Copy)]
+pub struct PartialDirective;
+
+fn get_string_param<'a>(d: &'a Directive, idx: usize) -> Result<&'a str, RenderError> {
+    let param = try!(d.param(idx)
+                         .ok_or_else(|| RenderError::new("Param required for directive \"partial\"")));
+    as_string(param.value()).ok_or_else(|| {
+        RenderError::new(format!("Param {} of directive \"partial\" must be string", idx))
+    })
+}
+
+impl DirectiveDef for PartialDirective {
+    fn call(&self, d: &Directive, _: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let name = try!(get_string_param(d, 0));
+        let source = try!(get_string_param(d, 1));
+
+        // named after the partial, so including itself is detected like other partials
+        let template = try!(Template::compile_with_name(source, name.to_owned(), false)
+                                .map_err(|e| {
+            RenderError::new(format!("Invalid template for partial {:?}: {}", name, e))
+        }));
+
+        rc.set_partial(name.to_owned(), template);
+        Ok(())
+    }
+}
+
+pub static PARTIAL_DIRECTIVE: PartialDirective = PartialDirective;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+
+    #[test]
+    #[cfg(not(feature = "partial_legacy"))]
+    fn test_partial_from_data() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{*partial \"snippet\" source}}<p>{{> snippet}}</p>").is_ok());
+
+        let data = btreemap! {
+            "source".to_string() => "Hello {{name}}".to_string(),
+            "name".to_string() => "<b>Ning</b>".to_string()
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap(),
+                   "<p>Hello &lt;b&gt;Ning&lt;/b&gt;</p>");
+    }
+
+    #[test]
+    #[cfg(not(feature = "partial_legacy"))]
+    fn test_partial_from_data_errors() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{*partial \"snippet\" source}}{{> snippet}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{*partial \"snippet\"}}").is_ok());
+
+        let invalid = btreemap! {"source".to_string() => "{{#if}}".to_string()};
+        assert!(handlebars.render("t0", &invalid)
+                    .unwrap_err()
+                    .desc
+                    .starts_with("Invalid template for partial \"snippet\""));
+
+        let recursive = btreemap! {"source".to_string() => "again {{> snippet}}".to_string()};
+        assert_eq!(handlebars.render("t0", &recursive).unwrap_err().desc,
+                   "Cannot include self in >");
+
+        assert_eq!(handlebars.render("t0", &btreemap! {"source".to_string() => 1})
+                       .unwrap_err()
+                       .desc,
+                   "Param 1 of directive \"partial\" must be string");
+        assert_eq!(handlebars.render("t1", &invalid).unwrap_err().desc,
+                   "Param required for directive \"partial\"");
+    }
+}
@@ -101,6 +101,16 @@ quick_error! {
             description(err.description())
             display("Template \"{}\": {}", name, err)
         }
+        TemplateErrorInFile(err: TemplateError, path: String) {
+            cause(err)
+            description(err.description())
+            display("Template file {:?}: {}", path, err)
+        }
+        DirectoryIOError(err: IOError, path: String) {
+            cause(err)
+            description(err.description())
+            display("Template directory {:?}: {}", path, err)
+        }
     }
 }
 
@@ -0,0 +1,112 @@
+use regex::{Regex, Captures};
+
+lazy_static!{
+    static ref DEFAULT_REPLACE: Regex = Regex::new(">|<|\"|&").unwrap();
+}
+
+/// The default *escape fn* replaces the characters `&"<>`
+/// with the equivalent html / xml entities.
+pub fn html_escape(data: &str) -> String {
+    DEFAULT_REPLACE.replace_all(data, |cap: &Captures| {
+            match cap.get(0).map(|m| m.as_str()) {
+                    Some("<") => "&lt;",
+                    Some(">") => "&gt;",
+                    Some("\"") => "&quot;",
+                    Some("&") => "&amp;",
+                    _ => unreachable!(),
+                }
+                .to_owned()
+        })
+        .into_owned()
+}
+
+/// `EscapeFn` that donot change any thing. Useful when using in a non-html
+/// environment.
+pub fn no_escape(data: &str) -> String {
+    data.to_owned()
+}
+
+/// `EscapeFn` for CSV fields, as specified in RFC 4180.
+///
+/// A field containing commas, double quotes or line breaks is enclosed in
+/// double quotes, and its double quotes are doubled. Other fields are not
+/// changed.
+pub fn csv_escape(data: &str) -> String {
+    if data.contains(&[',', '"', '\r', '\n'][..]) {
+        format!("\"{}\"", data.replace('"', "\"\""))
+    } else {
+        data.to_owned()
+    }
+}
+
+/// `EscapeFn` for the content of JSON or JavaScript strings.
+///
+/// Quotes, backslashes and control characters are escaped, as well as line
+/// separators U+2028 and U+2029 which are not allowed in JavaScript strings.
+/// `<`, `>` and `&` are escaped like `\u003c`, so a value can't close a
+/// `<script>` element it's rendered in. The surrounding double quotes are not
+/// added, so use it like `"{{name}}"`.
+pub fn json_escape(data: &str) -> String {
+    let mut buf = String::with_capacity(data.len());
+    for c in data.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            '\u{8}' => buf.push_str("\\b"),
+            '\u{c}' => buf.push_str("\\f"),
+            '<' | '>' | '&' | '\u{2028}' | '\u{2029}' => {
+                buf.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c if c < ' ' => buf.push_str(&format!("\\u{:04x}", c as u32)),
+            c => buf.push(c),
+        }
+    }
+    buf
+}
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use escape::{csv_escape, json_escape};
+
+    #[test]
+    fn test_csv_escape() {
+        let mut r = Registry::new();
+        r.register_escape_fn(csv_escape);
+        r.register_template_string("row", "{{name}},{{note}}\r\n").unwrap();
+
+        let row = |name: &str, note: &str| {
+            btreemap! {
+                "name".to_string() => name.to_string(),
+                "note".to_string() => note.to_string()
+            }
+        };
+
+        assert_eq!(r.render("row", &row("Ning", "plain")).unwrap(), "Ning,plain\r\n");
+        assert_eq!(r.render("row", &row("Sun, Ning", "a <b>")).unwrap(),
+                   "\"Sun, Ning\",a <b>\r\n");
+        assert_eq!(r.render("row", &row("Ning", "say \"hi\"")).unwrap(),
+                   "Ning,\"say \"\"hi\"\"\"\r\n");
+        assert_eq!(r.render("row", &row("Ning", "line1\nline2")).unwrap(),
+                   "Ning,\"line1\nline2\"\r\n");
+    }
+
+    #[test]
+    fn test_json_escape() {
+        let mut r = Registry::new();
+        r.register_escape_fn(json_escape);
+        r.register_template_string("t0", "{\"name\": \"{{name}}\"}").unwrap();
+
+        let data = |name: &str| btreemap! {"name".to_string() => name.to_string()};
+
+        assert_eq!(r.render("t0", &data("<Ning & Sun>")).unwrap(),
+                   "{\"name\": \"\\u003cNing \\u0026 Sun\\u003e\"}");
+        assert_eq!(json_escape("</script>"), "\\u003c/script\\u003e");
+        assert_eq!(r.render("t0", &data("say \"hi\"\\")).unwrap(),
+                   "{\"name\": \"say \\\"hi\\\"\\\\\"}");
+        assert_eq!(json_escape("a\nb\tc\u{1}\u{2028}"), "a\\nb\\tc\\u0001\\u2028");
+    }
+}
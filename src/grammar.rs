@@ -30,12 +30,12 @@ impl_rdp! {
         path_char = _{ ["/"] }
 
         identifier = @{ symbol_char ~ ( symbol_char | path_char )* }
-        reference = @{ identifier ~ (["["] ~ (string_literal|['0'..'9']+) ~ ["]"])* ~ ["-"]* ~ reference* }
+        reference = @{ identifier ~ (["["] ~ (string_literal|(["-"]? ~ ['0'..'9']+)) ~ ["]"])* ~ ["-"]* ~ reference* }
         name = _{ subexpression | reference }
 
         param = { !["as"] ~ (literal | reference | subexpression) }
         hash = { identifier ~ ["="] ~ param }
-        block_param = { ["as"] ~ ["|"] ~ identifier ~ identifier? ~ ["|"]}
+        block_param = { ["as"] ~ ["|"] ~ identifier ~ identifier* ~ ["|"]}
         exp_line = _{ identifier ~ (hash|param)* ~ block_param?}
         partial_exp_line = _{ name ~ (hash|param)* }
 
@@ -90,7 +90,7 @@ impl_rdp! {
                            pro_whitespace_omitter? ~ ["}}}}"] }
         raw_block = _{ raw_block_start ~ raw_block_text ~ raw_block_end }
 
-        hbs_comment = { !escape ~ ["{{!"] ~ (!["}}"] ~ any)* ~ ["}}"] }
+        hbs_comment = { !escape ~ (["{{!"] | ["{{~!"]) ~ (!["}}"] ~ any)* ~ ["}}"] }
 
         template = { (
             raw_text |
@@ -110,7 +110,7 @@ impl_rdp! {
 // json path visitor
         path_ident = _{ ['a'..'z']|['A'..'Z']|['0'..'9']|["_"]|["@"]|["$"]|["<"]|[">"]|["-"]}
         path_id = { path_ident+ }
-        path_num_id = { ['0'..'9']+ }
+        path_num_id = { ["-"]? ~ ['0'..'9']+ }
         path_raw_id = { path_ident* }
         path_sep = _{ ["/"] | ["."] }
         path_up = { [".."] }
@@ -152,12 +152,12 @@ impl_rdp! {
         path_char = _{ ["/"] }
 
         identifier = @{ symbol_char ~ ( symbol_char | path_char )* }
-        reference = @{ identifier ~ (["["] ~ (string_literal|['0'..'9']+) ~ ["]"])* ~ ["-"]* ~ reference* }
+        reference = @{ identifier ~ (["["] ~ (string_literal|(["-"]? ~ ['0'..'9']+)) ~ ["]"])* ~ ["-"]* ~ reference* }
         name = _{ subexpression | reference }
 
         param = { !["as"] ~ (literal | reference | subexpression) }
         hash = { identifier ~ ["="] ~ param }
-        block_param = { ["as"] ~ ["|"] ~ identifier ~ identifier? ~ ["|"]}
+        block_param = { ["as"] ~ ["|"] ~ identifier ~ identifier* ~ ["|"]}
         exp_line = _{ identifier ~ (hash|param)* ~ block_param?}
         partial_exp_line = _{ name ~ (hash|param)* }
 
@@ -210,7 +210,7 @@ impl_rdp! {
                            pro_whitespace_omitter? ~ ["}}}}"] }
         raw_block = _{ raw_block_start ~ raw_block_text ~ raw_block_end }
 
-        hbs_comment = { !escape ~ ["{{!"] ~ (!["}}"] ~ any)* ~ ["}}"] }
+        hbs_comment = { !escape ~ (["{{!"] | ["{{~!"]) ~ (!["}}"] ~ any)* ~ ["}}"] }
 
         template = { (
             raw_text |
@@ -232,7 +232,7 @@ impl_rdp! {
 // json path visitor
         path_ident = _{ ['a'..'z']|['A'..'Z']|['0'..'9']|["_"]|["@"]|["$"]|["<"]|[">"]|["-"]}
         path_id = { path_ident+ }
-        path_num_id = { ['0'..'9']+ }
+        path_num_id = { ["-"]? ~ ['0'..'9']+ }
         path_raw_id = { path_ident* }
         path_sep = _{ ["/"] | ["."] }
         path_up = { [".."] }
@@ -275,7 +275,8 @@ fn test_reference() {
                  "aBc[\"abc\"]",
                  "abc[0][\"nice\"]",
                  "some-name",
-                 "this.[0].ok"];
+                 "this.[0].ok",
+                 "titles.[-1]"];
     for i in s.iter() {
         let mut rdp = Rdp::new(StringInput::new(i));
         assert!(rdp.reference());
@@ -332,7 +333,7 @@ fn test_json_literal() {
 
 #[test]
 fn test_comment() {
-    let s = vec!["{{! hello }}"];
+    let s = vec!["{{! hello }}", "{{~! hello ~}}"];
     for i in s.iter() {
         let mut rdp = Rdp::new(StringInput::new(i));
         assert!(rdp.hbs_comment());
@@ -463,7 +464,7 @@ fn test_raw_block() {
 
 #[test]
 fn test_block_param() {
-    let s = vec!["as |person|", "as |key val|"];
+    let s = vec!["as |person|", "as |key val|", "as |first middle last|"];
     for i in s.iter() {
         let mut rdp = Rdp::new(StringInput::new(i));
         assert!(rdp.block_param());
@@ -482,7 +483,8 @@ fn test_path() {
                  "a[\"bbc\"]/b/c/../d",
                  "../a/b[0][1]",
                  "./this[0][1]/this/../a",
-                 "./this_name"];
+                 "./this_name",
+                 "a[-1].b"];
     for i in s.iter() {
         let mut rdp = Rdp::new(StringInput::new(i));
         assert!(rdp.path());
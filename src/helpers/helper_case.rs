@@ -0,0 +1,121 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::HelperDef;
+use registry::Registry;
+use context::{JsonRender, to_json};
+use render::{RenderContext, RenderError, Helper};
+
+/// Changes the case of a string, like `{{upper name}}`
+///
+/// Values other than strings are rendered first, so `{{upper true}}` renders
+/// `TRUE`. Returns the converted string when used as subexpression, and
+/// escapes it when written to output.
+#[derive(Clone, Copy)]
+pub struct CaseHelper {
+    name: &'static str,
+    convert: fn(&str) -> String,
+}
+
+impl HelperDef for CaseHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let value = try!(h.param(0).ok_or_else(|| {
+            RenderError::new(format!("Param not found for helper {:?}", self.name))
+        }));
+
+        Ok(Some(to_json(&(self.convert)(&value.value().render()))))
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        if let Some(result) = try!(self.call_inner(h, r, rc)) {
+            let output = rc.escape(r, result.render());
+            try!(rc.writer.write(output.into_bytes().as_ref()));
+        }
+        Ok(())
+    }
+}
+
+fn upper(s: &str) -> String {
+    s.to_uppercase()
+}
+
+fn lower(s: &str) -> String {
+    s.to_lowercase()
+}
+
+/// Uppercase the first character and keep the rest unchanged
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub static UPPER_HELPER: CaseHelper = CaseHelper {
+    name: "upper",
+    convert: upper,
+};
+pub static LOWER_HELPER: CaseHelper = CaseHelper {
+    name: "lower",
+    convert: lower,
+};
+pub static CAPITALIZE_HELPER: CaseHelper = CaseHelper {
+    name: "capitalize",
+    convert: capitalize,
+};
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use context::to_json;
+
+    #[test]
+    fn test_case() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{upper name}}|{{lower name}}|{{capitalize name}}")
+                    .is_ok());
+
+        let data = btreemap! {"name".to_string() => "ärger <b>im</b> Büro".to_string()};
+        assert_eq!(handlebars.render("t0", &data).unwrap(),
+                   "ÄRGER &lt;B&gt;IM&lt;/B&gt; BÜRO|ärger &lt;b&gt;im&lt;/b&gt; büro|\
+                    Ärger &lt;b&gt;im&lt;/b&gt; Büro");
+
+        let data = btreemap! {"name".to_string() => "ß".to_string()};
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "SS|ß|SS");
+    }
+
+    #[test]
+    fn test_case_non_string() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{upper flag}}|{{capitalize missing}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1", "{{upper fallback=true}}").is_ok());
+
+        let data = btreemap! {"flag".to_string() => to_json(&true)};
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "TRUE|");
+        assert_eq!(handlebars.render("t1", &data).unwrap_err().desc,
+                   "Param not found for helper \"upper\"");
+    }
+
+    #[test]
+    fn test_case_subexpression() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{upper (default name \"guest\")}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#if (eq (lower role) \"admin\")}}yes{{/if}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &()).unwrap(), "GUEST");
+        let data = btreemap! {"role".to_string() => "Admin".to_string()};
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "yes");
+    }
+}
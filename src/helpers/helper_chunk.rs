@@ -0,0 +1,69 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::{HelperDef, param_type_error};
+use registry::Registry;
+use render::{RenderContext, RenderError, Helper};
+
+#[derive(Clone, Copy)]
+pub struct ChunkHelper;
+
+impl HelperDef for ChunkHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let list = try!(h.param(0)
+            .ok_or_else(|| RenderError::new("Param not found for helper \"chunk\""))
+            .and_then(|v| {
+                v.value()
+                    .as_array()
+                    .ok_or_else(|| param_type_error("chunk", 0, "an array", v.value()))
+            }));
+        let size = try!(h.param(1)
+            .ok_or_else(|| RenderError::new("Param not found for helper \"chunk\""))
+            .and_then(|v| {
+                v.value()
+                    .as_u64()
+                    .and_then(|n| if n > 0 { Some(n as usize) } else { None })
+                    .ok_or_else(|| param_type_error("chunk", 1, "a positive integer", v.value()))
+            }));
+
+        let chunks = list.chunks(size).map(|c| Json::Array(c.to_vec())).collect();
+        Ok(Some(Json::Array(chunks)))
+    }
+}
+
+pub static CHUNK_HELPER: ChunkHelper = ChunkHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+
+    #[test]
+    fn test_chunk() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#each (chunk this 2) as |row|}}[{{#each row as |x|}}{{x}}{{/each}}]{{/each}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#each (chunk this 4) as |row|}}[{{#each row as |x|}}{{x}}{{/each}}]{{/each}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2",
+                                                    "{{#each (chunk this 10) as |row|}}[{{#each row as |x|}}{{x}}{{/each}}]{{/each}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t3", "{{chunk this 0}}").is_ok());
+
+        let data = vec![1, 2, 3, 4, 5, 6];
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "[12][34][56]");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "[1234][56]");
+        assert_eq!(handlebars.render("t2", &data).unwrap(), "[123456]");
+        assert!(handlebars.render("t3", &data).is_err());
+
+        let empty: Vec<u32> = Vec::new();
+        assert_eq!(handlebars.render("t0", &empty).unwrap(), "");
+    }
+}
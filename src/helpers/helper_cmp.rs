@@ -0,0 +1,162 @@
+use std::cmp::Ordering;
+
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::HelperDef;
+use registry::Registry;
+use context::{JsonTruthy, as_string, to_json, value_type};
+use render::{RenderContext, RenderError, Helper};
+
+/// Compares two numbers or two strings, like `{{#if (gt a b)}}`
+///
+/// Numbers are compared by value and strings lexicographically. Comparing
+/// values of other or mismatched types is an error. The result is a boolean
+/// when used as subexpression.
+#[derive(Clone, Copy)]
+pub struct CompareHelper {
+    name: &'static str,
+    accept: fn(Ordering) -> bool,
+}
+
+fn compare(name: &str, a: &Json, b: &Json) -> Result<Ordering, RenderError> {
+    let ordering = if a.is_number() && b.is_number() {
+        a.as_f64().and_then(|x| b.as_f64().and_then(|y| x.partial_cmp(&y)))
+    } else {
+        match (as_string(a), as_string(b)) {
+            (Some(x), Some(y)) => Some(x.cmp(y)),
+            _ => None,
+        }
+    };
+
+    ordering.ok_or_else(|| {
+        RenderError::new(format!("Helper {:?} can't compare {} with {}",
+                                 name,
+                                 value_type(a),
+                                 value_type(b)))
+    })
+}
+
+impl HelperDef for CompareHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let (a, b) = match (h.param(0), h.param(1)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => {
+                return Err(RenderError::new(format!("Insufficient params for helper {:?}",
+                                                    self.name)))
+            }
+        };
+
+        let ordering = try!(compare(self.name, a.value(), b.value()));
+        Ok(Some(to_json(&(self.accept)(ordering))))
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        // renders nothing rather than `false`, like `eq`
+        let matched = try!(self.call_inner(h, r, rc)).map(|v| v.is_truthy()).unwrap_or(false);
+        if matched {
+            try!(rc.writer.write("true".as_bytes()));
+        }
+        Ok(())
+    }
+}
+
+fn greater(o: Ordering) -> bool {
+    o == Ordering::Greater
+}
+
+fn less(o: Ordering) -> bool {
+    o == Ordering::Less
+}
+
+fn greater_or_equal(o: Ordering) -> bool {
+    o != Ordering::Less
+}
+
+fn less_or_equal(o: Ordering) -> bool {
+    o != Ordering::Greater
+}
+
+pub static GT_HELPER: CompareHelper = CompareHelper {
+    name: "gt",
+    accept: greater,
+};
+pub static LT_HELPER: CompareHelper = CompareHelper {
+    name: "lt",
+    accept: less,
+};
+pub static GTE_HELPER: CompareHelper = CompareHelper {
+    name: "gte",
+    accept: greater_or_equal,
+};
+pub static LTE_HELPER: CompareHelper = CompareHelper {
+    name: "lte",
+    accept: less_or_equal,
+};
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use context::to_json;
+
+    #[test]
+    fn test_compare_numbers() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{gt a b}}|{{lt a b}}|{{gte a b}}|{{lte a b}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#if (gt a 1.5)}}yes{{else}}no{{/if}}")
+                    .is_ok());
+
+        let data = btreemap! {"a".to_string() => to_json(&2), "b".to_string() => to_json(&10)};
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "|true||true");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "yes");
+
+        let data = btreemap! {"a".to_string() => to_json(&2), "b".to_string() => to_json(&2.0)};
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "||true|true");
+
+        let data = btreemap! {"a".to_string() => to_json(&-1), "b".to_string() => to_json(&0)};
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "no");
+    }
+
+    #[test]
+    fn test_compare_strings() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{gt a b}}|{{lt a b}}|{{gte a b}}|{{lte a b}}")
+                    .is_ok());
+
+        let data = btreemap! {
+            "a".to_string() => "b".to_string(),
+            "b".to_string() => "ab".to_string()
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "true||true|");
+    }
+
+    #[test]
+    fn test_compare_invalid() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{gt a b}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{lte a}}").is_ok());
+
+        let data = btreemap! {
+            "a".to_string() => to_json(&1),
+            "b".to_string() => to_json(&"1".to_owned())
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap_err().desc,
+                   "Helper \"gt\" can't compare number with string");
+        assert_eq!(handlebars.render("t0", &btreemap! {"a".to_string() => to_json(&true)})
+                       .unwrap_err()
+                       .desc,
+                   "Helper \"gt\" can't compare boolean with null");
+        assert_eq!(handlebars.render("t1", &data).unwrap_err().desc,
+                   "Insufficient params for helper \"lte\"");
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::{HelperDef, param_type_error};
+use helpers::helper_eq::json_eq;
+use registry::Registry;
+use context::{JsonRender, JsonTruthy, as_string, to_json};
+use render::{RenderContext, RenderError, Helper};
+
+/// Checks if an array, object or string contains a value, like
+/// `{{#if (contains roles "admin")}}`
+///
+/// An array contains values equal to one of its items, compared like `eq`,
+/// an object contains its keys and a string contains its substrings. A
+/// missing value contains nothing. The result is a boolean when used as
+/// subexpression.
+#[derive(Clone, Copy)]
+pub struct ContainsHelper;
+
+impl HelperDef for ContainsHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let (haystack, needle) = match (h.param(0), h.param(1)) {
+            (Some(haystack), Some(needle)) => (haystack.value(), needle.value()),
+            _ => return Err(RenderError::new("Insufficient params for helper \"contains\"")),
+        };
+
+        let found = if let Some(list) = haystack.as_array() {
+            list.iter().any(|item| json_eq(item, needle, false))
+        } else if let Some(map) = haystack.as_object() {
+            map.contains_key(&needle.render())
+        } else if let Some(s) = as_string(haystack) {
+            s.contains(needle.render().as_str())
+        } else if haystack.is_null() {
+            false
+        } else {
+            return Err(param_type_error("contains", 0, "an array, object or string", haystack));
+        };
+        Ok(Some(to_json(&found)))
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        // renders nothing rather than `false`, like `eq`
+        let found = try!(self.call_inner(h, r, rc)).map(|v| v.is_truthy()).unwrap_or(false);
+        if found {
+            try!(rc.writer.write("true".as_bytes()));
+        }
+        Ok(())
+    }
+}
+
+pub static CONTAINS_HELPER: ContainsHelper = ContainsHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use context::to_json;
+
+    #[test]
+    fn test_contains() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#if (contains this \"admin\")}}yes\
+                                                     {{else}}no{{/if}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1", "{{contains this 2}}").is_ok());
+
+        let roles = vec!["user".to_owned(), "admin".to_owned()];
+        assert_eq!(handlebars.render("t0", &roles).unwrap(), "yes");
+        assert_eq!(handlebars.render("t0", &vec!["user".to_owned()]).unwrap(), "no");
+        assert_eq!(handlebars.render("t1", &vec![1.0, 2.0]).unwrap(), "true");
+        assert_eq!(handlebars.render("t1", &vec!["2".to_owned()]).unwrap(), "");
+
+        let perms = btreemap! {"admin".to_string() => false};
+        assert_eq!(handlebars.render("t0", &perms).unwrap(), "yes");
+
+        assert_eq!(handlebars.render("t0", &"superadmin".to_owned()).unwrap(), "yes");
+        assert_eq!(handlebars.render("t0", &"guest".to_owned()).unwrap(), "no");
+        assert_eq!(handlebars.render("t0", &()).unwrap(), "no");
+    }
+
+    #[test]
+    fn test_contains_invalid() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{contains this 1}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{contains this}}").is_ok());
+
+        assert_eq!(handlebars.render("t0", &to_json(&1)).unwrap_err().desc,
+                   "Helper \"contains\" expected an array, object or string for param 0 but got \
+                    number");
+        assert_eq!(handlebars.render("t1", &vec![1]).unwrap_err().desc,
+                   "Insufficient params for helper \"contains\"");
+    }
+}
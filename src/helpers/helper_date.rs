@@ -0,0 +1,151 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+use helpers::{HelperDef, param_type_error};
+use registry::Registry;
+use context::{JsonRender, as_string};
+use render::{RenderContext, RenderError, Helper};
+
+/// Formats a date, like `{{date createdAt format="%Y-%m-%d"}}`
+///
+/// The param is a unix timestamp in seconds or an RFC3339 string. The format
+/// is a strftime pattern and defaults to RFC3339. Dates are shown in UTC, use
+/// `tz` for another offset like `tz="+08:00"`. Only available with the `dates`
+/// feature.
+#[derive(Clone, Copy)]
+pub struct DateHelper;
+
+/// Parse a time zone of `UTC`, `Z` or an offset like `+08:00` or `-0530`
+fn parse_offset(tz: &str) -> Option<FixedOffset> {
+    if tz == "UTC" || tz == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+
+    let sign = match tz.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return None,
+    };
+    let digits = tz[1..].replace(":", "");
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().unwrap();
+    let minutes: i32 = digits[2..].parse().unwrap();
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+impl HelperDef for DateHelper {
+    fn call(&self, h: &Helper, _: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let value = try!(h.param_checked(0, "date")).value();
+
+        let offset = match h.hash_get("tz") {
+            Some(tz) => {
+                let tz = tz.value().render();
+                try!(parse_offset(&tz).ok_or_else(|| {
+                    RenderError::new(format!("Invalid time zone {:?} for helper \"date\", \
+                                              expected UTC or an offset like +08:00",
+                                             tz))
+                }))
+            }
+            None => FixedOffset::east_opt(0).unwrap(),
+        };
+
+        let date: DateTime<Utc> = if let Some(s) = as_string(value) {
+            try!(DateTime::parse_from_rfc3339(s)
+                     .map(|d| d.with_timezone(&Utc))
+                     .map_err(|_| {
+                RenderError::new(format!("Invalid date {:?} for helper \"date\", expected an \
+                                          RFC3339 string like 2017-07-14T02:40:00Z",
+                                         s))
+            }))
+        } else if value.is_number() {
+            let seconds = value.as_f64().unwrap();
+            let date = if seconds.is_finite() && seconds.abs() < i64::max_value() as f64 {
+                let nanos = ((seconds - seconds.floor()) * 1e9) as u32;
+                Utc.timestamp_opt(seconds.floor() as i64, nanos).single()
+            } else {
+                None
+            };
+            try!(date.ok_or_else(|| {
+                RenderError::new(format!("Timestamp {} is out of range for helper \"date\"",
+                                         value.render()))
+            }))
+        } else {
+            return Err(param_type_error("date", 0, "a number or string", value));
+        };
+        let date = date.with_timezone(&offset);
+
+        let output = match h.hash_get("format") {
+            Some(format) => {
+                let format = format.value().render();
+                let mut output = String::new();
+                try!(write!(output, "{}", date.format(&format)).map_err(|_| {
+                    RenderError::new(format!("Invalid format {:?} for helper \"date\"", format))
+                }));
+                output
+            }
+            None => date.to_rfc3339(),
+        };
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }
+}
+
+pub static DATE_HELPER: DateHelper = DateHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use context::to_json;
+
+    #[test]
+    fn test_date() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{date this}}").is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{date this format=\"%Y-%m-%d %H:%M\"}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2",
+                                                    "{{date this format=\"%H:%M %z\" \
+                                                     tz=\"-05:30\"}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &1500000000).unwrap(),
+                   "2017-07-14T02:40:00+00:00");
+        assert_eq!(handlebars.render("t1", &1500000000).unwrap(), "2017-07-14 02:40");
+        assert_eq!(handlebars.render("t1", &-86400).unwrap(), "1969-12-31 00:00");
+        assert_eq!(handlebars.render("t2", &1500000000).unwrap(), "21:10 -0530");
+
+        let rfc3339 = "2017-07-14T10:40:00+08:00".to_owned();
+        assert_eq!(handlebars.render("t0", &rfc3339).unwrap(),
+                   "2017-07-14T02:40:00+00:00");
+        assert_eq!(handlebars.render("t2", &rfc3339).unwrap(), "21:10 -0530");
+    }
+
+    #[test]
+    fn test_date_invalid() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{date this}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{date this format=\"%Q\"}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{date this tz=\"Europe/Paris\"}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &"yesterday".to_owned()).unwrap_err().desc,
+                   "Invalid date \"yesterday\" for helper \"date\", expected an RFC3339 string \
+                    like 2017-07-14T02:40:00Z");
+        assert_eq!(handlebars.render("t0", &1e20).unwrap_err().desc,
+                   "Timestamp 100000000000000000000 is out of range for helper \"date\"");
+        assert_eq!(handlebars.render("t0", &to_json(&true)).unwrap_err().desc,
+                   "Helper \"date\" expected a number or string for param 0 but got boolean");
+        assert_eq!(handlebars.render("t1", &0).unwrap_err().desc,
+                   "Invalid format \"%Q\" for helper \"date\"");
+        assert_eq!(handlebars.render("t2", &0).unwrap_err().desc,
+                   "Invalid time zone \"Europe/Paris\" for helper \"date\", expected UTC or an \
+                    offset like +08:00");
+    }
+}
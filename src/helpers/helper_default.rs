@@ -0,0 +1,122 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::HelperDef;
+use registry::Registry;
+use context::JsonRender;
+use render::{RenderContext, RenderError, Helper};
+
+/// Returns the first truthy param, like `{{default user.nickname user.name "Anonymous"}}`
+///
+/// If no param is truthy, the last one is returned even if it's falsy or
+/// missing, so `{{default a b}}` renders nothing when neither `a` nor `b` is
+/// set. Truthiness follows `Registry::is_truthy`. The value is escaped like
+/// `{{name}}`.
+#[derive(Clone, Copy)]
+pub struct DefaultHelper;
+
+impl HelperDef for DefaultHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  r: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let last = try!(h.params()
+                            .last()
+                            .ok_or_else(|| {
+                                RenderError::new("Param not found for helper \"default\"")
+                            }));
+
+        let value = h.params().iter().find(|p| r.is_truthy(p.value())).unwrap_or(last);
+        Ok(Some(value.value().clone()))
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        if let Some(result) = try!(self.call_inner(h, r, rc)) {
+            let output = rc.escape(r, result.render());
+            try!(rc.writer.write(output.into_bytes().as_ref()));
+        }
+        Ok(())
+    }
+}
+
+pub static DEFAULT_HELPER: DefaultHelper = DefaultHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use render::{RenderContext, RenderError, Helper};
+
+    #[test]
+    fn test_default() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{default nickname name \"Anonymous\"}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1", "{{default nickname name}}").is_ok());
+
+        let full = btreemap! {
+            "nickname".to_string() => "ns".to_string(),
+            "name".to_string() => "Ning Sun".to_string()
+        };
+        let name_only = btreemap! {
+            "nickname".to_string() => "".to_string(),
+            "name".to_string() => "Ning Sun".to_string()
+        };
+        let none = btreemap! {
+            "nickname".to_string() => "".to_string()
+        };
+
+        assert_eq!(handlebars.render("t0", &full).unwrap(), "ns");
+        assert_eq!(handlebars.render("t0", &name_only).unwrap(), "Ning Sun");
+        assert_eq!(handlebars.render("t0", &none).unwrap(), "Anonymous");
+        assert_eq!(handlebars.render("t1", &none).unwrap(), "");
+    }
+
+    #[test]
+    fn test_default_escape() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{default nickname name}}").is_ok());
+
+        let data = btreemap! {
+            "name".to_string() => "<script>x</script>".to_string()
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap(),
+                   "&lt;script&gt;x&lt;/script&gt;");
+    }
+
+    #[test]
+    fn test_default_subexpression() {
+        let mut handlebars = Registry::new();
+        handlebars.register_helper("greet",
+                                   Box::new(|h: &Helper,
+                                             _: &Registry,
+                                             rc: &mut RenderContext|
+                                             -> Result<(), RenderError> {
+            let name = h.param_rendered(0).unwrap_or_default();
+            try!(rc.writer.write(format!("Hello, {}", name).into_bytes().as_ref()));
+            Ok(())
+        }));
+        assert!(handlebars.register_template_string("t0", "{{greet (default a b)}}").is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{json (default a b)}}")
+                    .is_ok());
+
+        let data = btreemap! {"b".to_string() => vec![1, 2]};
+        assert_eq!(handlebars.render("t0", &btreemap! {"b".to_string() => "x".to_owned()})
+                       .unwrap(),
+                   "Hello, x");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "[1,2]");
+    }
+
+    #[test]
+    fn test_default_no_params() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{default fallback=true}}").is_ok());
+
+        let err = handlebars.render("t0", &1).unwrap_err();
+        assert_eq!(err.desc, "Param not found for helper \"default\"");
+    }
+}
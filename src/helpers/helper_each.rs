@@ -1,18 +1,78 @@
-use std::collections::BTreeMap;
-
 #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
 use serialize::json::Json;
 #[cfg(feature = "serde_type")]
 use serde_json::value::Value as Json;
 
-use helpers::HelperDef;
+use helpers::{HelperDef, param_type_error};
 use registry::Registry;
-use context::{JsonTruthy, to_json};
+use context::{JsonRender, to_json};
 use render::{Renderable, RenderContext, RenderError, Helper};
 
+/// Iterates over an array or object
+///
+/// In each iteration `@first`, `@last` and `@index` are set for both arrays and
+/// objects, and `@key` for objects. Object keys are visited in sorted order. An
+/// empty array or object renders the `{{else}}` branch, if any, and sets none of
+/// these variables.
+///
+/// Items of a value without a path, like the result of a subexpression, are
+/// the base value of each iteration, so `{{this}}` and `{{name}}` refer to the
+/// item, while `{{../title}}` refers to the context around the block.
 #[derive(Clone, Copy)]
 pub struct EachHelper;
 
+/// Running total of a numeric field, exposed as a local variable in each iteration
+///
+/// Enabled by `{{#each items accumulate="price"}}`, the sum of `price` of items so far,
+/// including the current one, is available as `@running`, or the local variable named
+/// by `into`, like `into="subtotal"` for `@subtotal`. Non-numeric or missing fields
+/// count as zero.
+struct Accumulator {
+    field: String,
+    var_name: String,
+    sum: f64,
+    integral: bool,
+}
+
+impl Accumulator {
+    fn from_helper(h: &Helper) -> Option<Accumulator> {
+        h.hash_get("accumulate").map(|field| {
+            let var_name = h.hash_get("into")
+                .map(|v| v.value().render())
+                .map(|n| if n.starts_with('@') { n } else { format!("@{}", n) })
+                .unwrap_or_else(|| "@running".to_owned());
+            Accumulator {
+                field: field.value().render(),
+                var_name: var_name,
+                sum: 0.0,
+                integral: true,
+            }
+        })
+    }
+
+    fn add(&mut self, item: &Json) {
+        let mut value = Some(item);
+        if self.field != "this" {
+            for k in self.field.split('.') {
+                value = value.and_then(|v| v.as_object()).and_then(|m| m.get(k));
+            }
+        }
+
+        if let Some(n) = value.and_then(|v| v.as_f64()) {
+            self.sum += n;
+            self.integral = self.integral && value.and_then(|v| v.as_i64()).is_some();
+        }
+    }
+
+    fn value(&self) -> Json {
+        if self.integral {
+            to_json(&(self.sum as i64))
+        } else {
+            to_json(&self.sum)
+        }
+    }
+}
+
 impl HelperDef for EachHelper {
     fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
         let value =
@@ -23,22 +83,33 @@ impl HelperDef for EachHelper {
         match template {
             Some(t) => {
                 rc.promote_local_vars();
-                let local_path_root = value.path_root().map(|p| format!("{}/{}", rc.get_path(), p));
+                let local_path_root = match value.path_root() {
+                    Some(p) => format!("{}/{}", rc.get_path(), p),
+                    // `../` of a base value is the current context
+                    None => format!("{}/this", rc.get_path()),
+                };
+                let mut accumulator = Accumulator::from_helper(h);
 
                 debug!("each value {:?}", value.value());
-                let rendered = match (value.value().is_truthy(), value.value()) {
+                let rendered = match (r.is_truthy(value.value()), value.value()) {
                     (true, &Json::Array(ref list)) => {
                         let len = list.len();
                         for i in 0..len {
                             let mut local_rc = rc.derive();
-                            if let Some(ref p) = local_path_root {
-                                local_rc.push_local_path_root(p.clone());
+                            local_rc.push_local_path_root(local_path_root.clone());
+                            if value.path().is_none() {
+                                local_rc.set_base_value(&list[i]);
                             }
 
                             local_rc.set_local_var("@first".to_string(), to_json(&(i == 0usize)));
                             local_rc.set_local_var("@last".to_string(), to_json(&(i == len - 1)));
                             local_rc.set_local_var("@index".to_string(), to_json(&i));
 
+                            if let Some(ref mut acc) = accumulator {
+                                acc.add(&list[i]);
+                                local_rc.set_local_var(acc.var_name.clone(), acc.value());
+                            }
+
                             if let Some(inner_path) = value.path() {
                                 let new_path =
                                     format!("{}/{}.[{}]", local_rc.get_path(), inner_path, i);
@@ -46,60 +117,74 @@ impl HelperDef for EachHelper {
                                 local_rc.set_path(new_path.clone());
                             }
 
+                            // TODO: each item is cloned into its block context here, and the
+                            // list is cloned once by `Parameter::expand`. Iterating by reference
+                            // needs params borrowing from the context (a `&'b Json` in
+                            // `ContextJson` for params with a path) and block contexts borrowing
+                            // their values, e.g. `RenderContext::push_block_context_ref(&mut self,
+                            // name: &str, value: &'b Json)`. examples/each_memory.rs reports the
+                            // allocations of a large each.
                             if let Some(block_param) = h.block_param() {
-                                let mut map = BTreeMap::new();
-                                map.insert(block_param.to_string(), to_json(&list[i]));
-                                local_rc.push_block_context(&map);
+                                local_rc.push_block_params(vec![(block_param.to_string(),
+                                                                 list[i].clone())]);
+                            } else if let Some((bp_val, bp_index)) = h.block_param_pair() {
+                                local_rc.push_block_params(vec![(bp_val.to_string(),
+                                                                 list[i].clone()),
+                                                                (bp_index.to_string(),
+                                                                 to_json(&i))]);
                             }
 
                             try!(t.render(r, &mut local_rc));
 
-                            if h.block_param().is_some() {
+                            if h.block_param().is_some() || h.block_param_pair().is_some() {
                                 local_rc.pop_block_context();
                             }
 
-                            if local_path_root.is_some() {
-                                local_rc.pop_local_path_root();
-                            }
+                            local_rc.pop_local_path_root();
                         }
                         Ok(())
                     }
                     (true, &Json::Object(ref obj)) => {
-                        let mut first: bool = true;
-                        for k in obj.keys() {
+                        let len = obj.len();
+                        for (i, k) in obj.keys().enumerate() {
                             let mut local_rc = rc.derive();
-                            if let Some(ref p) = local_path_root {
-                                local_rc.push_local_path_root(p.clone());
-                            }
-                            local_rc.set_local_var("@first".to_string(), to_json(&first));
-                            if first {
-                                first = false;
+                            local_rc.push_local_path_root(local_path_root.clone());
+                            if value.path().is_none() {
+                                local_rc.set_base_value(obj.get(k).unwrap());
                             }
 
+                            local_rc.set_local_var("@first".to_string(), to_json(&(i == 0usize)));
+                            local_rc.set_local_var("@last".to_string(), to_json(&(i == len - 1)));
+                            local_rc.set_local_var("@index".to_string(), to_json(&i));
                             local_rc.set_local_var("@key".to_string(), to_json(k));
 
+                            if let Some(ref mut acc) = accumulator {
+                                acc.add(obj.get(k).unwrap());
+                                local_rc.set_local_var(acc.var_name.clone(), acc.value());
+                            }
+
                             if let Some(inner_path) = value.path() {
                                 let new_path =
                                     format!("{}/{}.[{}]", local_rc.get_path(), inner_path, k);
                                 local_rc.set_path(new_path);
                             }
 
-                            if let Some((bp_key, bp_val)) = h.block_param_pair() {
-                                let mut map = BTreeMap::new();
-                                map.insert(bp_key.to_string(), to_json(k));
-                                map.insert(bp_val.to_string(), to_json(obj.get(k).unwrap()));
-                                local_rc.push_block_context(&map);
+                            if let Some(block_param) = h.block_param() {
+                                local_rc.push_block_params(vec![(block_param.to_string(),
+                                                                 obj[k].clone())]);
+                            } else if let Some((bp_val, bp_key)) = h.block_param_pair() {
+                                local_rc.push_block_params(vec![(bp_key.to_string(), to_json(k)),
+                                                                (bp_val.to_string(),
+                                                                 obj[k].clone())]);
                             }
 
                             try!(t.render(r, &mut local_rc));
 
-                            if h.block_param().is_some() {
+                            if h.block_param().is_some() || h.block_param_pair().is_some() {
                                 local_rc.pop_block_context();
                             }
 
-                            if local_path_root.is_some() {
-                                local_rc.pop_local_path_root();
-                            }
+                            local_rc.pop_local_path_root();
                         }
 
                         Ok(())
@@ -110,9 +195,7 @@ impl HelperDef for EachHelper {
                         }
                         Ok(())
                     }
-                    _ => {
-                        Err(RenderError::new(format!("Param type is not iterable: {:?}", template)))
-                    }
+                    (true, v) => Err(param_type_error("each", 0, "an array or object", v)),
                 };
 
                 rc.demote_local_vars();
@@ -154,6 +237,27 @@ mod test {
         assert_eq!(r1.ok().unwrap(), "true|ftp:21|false|http:80|".to_string());
     }
 
+    #[test]
+    fn test_each_object_last_and_index() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#each this}}{{@first}}|{{@last}}|{{@index}}|{{@key}}:{{this}}|{{/each}}")
+                .is_ok());
+
+        let mut m: BTreeMap<String, u16> = BTreeMap::new();
+        m.insert("ftp".to_string(), 21);
+        m.insert("http".to_string(), 80);
+        m.insert("dns".to_string(), 53);
+        let r0 = handlebars.render("t0", &m);
+        assert_eq!(r0.ok().unwrap(),
+                   "true|false|0|dns:53|false|false|1|ftp:21|false|true|2|http:80|".to_string());
+
+        let mut single: BTreeMap<String, u16> = BTreeMap::new();
+        single.insert("ssh".to_string(), 22);
+        let r1 = handlebars.render("t0", &single);
+        assert_eq!(r1.ok().unwrap(), "true|true|0|ssh:22|".to_string());
+    }
+
     #[test]
     #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
     fn test_each_with_parent() {
@@ -205,6 +309,26 @@ mod test {
     }
 
 
+    #[test]
+    fn test_nested_object_keys() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#each this}}{{#each this}}{{@../key}}.\
+                                                     {{@key}}={{this}} {{/each}}{{@key}}; \
+                                                     {{/each}}")
+                    .is_ok());
+
+        let data = btreemap! {
+            "db".to_string() => btreemap! {
+                "host".to_string() => "localhost".to_string(),
+                "port".to_string() => "5432".to_string()
+            },
+            "web".to_string() => btreemap! {"port".to_string() => "80".to_string()}
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap(),
+                   "db.host=localhost db.port=5432 db; web.port=80 web; ");
+    }
+
     #[test]
     fn test_nested_array() {
         let mut handlebars = Registry::new();
@@ -262,6 +386,12 @@ mod test {
         };
         let r1 = handlebars.render("t0", &m2).unwrap();
         assert_eq!(r1, "empty");
+
+        let m3 = btreemap!{
+            "a".to_string() => BTreeMap::<String, String>::new()
+        };
+        let r2 = handlebars.render("t0", &m3).unwrap();
+        assert_eq!(r2, "empty");
     }
 
     #[test]
@@ -276,10 +406,53 @@ mod test {
         assert_eq!(r0, "12345");
     }
 
+    #[test]
+    fn test_each_block_param_index() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#each a as |item idx|}}{{idx}}:{{item}}|{{/each}}")
+                    .is_ok());
+        let m1 = btreemap! {
+            "a".to_string() => vec!["x".to_string(), "y".to_string(), "z".to_string()]
+        };
+        let r0 = handlebars.render("t0", &m1).unwrap();
+        assert_eq!(r0, "0:x|1:y|2:z|");
+    }
+
+    #[test]
+    fn test_each_accumulate() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#each items accumulate=\"price\"}}{{name}}:{{@running}}|{{/each}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#each items accumulate=\"price\" into=\"subtotal\"}}{{@subtotal}}|{{/each}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2",
+                                                    "{{#each counts accumulate=\"this\"}}{{@running}}|{{/each}}")
+                    .is_ok());
+
+        let data = btreemap! {
+            "items".to_string() => to_json(&vec![
+                btreemap! {"name".to_string() => to_json(&"apple".to_owned()),
+                           "price".to_string() => to_json(&3)},
+                btreemap! {"name".to_string() => to_json(&"pear".to_owned()),
+                           "price".to_string() => to_json(&4)},
+                btreemap! {"name".to_string() => to_json(&"gift".to_owned())}
+            ]),
+            "counts".to_string() => to_json(&vec![1.5, 2.0])
+        };
+
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "apple:3|pear:7|gift:7|");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "3|7|7|");
+        assert_eq!(handlebars.render("t2", &data).unwrap(), "1.5|3.5|");
+    }
+
     #[test]
     fn test_each_object_block_param() {
         let mut handlebars = Registry::new();
-        assert!(handlebars.register_template_string("t0", "{{#each this as |k v|}}{{#with k as |inner_k|}}{{inner_k}}{{/with}}:{{v}}|{{/each}}").is_ok());
+        assert!(handlebars.register_template_string("t0", "{{#each this as |v k|}}{{#with k as |inner_k|}}{{inner_k}}{{/with}}:{{v}}|{{/each}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{#each this as |v|}}{{@key}}:{{v}}|{{/each}}").is_ok());
 
         let m = btreemap!{
             "ftp".to_string() => 21,
@@ -287,6 +460,9 @@ mod test {
         };
         let r0 = handlebars.render("t0", &m);
         assert_eq!(r0.ok().unwrap(), "ftp:21|http:80|".to_string());
+
+        let r1 = handlebars.render("t1", &m);
+        assert_eq!(r1.ok().unwrap(), "ftp:21|http:80|".to_string());
     }
 
     #[test]
@@ -316,4 +492,22 @@ mod test {
         let r0 = handlebars.render("t0", &data);
         assert_eq!(r0.ok().unwrap(), "template<T>template<T>".to_string());
     }
+
+    #[test]
+    #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+    fn test_parent() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{#each groups}}{{#each items}}{{@parent.title}}:{{this}} {{/each}}{{/each}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{#each groups}}{{#with owner}}{{name}}@{{@parent.title}} {{/with}}{{/each}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{#each groups}}{{#each items}}{{lookup @parent \"title\"}}{{/each}}{{/each}}").is_ok());
+
+        let data = Json::from_str(r#"{"groups": [
+            {"title": "A", "items": [1, 2], "owner": {"name": "x"}},
+            {"title": "B", "items": [3], "owner": {"name": "y"}}
+        ]}"#)
+                .unwrap();
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "A:1 A:2 B:3 ");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "x@A y@B ");
+        assert_eq!(handlebars.render("t2", &data).unwrap(), "AAB");
+    }
 }
@@ -0,0 +1,86 @@
+use std::env;
+
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::HelperDef;
+use registry::Registry;
+use context::{JsonRender, to_json};
+use render::{RenderContext, RenderError, Helper};
+
+/// Returns the value of an environment variable, like `{{env "HOME"}}`
+///
+/// An unset variable, or one that isn't valid unicode, returns the `default`
+/// hash or empty string. Not registered by default, see
+/// `Registry::register_env_helper`.
+#[derive(Clone, Copy)]
+pub struct EnvHelper;
+
+impl HelperDef for EnvHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let name =
+            try!(h.param(0).ok_or_else(|| RenderError::new("Param not found for helper \"env\"")));
+
+        let value = match env::var(name.value().render()) {
+            Ok(value) => to_json(&value),
+            Err(_) => {
+                h.hash_get("default")
+                    .map(|v| v.value().clone())
+                    .unwrap_or_else(|| to_json(&String::new()))
+            }
+        };
+        Ok(Some(value))
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        if let Some(result) = try!(self.call_inner(h, r, rc)) {
+            let output = rc.escape(r, result.render());
+            try!(rc.writer.write(output.into_bytes().as_ref()));
+        }
+        Ok(())
+    }
+}
+
+pub static ENV_HELPER: EnvHelper = EnvHelper;
+
+#[cfg(test)]
+mod test {
+    use std::env;
+
+    use registry::Registry;
+
+    #[test]
+    fn test_env() {
+        env::set_var("HANDLEBARS_TEST_ENV", "<ci>");
+        env::remove_var("HANDLEBARS_TEST_ENV_UNSET");
+
+        let mut handlebars = Registry::new();
+        handlebars.register_env_helper();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{env \"HANDLEBARS_TEST_ENV\"}}|\
+                                                     {{env \"HANDLEBARS_TEST_ENV_UNSET\"}}|\
+                                                     {{env \"HANDLEBARS_TEST_ENV_UNSET\" \
+                                                     default=\"local\"}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#if (env \"HANDLEBARS_TEST_ENV_UNSET\")}}\
+                                                     set{{else}}unset{{/if}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &()).unwrap(), "&lt;ci&gt;||local");
+        assert_eq!(handlebars.render("t1", &()).unwrap(), "unset");
+    }
+
+    #[test]
+    fn test_env_not_registered() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{env \"HOME\"}}").is_ok());
+        assert!(handlebars.render("t0", &()).is_err());
+    }
+}
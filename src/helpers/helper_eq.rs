@@ -0,0 +1,122 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::HelperDef;
+use registry::Registry;
+use context::{JsonTruthy, as_string, to_json};
+use render::{RenderContext, RenderError, Helper};
+
+/// Compares the first param with the others
+///
+/// The result is a boolean when used as subexpression, like
+/// `{{#if (eq a b)}}`. Values of different types never equal: there's no
+/// coercion between numbers and strings, so `1` doesn't equal `"1"`. With
+/// `ignoreCase=true`, strings are compared case-insensitively.
+#[derive(Clone, Copy)]
+pub struct EqHelper;
+
+/// Json equality, except that numbers are compared by value regardless of
+/// their internal representation
+pub fn json_eq(a: &Json, b: &Json, ignore_case: bool) -> bool {
+    if a.is_number() && b.is_number() {
+        a.as_f64() == b.as_f64()
+    } else if ignore_case && a.is_string() && b.is_string() {
+        as_string(a).map(|s| s.to_lowercase()) == as_string(b).map(|s| s.to_lowercase())
+    } else {
+        a == b
+    }
+}
+
+impl HelperDef for EqHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let value =
+            try!(h.param(0).ok_or_else(|| RenderError::new("Param not found for helper \"eq\"")));
+        if h.params().len() < 2 {
+            return Err(RenderError::new("Insufficient params for helper \"eq\""));
+        }
+        let ignore_case = h.hash_get("ignoreCase").map(|v| v.value().is_truthy()).unwrap_or(false);
+
+        // true if the first param equals any of the following ones
+        let matched =
+            h.params()[1..].iter().any(|p| json_eq(value.value(), p.value(), ignore_case));
+        Ok(Some(to_json(&matched)))
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        // renders nothing rather than `false`, like `and` and `or`
+        let matched = try!(self.call_inner(h, r, rc)).map(|v| v.is_truthy()).unwrap_or(false);
+        if matched {
+            try!(rc.writer.write("true".as_bytes()));
+        }
+        Ok(())
+    }
+}
+
+pub static EQ_HELPER: EqHelper = EqHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+
+    #[test]
+    fn test_eq() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{#if (eq status \"active\")}}yes{{else}}no{{/if}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{#if (eq status \"active\" \"pending\")}}yes{{else}}no{{/if}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{eq count 1}}").is_ok());
+
+        let active = btreemap! {"status".to_string() => "active".to_string()};
+        let pending = btreemap! {"status".to_string() => "pending".to_string()};
+        let closed = btreemap! {"status".to_string() => "closed".to_string()};
+
+        assert_eq!(handlebars.render("t0", &active).unwrap(), "yes");
+        assert_eq!(handlebars.render("t0", &pending).unwrap(), "no");
+
+        assert_eq!(handlebars.render("t1", &active).unwrap(), "yes");
+        assert_eq!(handlebars.render("t1", &pending).unwrap(), "yes");
+        assert_eq!(handlebars.render("t1", &closed).unwrap(), "no");
+
+        assert_eq!(handlebars.render("t2", &btreemap! {"count".to_string() => 1i32}).unwrap(),
+                   "true");
+        assert_eq!(handlebars.render("t2", &btreemap! {"count".to_string() => 2i32}).unwrap(),
+                   "");
+    }
+
+    #[test]
+    fn test_eq_ignore_case() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{#if (eq status \"Active\")}}yes{{else}}no{{/if}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{#if (eq status \"Active\" ignoreCase=true)}}yes{{else}}no{{/if}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{#if (eq count \"1\" ignoreCase=true)}}yes{{else}}no{{/if}}").is_ok());
+
+        let active = btreemap! {"status".to_string() => "active".to_string()};
+        assert_eq!(handlebars.render("t0", &active).unwrap(), "no");
+        assert_eq!(handlebars.render("t1", &active).unwrap(), "yes");
+
+        // no coercion between numbers and strings
+        assert_eq!(handlebars.render("t2", &btreemap! {"count".to_string() => 1i32}).unwrap(),
+                   "no");
+    }
+
+    #[test]
+    fn test_eq_subexpression_value() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{json (eq a b)}}").is_ok());
+
+        let data = btreemap! {"a".to_string() => 1i32, "b".to_string() => 2i32};
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "false");
+    }
+
+    #[test]
+    fn test_eq_insufficient_params() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{eq this}}").is_ok());
+        assert!(handlebars.render("t0", &1).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use helpers::{HelperDef, param_type_error};
+use registry::Registry;
+use context::JsonTruthy;
+use render::{RenderContext, RenderError, Helper};
+
+static DECIMAL_UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
+static BINARY_UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+#[derive(Clone, Copy)]
+pub struct FileSizeHelper;
+
+fn format_size(bytes: f64, binary: bool, precision: usize) -> String {
+    let (base, units) = if binary {
+        (1024f64, &BINARY_UNITS)
+    } else {
+        (1000f64, &DECIMAL_UNITS)
+    };
+
+    let mut size = bytes;
+    let mut unit = 0;
+    while size.abs() >= base && unit < units.len() - 1 {
+        size /= base;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", size, units[unit])
+    } else {
+        format!("{:.*} {}", precision, size, units[unit])
+    }
+}
+
+impl HelperDef for FileSizeHelper {
+    fn call(&self, h: &Helper, _: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let param = try!(h.param(0)
+                             .ok_or_else(|| RenderError::new("Param not found for helper \"filesize\"")));
+        let bytes = try!(param.value()
+                             .as_f64()
+                             .ok_or_else(|| {
+                                             param_type_error("filesize", 0, "a number", param.value())
+                                         }));
+
+        let binary = h.hash_get("binary").map(|v| v.value().is_truthy()).unwrap_or(false);
+        let precision = h.hash_get("precision")
+            .and_then(|v| v.value().as_u64())
+            .unwrap_or(1) as usize;
+
+        let output = format_size(bytes, binary, precision);
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }
+}
+
+pub static FILESIZE_HELPER: FileSizeHelper = FileSizeHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+
+    #[test]
+    fn test_filesize() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{filesize this}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{filesize this binary=true}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2",
+                                                    "{{filesize this binary=true precision=3}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &0).unwrap(), "0 B");
+        assert_eq!(handlebars.render("t1", &0).unwrap(), "0 B");
+
+        assert_eq!(handlebars.render("t0", &1023).unwrap(), "1.0 KB");
+        assert_eq!(handlebars.render("t1", &1023).unwrap(), "1023 B");
+
+        assert_eq!(handlebars.render("t0", &1024).unwrap(), "1.0 KB");
+        assert_eq!(handlebars.render("t1", &1024).unwrap(), "1.0 KiB");
+
+        assert_eq!(handlebars.render("t0", &5368709120u64).unwrap(), "5.4 GB");
+        assert_eq!(handlebars.render("t1", &5368709120u64).unwrap(), "5.0 GiB");
+        assert_eq!(handlebars.render("t2", &5500000000u64).unwrap(), "5.122 GiB");
+    }
+
+    #[test]
+    fn test_filesize_invalid_param() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{filesize this}}").is_ok());
+
+        assert!(handlebars.render("t0", &"large".to_owned()).is_err());
+        assert!(handlebars.render("t0", &()).is_err());
+    }
+}
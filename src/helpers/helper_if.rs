@@ -1,6 +1,5 @@
 use helpers::HelperDef;
 use registry::Registry;
-use context::JsonTruthy;
 use render::{Renderable, RenderContext, RenderError, Helper};
 
 #[derive(Clone, Copy)]
@@ -13,7 +12,7 @@ impl HelperDef for IfHelper {
         let param =
             try!(h.param(0).ok_or_else(|| RenderError::new("Param not found for helper \"if\"")));
 
-        let mut value = param.value().is_truthy();
+        let mut value = r.is_truthy(param.value());
 
         if !self.positive {
             value = !value;
@@ -56,6 +55,37 @@ mod test {
         assert_eq!(r2.ok().unwrap(), "".to_string());
     }
 
+    #[test]
+    fn test_truthy_fn() {
+        use context::{JsonTruthy, as_string};
+
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{#if this}}yes{{else}}no{{/if}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#unless this}}yes{{else}}no{{/unless}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2",
+                                                    "{{#each this}}{{@key}}{{else}}no{{/each}}")
+                    .is_ok());
+
+        let empty = btreemap! {"a".to_string() => ()};
+        assert_eq!(handlebars.render("t0", &"false".to_owned()).unwrap(), "yes");
+        assert_eq!(handlebars.render("t2", &empty).unwrap(), "a");
+
+        handlebars.set_truthy_fn(|v| {
+            let all_null = v.as_object().map(|m| m.values().all(|v| v.is_null())).unwrap_or(false);
+            v.is_truthy() && as_string(v) != Some("false") && !all_null
+        });
+        assert_eq!(handlebars.render("t0", &"false".to_owned()).unwrap(), "no");
+        assert_eq!(handlebars.render("t1", &"false".to_owned()).unwrap(), "yes");
+        assert_eq!(handlebars.render("t0", &"true".to_owned()).unwrap(), "yes");
+        assert_eq!(handlebars.render("t2", &empty).unwrap(), "no");
+
+        handlebars.unset_truthy_fn();
+        assert_eq!(handlebars.render("t0", &"false".to_owned()).unwrap(), "yes");
+    }
+
     #[test]
     #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
     fn test_if_context() {
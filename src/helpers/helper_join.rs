@@ -0,0 +1,80 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::HelperDef;
+use registry::Registry;
+use context::JsonRender;
+use render::{RenderContext, RenderError, Helper};
+
+/// Joins items of an array with a separator, like `{{join tags ", "}}`
+///
+/// Use `last` for a different separator before the last item, so
+/// `{{join names ", " last=" and "}}` renders `a, b and c`. The separator
+/// defaults to `,`. Items are escaped, separators are not. A value that is not
+/// an array is rendered as a single item, and a missing value renders nothing.
+#[derive(Clone, Copy)]
+pub struct JoinHelper;
+
+impl HelperDef for JoinHelper {
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let value =
+            try!(h.param(0).ok_or_else(|| RenderError::new("Param not found for helper \"join\"")));
+        let separator = h.param(1).map(|v| v.value().render()).unwrap_or_else(|| ",".to_owned());
+        let last = h.hash_get("last")
+            .map(|v| v.value().render())
+            .unwrap_or_else(|| separator.clone());
+
+        let items = match *value.value() {
+            Json::Array(ref list) => list.iter().collect(),
+            Json::Null => Vec::new(),
+            ref v => vec![v],
+        };
+
+        let mut output = String::new();
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                output.push_str(if i == items.len() - 1 { &last } else { &separator });
+            }
+            output.push_str(&rc.escape(r, item.render()));
+        }
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }
+}
+
+pub static JOIN_HELPER: JoinHelper = JoinHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+
+    #[test]
+    fn test_join() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{join this \", \"}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{join this \", \" last=\" and \"}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2", "{{join this}}").is_ok());
+
+        let names = vec!["a".to_owned(), "b".to_owned(), "<c>".to_owned()];
+        assert_eq!(handlebars.render("t0", &names).unwrap(), "a, b, &lt;c&gt;");
+        assert_eq!(handlebars.render("t1", &names).unwrap(), "a, b and &lt;c&gt;");
+        assert_eq!(handlebars.render("t2", &names).unwrap(), "a,b,&lt;c&gt;");
+
+        assert_eq!(handlebars.render("t1", &vec![1, 2]).unwrap(), "1 and 2");
+        assert_eq!(handlebars.render("t1", &vec![1]).unwrap(), "1");
+        assert_eq!(handlebars.render("t1", &Vec::<i32>::new()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_join_not_array() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{join name \", \"}}").is_ok());
+
+        let data = btreemap! {"name".to_string() => "a".to_string()};
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "a");
+        assert_eq!(handlebars.render("t0", &()).unwrap(), "");
+    }
+}
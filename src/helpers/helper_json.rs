@@ -0,0 +1,76 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+#[cfg(feature = "serde_type")]
+use serde_json;
+
+use helpers::HelperDef;
+use registry::Registry;
+use context::{JsonRender, JsonTruthy};
+use render::{RenderContext, RenderError, Helper};
+
+#[derive(Clone, Copy)]
+pub struct JsonHelper;
+
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+fn to_pretty_string(value: &Json) -> String {
+    value.pretty().to_string()
+}
+
+#[cfg(feature = "serde_type")]
+fn to_pretty_string(value: &Json) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.render_json())
+}
+
+impl HelperDef for JsonHelper {
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let value =
+            try!(h.param(0).ok_or_else(|| RenderError::new("Param not found for helper \"json\"")));
+        let pretty = h.hash_get("pretty").map(|v| v.value().is_truthy()).unwrap_or(false);
+
+        let json = if pretty {
+            to_pretty_string(value.value())
+        } else {
+            value.value().render_json()
+        };
+
+        let output = rc.escape(r, json);
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }
+}
+
+pub static JSON_HELPER: JsonHelper = JsonHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use escape::no_escape;
+    use context::to_json;
+
+    #[test]
+    fn test_json() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{json this}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{json name}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{json this pretty=true}}").is_ok());
+        assert!(handlebars.register_template_string("t3", "{{#if (json tags)}}ok{{/if}}").is_ok());
+
+        let data = btreemap! {
+            "name".to_string() => to_json(&"<b>".to_owned()),
+            "tags".to_string() => to_json(&vec![1, 2])
+        };
+
+        assert_eq!(handlebars.render("t0", &data).unwrap(),
+                   "{&quot;name&quot;:&quot;&lt;b&gt;&quot;,&quot;tags&quot;:[1,2]}");
+        assert_eq!(handlebars.render("t3", &data).unwrap(), "ok");
+
+        handlebars.register_escape_fn(no_escape);
+        assert_eq!(handlebars.render("t0", &data).unwrap(),
+                   "{\"name\":\"<b>\",\"tags\":[1,2]}");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "\"<b>\"");
+        assert_eq!(handlebars.render("t2", &data).unwrap(),
+                   "{\n  \"name\": \"<b>\",\n  \"tags\": [\n    1,\n    2\n  ]\n}");
+    }
+}
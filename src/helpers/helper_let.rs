@@ -0,0 +1,78 @@
+use helpers::HelperDef;
+use registry::Registry;
+use render::{Renderable, RenderContext, RenderError, Helper};
+
+#[derive(Clone, Copy)]
+pub struct LetHelper;
+
+impl HelperDef for LetHelper {
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        // variables are set on a derived context, so they are dropped with it
+        let mut local_rc = rc.derive();
+        for (name, value) in h.hash_iter() {
+            local_rc.set_local_var(name.to_owned(), value.value().clone());
+        }
+
+        match h.template() {
+            Some(t) => t.render(r, &mut local_rc),
+            None => Ok(()),
+        }
+    }
+}
+
+pub static LET_HELPER: LetHelper = LetHelper;
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+    use serialize::json::Json;
+    #[cfg(feature = "serde_type")]
+    use serde_json::value::Value as Json;
+
+    use std::collections::BTreeMap;
+
+    use context::to_json;
+    use helpers::HelperDef;
+    use registry::Registry;
+    use render::{RenderContext, RenderError, Helper};
+
+    struct AddHelper;
+
+    impl HelperDef for AddHelper {
+        fn call_inner(&self,
+                      h: &Helper,
+                      _: &Registry,
+                      _: &mut RenderContext)
+                      -> Result<Option<Json>, RenderError> {
+            let mut sum = 0;
+            for p in h.params() {
+                sum += p.value().as_i64().unwrap_or(0);
+            }
+            Ok(Some(to_json(&sum)))
+        }
+    }
+
+    #[test]
+    fn test_let() {
+        let mut handlebars = Registry::new();
+        handlebars.register_helper("add", Box::new(AddHelper));
+        assert!(handlebars.register_template_string("t0",
+                                     "{{#let total=(add a b)}}Total: {{total}}{{/let}}|{{total}}")
+            .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                     "{{#let total=(add a b) name=\"sum\"}}{{#each list}}{{@index}}:{{name}}={{total}} {{/each}}{{/let}}")
+            .is_ok());
+        assert!(handlebars.register_template_string("t2",
+                                     "{{#let total=(add a b)}}{{#repeat total}}x{{/repeat}}{{/let}}")
+            .is_ok());
+
+        let mut data = BTreeMap::new();
+        data.insert("a".to_string(), to_json(&1));
+        data.insert("b".to_string(), to_json(&2));
+        data.insert("list".to_string(), to_json(&vec![5, 6]));
+
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "Total: 3|");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "0:sum=3 1:sum=3 ");
+        assert_eq!(handlebars.render("t2", &data).unwrap(), "xxx");
+    }
+}
@@ -1,22 +1,68 @@
+use std::str::FromStr;
+
+use log::LogLevel;
+
 use helpers::HelperDef;
 use registry::Registry;
 use context::JsonRender;
 use render::{RenderContext, RenderError, Helper};
 
+/// Logs params with the `log` crate, like `{{log "user" user.name level="warn"}}`
+///
+/// Params are rendered and joined with spaces, and a param from a path is
+/// prefixed with the path. The level is one of `trace`, `debug`, `info`,
+/// `warn` and `error`, and defaults to `info`. Nothing is written to output.
 #[derive(Clone, Copy)]
 pub struct LogHelper;
 
 impl HelperDef for LogHelper {
     fn call(&self, h: &Helper, _: &Registry, _: &mut RenderContext) -> Result<(), RenderError> {
-        let param =
-            try!(h.param(0).ok_or_else(|| RenderError::new("Param not found for helper \"log\"")));
+        if h.params().is_empty() {
+            return Err(RenderError::new("Param not found for helper \"log\""));
+        }
 
-        info!("{}: {}",
-              param.path().unwrap_or(&"".to_owned()),
-              param.value().render());
+        let level = match h.hash_get("level") {
+            Some(v) => {
+                let name = v.value().render();
+                try!(LogLevel::from_str(&name).map_err(|_| {
+                    RenderError::new(format!("Invalid log level {:?} for helper \"log\"", name))
+                }))
+            }
+            None => LogLevel::Info,
+        };
+
+        let message = h.params()
+            .iter()
+            .map(|p| match p.path() {
+                Some(path) => format!("{}: {}", path, p.value().render()),
+                None => p.value().render(),
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        log!(level, "{}", message);
 
         Ok(())
     }
 }
 
 pub static LOG_HELPER: LogHelper = LogHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+
+    #[test]
+    fn test_log() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "a{{log \"name\" name}}b").is_ok());
+        assert!(handlebars.register_template_string("t1", "a{{log name level=\"WARN\"}}b")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2", "{{log name level=\"loud\"}}").is_ok());
+
+        let data = btreemap! {"name".to_string() => "Ning Sun".to_string()};
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "ab");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "ab");
+        assert_eq!(handlebars.render("t2", &data).unwrap_err().desc,
+                   "Invalid log level \"loud\" for helper \"log\"");
+    }
+}
@@ -0,0 +1,166 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::HelperDef;
+use registry::Registry;
+use context::{JsonTruthy, to_json};
+use render::{RenderContext, RenderError, Helper};
+
+/// True if all params are truthy, like `{{#if (and isAdmin (not isBanned))}}`
+///
+/// `and`, `or` and `not` return a boolean when used as subexpression, and
+/// render `true` or nothing otherwise. Truthiness follows `Registry::is_truthy`.
+#[derive(Clone, Copy)]
+pub struct AndHelper;
+
+/// True if any param is truthy
+#[derive(Clone, Copy)]
+pub struct OrHelper;
+
+/// True if the param is falsy
+#[derive(Clone, Copy)]
+pub struct NotHelper;
+
+fn write_bool(value: Option<Json>, rc: &mut RenderContext) -> Result<(), RenderError> {
+    if value.map(|v| v.is_truthy()).unwrap_or(false) {
+        try!(rc.writer.write("true".as_bytes()));
+    }
+    Ok(())
+}
+
+impl HelperDef for AndHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  r: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        if h.params().is_empty() {
+            return Err(RenderError::new("Param not found for helper \"and\""));
+        }
+
+        let value = h.params().iter().all(|p| r.is_truthy(p.value()));
+        Ok(Some(to_json(&value)))
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let value = try!(self.call_inner(h, r, rc));
+        write_bool(value, rc)
+    }
+}
+
+impl HelperDef for OrHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  r: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        if h.params().is_empty() {
+            return Err(RenderError::new("Param not found for helper \"or\""));
+        }
+
+        let value = h.params().iter().any(|p| r.is_truthy(p.value()));
+        Ok(Some(to_json(&value)))
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let value = try!(self.call_inner(h, r, rc));
+        write_bool(value, rc)
+    }
+}
+
+impl HelperDef for NotHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  r: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let param =
+            try!(h.param(0).ok_or_else(|| RenderError::new("Param not found for helper \"not\"")));
+
+        Ok(Some(to_json(&!r.is_truthy(param.value()))))
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let value = try!(self.call_inner(h, r, rc));
+        write_bool(value, rc)
+    }
+}
+
+pub static AND_HELPER: AndHelper = AndHelper;
+pub static OR_HELPER: OrHelper = OrHelper;
+pub static NOT_HELPER: NotHelper = NotHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use context::{JsonTruthy, to_json};
+
+    #[test]
+    fn test_and_or() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#if (and a b)}}yes{{else}}no{{/if}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#if (and a b c)}}yes{{else}}no{{/if}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2",
+                                                    "{{#if (or c d)}}yes{{else}}no{{/if}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t3",
+                                                    "{{#if (or c d b)}}yes{{else}}no{{/if}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t4", "{{and a b}}|{{or c d}}").is_ok());
+
+        let data = btreemap! {
+            "a".to_string() => to_json(&true),
+            "b".to_string() => to_json(&"text".to_owned()),
+            "c".to_string() => to_json(&0),
+            "d".to_string() => to_json(&Vec::<u32>::new())
+        };
+
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "yes");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "no");
+        assert_eq!(handlebars.render("t2", &data).unwrap(), "no");
+        assert_eq!(handlebars.render("t3", &data).unwrap(), "yes");
+        assert_eq!(handlebars.render("t4", &data).unwrap(), "true|");
+    }
+
+    #[test]
+    fn test_not() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{#if (not this)}}yes{{else}}no{{/if}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#if (not (and this 1))}}yes{{else}}no{{/if}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &false).unwrap(), "yes");
+        assert_eq!(handlebars.render("t0", &1).unwrap(), "no");
+        assert_eq!(handlebars.render("t1", &false).unwrap(), "yes");
+    }
+
+    #[test]
+    fn test_logic_subexpression_value() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{#if (and isAdmin (not isBanned))}}yes{{else}}no{{/if}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{json (and isAdmin isBanned)}}|{{json (or isAdmin isBanned)}}|{{json (not isAdmin)}}").is_ok());
+
+        let data = btreemap! {
+            "isAdmin".to_string() => true,
+            "isBanned".to_string() => false
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "yes");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "false|true|false");
+
+        let data = btreemap! {
+            "isAdmin".to_string() => to_json(&0),
+            "isBanned".to_string() => to_json(&false)
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "no");
+        handlebars.set_truthy_fn(|v| v.is_number() || v.is_truthy());
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "yes");
+    }
+}
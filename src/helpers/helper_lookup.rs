@@ -5,14 +5,18 @@ use serde_json::value::Value as Json;
 
 use helpers::HelperDef;
 use registry::Registry;
-use context::JsonRender;
 use render::{RenderContext, RenderError, Helper};
+use context::JsonRender;
 
 #[derive(Clone, Copy)]
 pub struct LookupHelper;
 
 impl HelperDef for LookupHelper {
-    fn call(&self, h: &Helper, _: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+    fn call_inner(&self,
+                  h: &Helper,
+                  r: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
         let collection_value = try!(h.param(0).ok_or_else(|| {
             RenderError::new("Param not found for helper \"lookup\"")
         }));
@@ -20,34 +24,27 @@ impl HelperDef for LookupHelper {
             RenderError::new("Insufficient params for helper \"lookup\"")
         }));
 
-        let null = Json::Null;
+        // index can be a number or a string like "1", key can be a string or a number
         let value = match collection_value.value() {
             &Json::Array(ref v) => {
                 index.value()
                     .as_u64()
-                    .and_then(|u| Some(u as usize))
+                    .map(|u| u as usize)
+                    .or_else(|| index.value().render().parse::<usize>().ok())
                     .and_then(|u| v.get(u))
-                    .unwrap_or(&null)
-            }
-            #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
-            &Json::Object(ref m) => {
-                index.value()
-                    .as_string()
-                    .and_then(|k| m.get(k))
-                    .unwrap_or(&null)
             }
-            #[cfg(feature = "serde_type")]
-            &Json::Object(ref m) => {
-                index.value()
-                    .as_str()
-                    .and_then(|k| m.get(k))
-                    .unwrap_or(&null)
-            }
-            _ => &null,
+            &Json::Object(ref m) => m.get(&index.value().render()),
+            _ => None,
         };
-        let r = value.render();
-        try!(rc.writer.write(r.into_bytes().as_ref()));
-        Ok(())
+
+        match value {
+            Some(v) => Ok(Some(v.clone())),
+            None if r.is_strict_mode() => {
+                Err(RenderError::new(format!("Key {:?} not found for helper \"lookup\"",
+                                             index.value().render())))
+            }
+            None => Ok(Some(Json::Null)),
+        }
     }
 }
 
@@ -56,6 +53,7 @@ pub static LOOKUP_HELPER: LookupHelper = LookupHelper;
 #[cfg(test)]
 mod test {
     use registry::Registry;
+    use context::to_json;
 
     use std::collections::BTreeMap;
 
@@ -85,4 +83,31 @@ mod test {
         let r2 = handlebars.render("t2", &m2);
         assert_eq!(r2.ok().unwrap(), "world".to_string());
     }
+
+    #[test]
+    fn test_lookup_with_variables() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{lookup list idx}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{lookup codes key}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{lookup codes missing}}").is_ok());
+
+        let data = btreemap! {
+            "list".to_string() => to_json(&vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]),
+            "codes".to_string() => to_json(&btreemap! {
+                "404".to_string() => "Not Found".to_string()
+            }),
+            "idx".to_string() => to_json(&"1".to_owned()),
+            "key".to_string() => to_json(&404),
+            "missing".to_string() => to_json(&"500".to_owned())
+        };
+
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "b");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "Not Found");
+        assert_eq!(handlebars.render("t2", &data).unwrap(), "");
+
+        handlebars.set_strict_mode(true);
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "Not Found");
+        assert_eq!(handlebars.render("t2", &data).unwrap_err().desc,
+                   "Key \"500\" not found for helper \"lookup\"");
+    }
 }
@@ -0,0 +1,47 @@
+use pulldown_cmark::{Parser, html};
+
+use helpers::HelperDef;
+use registry::Registry;
+use render::{RenderContext, RenderError, Helper};
+
+/// Renders the block, then converts the output from Markdown to HTML
+///
+/// `{{#markdown}}# {{title}}{{/markdown}}` renders `<h1>...</h1>`. Expressions
+/// in the block are escaped as usual, but the generated HTML is written as is.
+/// Only available with the `markdown` feature.
+#[derive(Clone, Copy)]
+pub struct MarkdownHelper;
+
+impl HelperDef for MarkdownHelper {
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let source = try!(h.render_template(r, rc));
+
+        let mut output = String::new();
+        html::push_html(&mut output, Parser::new(&source));
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }
+}
+
+pub static MARKDOWN_HELPER: MarkdownHelper = MarkdownHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+
+    #[test]
+    fn test_markdown() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#markdown}}# {{title}}\n\n*{{body}}*\
+                                                     {{/markdown}}")
+                    .is_ok());
+
+        let data = btreemap! {
+            "title".to_string() => "Hello".to_string(),
+            "body".to_string() => "<b>world</b>".to_string()
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap(),
+                   "<h1>Hello</h1>\n<p><em>&lt;b&gt;world&lt;/b&gt;</em></p>\n");
+    }
+}
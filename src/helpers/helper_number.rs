@@ -0,0 +1,81 @@
+use helpers::{HelperDef, param_type_error};
+use registry::{Registry, NumberLocale};
+use context::JsonRender;
+use render::{RenderContext, RenderError, Helper};
+
+/// Formats a number with thousands separators and fixed decimals
+///
+/// `{{number price decimals=2 thousands="," decimalPoint="."}}` renders
+/// `1234.5` as `1,234.50`. Separators default to those of the registry's
+/// number locale, or `,` and `.` if no locale is set. Without `decimals`, the
+/// number keeps its own fractional part.
+#[derive(Clone, Copy)]
+pub struct NumberHelper;
+
+impl HelperDef for NumberHelper {
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let param = try!(h.param(0)
+                             .ok_or_else(|| RenderError::new("Param not found for helper \"number\"")));
+        let value = param.value();
+        let number = try!(value.as_f64()
+                              .ok_or_else(|| param_type_error("number", 0, "a number", value)));
+
+        let plain = match h.hash_get("decimals").and_then(|v| v.value().as_u64()) {
+            Some(decimals) => format!("{:.*}", decimals as usize, number),
+            None => value.render(),
+        };
+
+        let (default_group, default_decimal) = match r.get_number_locale() {
+            Some(locale) => (locale.group_separator.clone(), locale.decimal_separator.clone()),
+            None => (",".to_owned(), ".".to_owned()),
+        };
+        let group = h.hash_get("thousands").map(|v| v.value().render()).unwrap_or(default_group);
+        let decimal = h.hash_get("decimalPoint")
+            .map(|v| v.value().render())
+            .unwrap_or(default_decimal);
+
+        let output = NumberLocale::new(group, decimal).format(&plain);
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }
+}
+
+pub static NUMBER_HELPER: NumberHelper = NumberHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::{Registry, NumberLocale};
+
+    #[test]
+    fn test_number() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{number this}}").is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{number this decimals=2 thousands=\",\"}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2", "{{number this decimals=1 thousands=\".\" decimalPoint=\",\"}}").is_ok());
+
+        assert_eq!(handlebars.render("t0", &1234567).unwrap(), "1,234,567");
+        assert_eq!(handlebars.render("t0", &1234.5).unwrap(), "1,234.5");
+        assert_eq!(handlebars.render("t0", &-999).unwrap(), "-999");
+
+        assert_eq!(handlebars.render("t1", &1234.5).unwrap(), "1,234.50");
+        assert_eq!(handlebars.render("t1", &0).unwrap(), "0.00");
+        assert_eq!(handlebars.render("t1", &-1234567.891).unwrap(), "-1,234,567.89");
+
+        assert_eq!(handlebars.render("t2", &1234.56).unwrap(), "1.234,6");
+
+        handlebars.set_number_locale(Some(NumberLocale::new(" ", ",")));
+        assert_eq!(handlebars.render("t0", &1234.5).unwrap(), "1 234,5");
+    }
+
+    #[test]
+    fn test_number_not_numeric() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{number this}}").is_ok());
+
+        let err = handlebars.render("t0", &"abc".to_owned()).unwrap_err();
+        assert_eq!(err.desc,
+                   "Helper \"number\" expected a number for param 0 but got string");
+    }
+}
@@ -4,7 +4,7 @@ use std::iter::FromIterator;
 use helpers::HelperDef;
 use registry::Registry;
 use context::JsonRender;
-use render::{Renderable, RenderContext, RenderError, Helper};
+use render::{Renderable, RenderContext, RenderError, RenderErrorKind, Helper};
 
 #[derive(Clone, Copy)]
 pub struct IncludeHelper;
@@ -17,19 +17,16 @@ pub struct PartialHelper;
 
 impl HelperDef for IncludeHelper {
     fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
-        let template = try!(h.params()
-                                .get(0)
-                                .ok_or(RenderError::new("Param not found for helper"))
-                                .and_then(|ref t| {
-            t.path()
-                .or(Some(&t.value().render()))
-                .ok_or(RenderError::new("Invalid template name to include"))
-                .and_then(|p| if rc.is_current_template(p) {
-                              Err(RenderError::new("Cannot include self in >"))
-                          } else {
-                              Ok(r.get_template(&p))
-                          })
-        }));
+        let name = try!(h.params()
+                            .get(0)
+                            .ok_or(RenderError::new("Param not found for helper"))
+                            .map(|t| t.path().cloned().unwrap_or_else(|| t.value().render())));
+        let name = try!(r.resolve_partial_alias(&name)).to_owned();
+        if rc.is_current_template(&name) {
+            return Err(RenderError::new("Cannot include self in >"));
+        }
+        let reloaded = try!(r.reload_template(&name));
+        let template = reloaded.as_ref().or(r.get_template(&name));
 
         let context_param = h.params().get(1).and_then(|p| p.path());
         let old_path = match context_param {
@@ -61,7 +58,7 @@ impl HelperDef for IncludeHelper {
                     t.render(r, &mut local_rc)
                 }
             }
-            None => Err(RenderError::new("Template not found.")),
+            None => Err(RenderError::from_kind(RenderErrorKind::PartialNotFound(name.clone()))),
         };
 
         if let Some(path) = old_path {
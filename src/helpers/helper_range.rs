@@ -0,0 +1,146 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::{HelperDef, param_type_error};
+use registry::Registry;
+use context::to_json;
+use render::{RenderContext, RenderError, Helper};
+
+/// Returns an array of integers from `start` up to, but not including, `end`
+///
+/// `{{#each (range 1 5)}}{{this}}{{/each}}` renders `1234`. Use the `step`
+/// hash to skip numbers, like `range 0 10 step=2`. If `end` is not greater
+/// than `start` the array is empty, so `each` renders its `{{else}}` branch.
+/// The array has at most `Registry::get_max_repeat_count` numbers.
+#[derive(Clone, Copy)]
+pub struct RangeHelper;
+
+fn integer_param(h: &Helper, idx: usize) -> Result<i64, RenderError> {
+    let param = try!(h.param(idx)
+                         .ok_or_else(|| RenderError::new("Param not found for helper \"range\"")));
+    let value = param.value();
+    value.as_i64().ok_or_else(|| param_type_error("range", idx, "an integer", value))
+}
+
+impl HelperDef for RangeHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  r: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let start = try!(integer_param(h, 0));
+        let end = try!(integer_param(h, 1));
+        let step = match h.hash_get("step") {
+            Some(v) => {
+                match v.value().as_i64() {
+                    Some(step) if step > 0 => step,
+                    _ => {
+                        return Err(RenderError::new("Helper \"range\" expected a positive \
+                                                     integer for hash step"))
+                    }
+                }
+            }
+            None => 1,
+        };
+
+        let mut numbers = Vec::new();
+        let mut n = start;
+        while n < end {
+            if numbers.len() as u64 >= r.get_max_repeat_count() {
+                return Err(RenderError::new("Length exceeds limit for helper \"range\""));
+            }
+            numbers.push(n);
+            n = match n.checked_add(step) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        Ok(Some(to_json(&numbers)))
+    }
+}
+
+pub static RANGE_HELPER: RangeHelper = RangeHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use context::to_json;
+
+    #[test]
+    fn test_range() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{#each (range 1 5)}}{{this}}{{/each}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1", "{{#each (range 0 7 step=3)}}{{@index}}:{{this}} {{/each}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{#each (range a b)}}{{this}}{{else}}empty{{/each}}").is_ok());
+        assert!(handlebars.register_template_string("t3", "{{json (range 0 3)}}").is_ok());
+
+        assert_eq!(handlebars.render("t0", &()).unwrap(), "1234");
+        assert_eq!(handlebars.render("t1", &()).unwrap(), "0:0 1:3 2:6 ");
+        assert_eq!(handlebars.render("t2", &btreemap! {"a".to_string() => 3, "b".to_string() => 3})
+                       .unwrap(),
+                   "empty");
+        assert_eq!(handlebars.render("t2", &btreemap! {"a".to_string() => 5, "b".to_string() => -1})
+                       .unwrap(),
+                   "empty");
+        assert_eq!(handlebars.render("t3", &()).unwrap(), "[0,1,2]");
+    }
+
+    #[test]
+    fn test_range_invalid_params() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{json (range 1 \"a\")}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{json (range 1 5 step=0)}}").is_ok());
+
+        assert_eq!(handlebars.render("t0", &()).unwrap_err().desc,
+                   "Helper \"range\" expected an integer for param 1 but got string");
+        assert_eq!(handlebars.render("t1", &()).unwrap_err().desc,
+                   "Helper \"range\" expected a positive integer for hash step");
+    }
+
+    #[test]
+    fn test_range_parent_context() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#each (range 1 3)}}{{../title}}{{this}},\
+                                                     {{/each}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#each (range 0 2)}}{{#each ../list}}\
+                                                     {{this}}{{/each}};{{/each}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2",
+                                                    "{{#each items}}{{#each (range 0 2)}}\
+                                                     {{../name}}{{this}}{{/each}}{{/each}}")
+                    .is_ok());
+
+        let items = vec![btreemap! {"name".to_string() => "x".to_string()},
+                         btreemap! {"name".to_string() => "y".to_string()}];
+        let data = btreemap! {
+            "title".to_string() => to_json(&"T".to_string()),
+            "list".to_string() => to_json(&vec!["a".to_string(), "b".to_string()]),
+            "items".to_string() => to_json(&items)
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "T1,T2,");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "ab;ab;");
+        assert_eq!(handlebars.render("t2", &data).unwrap(), "x0x1y0y1");
+    }
+
+    #[test]
+    fn test_range_limits() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{json (range 0 10)}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{json (range a b step=5)}}").is_ok());
+
+        handlebars.set_max_repeat_count(5);
+        assert_eq!(handlebars.render("t0", &()).unwrap_err().desc,
+                   "Length exceeds limit for helper \"range\"");
+        let data = btreemap! {
+            "a".to_string() => i64::max_value() - 1,
+            "b".to_string() => i64::max_value()
+        };
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "[9223372036854775806]");
+    }
+}
@@ -30,4 +30,17 @@ mod test {
         let r = handlebars.render("t0", &());
         assert_eq!(r.ok().unwrap(), "a{{content}}{{else}}hello");
     }
+
+    #[test]
+    fn test_raw_unbalanced_tags() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{{{raw}}}}{{#if a}}{{> p}}{{!-- c --}}\
+                                                     {{{{/raw}}}}{{a}}")
+                    .is_ok());
+
+        let data = btreemap! {"a".to_string() => "<b>".to_string()};
+        assert_eq!(handlebars.render("t0", &data).unwrap(),
+                   "{{#if a}}{{> p}}{{!-- c --}}&lt;b&gt;");
+    }
 }
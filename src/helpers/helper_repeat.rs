@@ -0,0 +1,138 @@
+use helpers::{HelperDef, param_type_error};
+use registry::Registry;
+use context::{JsonRender, to_json};
+use render::{Renderable, RenderContext, RenderError, Helper};
+
+#[derive(Clone, Copy)]
+pub struct RepeatHelper;
+
+impl HelperDef for RepeatHelper {
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        if h.is_block() {
+            let count = try!(h.param(0)
+                .ok_or_else(|| RenderError::new("Param not found for helper \"repeat\""))
+                .and_then(|v| {
+                    v.value()
+                        .as_u64()
+                        .ok_or_else(|| {
+                            param_type_error("repeat", 0, "a non-negative integer", v.value())
+                        })
+                }));
+            if count > r.get_max_repeat_count() {
+                return Err(RenderError::new("Count exceeds limit for helper \"repeat\""));
+            }
+
+            if let Some(t) = h.template() {
+                rc.promote_local_vars();
+                let mut rendered = Ok(());
+                for i in 0..count {
+                    let mut local_rc = rc.derive();
+                    local_rc.set_local_var("@first".to_string(), to_json(&(i == 0)));
+                    local_rc.set_local_var("@last".to_string(), to_json(&(i == count - 1)));
+                    local_rc.set_local_var("@index".to_string(), to_json(&i));
+                    rendered = t.render(r, &mut local_rc);
+                    if rendered.is_err() {
+                        break;
+                    }
+                }
+                rc.demote_local_vars();
+                return rendered;
+            }
+            Ok(())
+        } else {
+            let value = try!(h.param(0)
+                .ok_or_else(|| RenderError::new("Param not found for helper \"repeat\"")));
+            let count = try!(h.param(1)
+                .ok_or_else(|| RenderError::new("Param not found for helper \"repeat\""))
+                .and_then(|v| {
+                    v.value()
+                        .as_u64()
+                        .ok_or_else(|| {
+                            param_type_error("repeat", 1, "a non-negative integer", v.value())
+                        })
+                }));
+
+            let s = value.value().render();
+            if (s.len() as u64).saturating_mul(count) > r.get_max_repeat_output() as u64 {
+                return Err(RenderError::new("Output exceeds limit for helper \"repeat\""));
+            }
+
+            let output = s.repeat(count as usize);
+            try!(rc.writer.write(output.into_bytes().as_ref()));
+            Ok(())
+        }
+    }
+}
+
+pub static REPEAT_HELPER: RepeatHelper = RepeatHelper;
+
+#[cfg(test)]
+mod test {
+    use context::to_json;
+    use registry::Registry;
+
+    #[test]
+    fn test_repeat() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{repeat \"=\" 10}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{repeat sep n}}").is_ok());
+
+        let data = btreemap! {
+            "sep".to_string() => "-+".to_string(),
+            "n".to_string() => "3".to_string()
+        };
+        assert_eq!(handlebars.render("t0", &()).unwrap(), "==========");
+        // count must be a number
+        assert!(handlebars.render("t1", &data).is_err());
+
+        let data = btreemap! {
+            "sep".to_string() => to_json(&"-+".to_owned()),
+            "n".to_string() => to_json(&3)
+        };
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "-+-+-+");
+    }
+
+    #[test]
+    fn test_repeat_block() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{#repeat 3}}item{{/repeat}}").is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#repeat 3}}{{@index}}{{#unless @last}},{{/unless}}{{/repeat}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t2", "{{#repeat 0}}item{{/repeat}}").is_ok());
+
+        assert_eq!(handlebars.render("t0", &()).unwrap(), "itemitemitem");
+        assert_eq!(handlebars.render("t1", &()).unwrap(), "0,1,2");
+        assert_eq!(handlebars.render("t2", &()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_repeat_limit() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{repeat \"abc\" 1000000}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{#repeat 1000000}}a{{/repeat}}").is_ok());
+
+        assert!(handlebars.render("t0", &()).is_err());
+        assert!(handlebars.render("t1", &()).is_err());
+
+        handlebars.set_max_repeat_output(3000000);
+        assert_eq!(handlebars.render("t0", &()).unwrap().len(), 3000000);
+
+        assert!(handlebars.register_template_string("t2", "{{#repeat 3}}a{{/repeat}}").is_ok());
+        assert_eq!(handlebars.render("t2", &()).unwrap(), "aaa");
+        handlebars.set_max_repeat_count(2);
+        assert_eq!(handlebars.render("t2", &()).unwrap_err().desc,
+                   "Count exceeds limit for helper \"repeat\"");
+    }
+
+    #[test]
+    fn test_repeat_keeps_outer_locals() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#each this}}[{{@index}}{{#repeat 1}}\
+                                                     {{/repeat}}{{@index}}]{{/each}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &vec![1, 2]).unwrap(), "[00][11]");
+    }
+}
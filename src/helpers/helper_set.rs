@@ -0,0 +1,129 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
+use helpers::{HelperDef, param_type_error};
+use registry::Registry;
+use render::{RenderContext, RenderError, Helper};
+
+#[derive(Clone, Copy)]
+pub struct DifferenceHelper;
+
+#[derive(Clone, Copy)]
+pub struct IntersectionHelper;
+
+#[derive(Clone, Copy)]
+pub struct UnionHelper;
+
+fn array_params<'a>(h: &'a Helper,
+                    name: &str)
+                    -> Result<(&'a Vec<Json>, &'a Vec<Json>), RenderError> {
+    let mut arrays = Vec::new();
+    for idx in 0..2 {
+        let param = try!(h.param(idx).ok_or_else(|| {
+            RenderError::new(format!("Param not found for helper \"{}\"", name))
+        }));
+        let array = try!(param.value()
+            .as_array()
+            .ok_or_else(|| param_type_error(name, idx, "an array", param.value())));
+        arrays.push(array);
+    }
+    Ok((arrays[0], arrays[1]))
+}
+
+/// Collect items of `list` that pass `filter`, each distinct item only once
+fn distinct<F>(list: &[Json], result: &mut Vec<Json>, filter: F)
+    where F: Fn(&Json) -> bool
+{
+    for item in list {
+        if filter(item) && !result.contains(item) {
+            result.push(item.clone());
+        }
+    }
+}
+
+impl HelperDef for DifferenceHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let (first, second) = try!(array_params(h, "difference"));
+        let mut result = Vec::new();
+        distinct(first, &mut result, |item| !second.contains(item));
+        Ok(Some(Json::Array(result)))
+    }
+}
+
+impl HelperDef for IntersectionHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let (first, second) = try!(array_params(h, "intersection"));
+        let mut result = Vec::new();
+        distinct(first, &mut result, |item| second.contains(item));
+        Ok(Some(Json::Array(result)))
+    }
+}
+
+impl HelperDef for UnionHelper {
+    fn call_inner(&self,
+                  h: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        let (first, second) = try!(array_params(h, "union"));
+        let mut result = Vec::new();
+        distinct(first, &mut result, |_| true);
+        distinct(second, &mut result, |_| true);
+        Ok(Some(Json::Array(result)))
+    }
+}
+
+pub static DIFFERENCE_HELPER: DifferenceHelper = DifferenceHelper;
+pub static INTERSECTION_HELPER: IntersectionHelper = IntersectionHelper;
+pub static UNION_HELPER: UnionHelper = UnionHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+    use context::to_json;
+
+    #[test]
+    fn test_set_helpers() {
+        let mut handlebars = Registry::new();
+        for name in &["difference", "intersection", "union"] {
+            let tpl = format!("{{{{#each ({} a b) as |x|}}}}{{{{x}}}},{{{{/each}}}}", name);
+            assert!(handlebars.register_template_string(name, tpl).is_ok());
+        }
+        assert!(handlebars.register_template_string("t0", "{{union a b}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{union a name}}").is_ok());
+
+        let overlapping = btreemap! {
+            "a".to_string() => vec![4, 1, 2, 3, 2],
+            "b".to_string() => vec![3, 5, 4]
+        };
+        assert_eq!(handlebars.render("difference", &overlapping).unwrap(), "1,2,");
+        assert_eq!(handlebars.render("intersection", &overlapping).unwrap(), "4,3,");
+        assert_eq!(handlebars.render("union", &overlapping).unwrap(), "4,1,2,3,5,");
+        assert_eq!(handlebars.render("t0", &overlapping).unwrap(), "[4, 1, 2, 3, 5]");
+
+        let disjoint = btreemap! {
+            "a".to_string() => vec![1, 2],
+            "b".to_string() => vec![3]
+        };
+        assert_eq!(handlebars.render("difference", &disjoint).unwrap(), "1,2,");
+        assert_eq!(handlebars.render("intersection", &disjoint).unwrap(), "");
+        assert_eq!(handlebars.render("union", &disjoint).unwrap(), "1,2,3,");
+
+        let data = btreemap! {
+            "a".to_string() => to_json(&vec![1]),
+            "name".to_string() => to_json(&"Ning".to_owned())
+        };
+        assert_eq!(handlebars.render("t1", &data).unwrap_err().desc,
+                   "Helper \"union\" expected an array for param 1 but got string");
+    }
+}
@@ -0,0 +1,112 @@
+use helpers::HelperDef;
+use registry::Registry;
+use context::{JsonRender, JsonTruthy};
+use render::{RenderContext, RenderError, Helper};
+
+#[derive(Clone, Copy)]
+pub struct SlugHelper;
+
+/// ASCII replacement of common accented latin letters, in lowercase
+fn transliterate(c: char) -> Option<&'static str> {
+    let s = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'ğ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ı' => "i",
+        'ł' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ř' => "r",
+        'ś' | 'š' | 'ş' => "s",
+        'ß' => "ss",
+        'ť' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    };
+    Some(s)
+}
+
+/// Lowercase `input` and join its words with `sep`
+///
+/// With `ascii`, accented letters are transliterated and other non-ASCII
+/// characters are treated as separators.
+fn slugify(input: &str, sep: &str, ascii: bool) -> String {
+    let mut buf = String::with_capacity(input.len());
+    let mut pending_sep = false;
+
+    for c in input.chars().flat_map(|c| c.to_lowercase()) {
+        let mut word_char = c.is_alphanumeric();
+        let mut replacement = None;
+        if word_char && ascii && !c.is_ascii() {
+            replacement = transliterate(c);
+            word_char = replacement.is_some();
+        }
+
+        if !word_char {
+            pending_sep = true;
+            continue;
+        }
+
+        if pending_sep && !buf.is_empty() {
+            buf.push_str(sep);
+        }
+        pending_sep = false;
+
+        match replacement {
+            Some(r) => buf.push_str(r),
+            None => buf.push(c),
+        }
+    }
+    buf
+}
+
+impl HelperDef for SlugHelper {
+    fn call(&self, h: &Helper, _: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let value =
+            try!(h.param(0).ok_or_else(|| RenderError::new("Param not found for helper \"slug\"")));
+        let sep = h.hash_get("sep").map(|v| v.value().render()).unwrap_or_else(|| "-".to_owned());
+        let ascii = h.hash_get("ascii").map(|v| v.value().is_truthy()).unwrap_or(false);
+
+        let slug = slugify(&value.value().render(), &sep, ascii);
+        try!(rc.writer.write(slug.into_bytes().as_ref()));
+        Ok(())
+    }
+}
+
+pub static SLUG_HELPER: SlugHelper = SlugHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+
+    #[test]
+    fn test_slug() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{slug this}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{slug this sep=\"_\"}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{slug this ascii=true}}").is_ok());
+
+        assert_eq!(handlebars.render("t0", &"My Post Title".to_owned()).unwrap(),
+                   "my-post-title");
+        assert_eq!(handlebars.render("t0", &"  Hello, World!  ".to_owned()).unwrap(),
+                   "hello-world");
+        assert_eq!(handlebars.render("t0", &"--Rust & Handlebars: 2017--".to_owned()).unwrap(),
+                   "rust-handlebars-2017");
+        assert_eq!(handlebars.render("t1", &"My Post Title".to_owned()).unwrap(),
+                   "my_post_title");
+
+        assert_eq!(handlebars.render("t0", &"Crème Brûlée".to_owned()).unwrap(),
+                   "crème-brûlée");
+        assert_eq!(handlebars.render("t2", &"Crème Brûlée à Paris".to_owned()).unwrap(),
+                   "creme-brulee-a-paris");
+        assert_eq!(handlebars.render("t2", &"Straße 你好 Łódź".to_owned()).unwrap(),
+                   "strasse-lodz");
+    }
+}
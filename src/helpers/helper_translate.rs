@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::iter::FromIterator;
+
+use helpers::HelperDef;
+use registry::Registry;
+use context::{Context, JsonRender};
+use render::{Renderable, RenderContext, RenderError, Helper};
+use template::Template;
+
+#[derive(Clone, Copy)]
+pub struct TranslateHelper;
+
+impl HelperDef for TranslateHelper {
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let key = try!(h.param(0)
+                .ok_or_else(|| RenderError::new("Param not found for helper \"t\"")))
+            .value()
+            .render();
+
+        let message = match r.get_message(&key) {
+            Some(m) => m,
+            None => {
+                // fallback to the key itself
+                let output = rc.escape(r, key);
+                try!(rc.writer.write(output.into_bytes().as_ref()));
+                return Ok(());
+            }
+        };
+
+        let t = try!(Template::compile(message).map_err(|e| {
+            RenderError::new(format!("Invalid message {:?} for helper \"t\": {}", key, e))
+        }));
+
+        // messages are rendered with hash params as the context, like a partial
+        let hash_ctx = BTreeMap::from_iter(h.hash()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.value().clone())));
+        let mut local_ctx = Context::wraps(&hash_ctx);
+        let mut local_rc = rc.derive_with_context(&mut local_ctx);
+        t.render(r, &mut local_rc)
+    }
+}
+
+pub static TRANSLATE_HELPER: TranslateHelper = TranslateHelper;
+
+#[cfg(test)]
+mod test {
+    use registry::Registry;
+
+    #[test]
+    fn test_translate() {
+        let mut handlebars = Registry::new();
+        handlebars.register_messages("en",
+                                     hashmap! {
+                                         "greeting".to_string() => "Hello, {{name}}!".to_string()
+                                     });
+        handlebars.register_messages("zh",
+                                     hashmap! {
+                                         "greeting".to_string() => "你好，{{name}}！".to_string()
+                                     });
+        assert!(handlebars.register_template_string("t0", "{{t \"greeting\" name=user.name}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1", "{{t \"farewell\" name=user.name}}")
+                    .is_ok());
+
+        let data = btreemap! {
+            "user".to_string() => btreemap! {
+                "name".to_string() => "<Ning>".to_string()
+            }
+        };
+
+        // no language selected
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "greeting");
+
+        handlebars.set_language("en");
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "Hello, &lt;Ning&gt;!");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "farewell");
+
+        handlebars.set_language("zh");
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "你好，&lt;Ning&gt;！");
+    }
+
+    #[test]
+    fn test_translate_render_context() {
+        use std::collections::HashMap;
+        use context::Context;
+        use registry::EscapeMode;
+
+        let mut handlebars = Registry::new();
+        handlebars.register_messages("en",
+                                     hashmap! {
+                                         "greeting".to_string() => "Hi {{name}} of {{@root.site}}"
+                                             .to_string()
+                                     });
+        handlebars.set_language("en");
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{t \"greeting\" name=user}}|{{t key}}")
+                    .is_ok());
+
+        let data = btreemap! {
+            "user".to_string() => "<Ning>".to_string(),
+            "site".to_string() => "<a>".to_string(),
+            "key".to_string() => "<missing>".to_string()
+        };
+        assert_eq!(handlebars.render("t0", &data).unwrap(),
+                   "Hi &lt;Ning&gt; of &lt;a&gt;|&lt;missing&gt;");
+        assert_eq!(handlebars.render_full("t0",
+                                          &Context::wraps(&data),
+                                          &HashMap::new(),
+                                          None,
+                                          Some(EscapeMode::NoEscape))
+                       .unwrap(),
+                   "Hi <Ning> of <a>|<missing>");
+    }
+}
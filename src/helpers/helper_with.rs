@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 
 use helpers::HelperDef;
 use registry::Registry;
-use context::{JsonTruthy, to_json};
+use context::to_json;
 use render::{Renderable, RenderContext, RenderError, Helper};
 
 #[derive(Clone, Copy)]
@@ -18,9 +18,13 @@ impl HelperDef for WithHelper {
         let result = {
             let mut local_rc = rc.derive();
 
-            let not_empty = param.value().is_truthy();
+            let not_empty = r.is_truthy(param.value());
             let template = if not_empty { h.template() } else { h.inverse() };
 
+            // the block param is bound only for the main template, the value is
+            // falsy in `{{else}}` branch
+            let block_param = if not_empty { h.block_param() } else { None };
+
             if let Some(path_root) = param.path_root() {
                 let local_path_root = format!("{}/{}", local_rc.get_path(), path_root);
                 local_rc.push_local_path_root(local_path_root);
@@ -31,7 +35,7 @@ impl HelperDef for WithHelper {
                     local_rc.set_path(new_path);
                 }
 
-                if let Some(block_param) = h.block_param() {
+                if let Some(block_param) = block_param {
                     let mut map = BTreeMap::new();
                     map.insert(block_param.to_string(), to_json(param.value()));
                     local_rc.push_block_context(&map);
@@ -43,7 +47,7 @@ impl HelperDef for WithHelper {
                 None => Ok(()),
             };
 
-            if h.block_param().is_some() {
+            if block_param.is_some() {
                 local_rc.pop_block_context();
             }
 
@@ -169,6 +173,28 @@ mod test {
         assert_eq!(r2.ok().unwrap(), "China".to_string());
     }
 
+    #[test]
+    fn test_with_block_param_scopes() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#with addr as |a|}}{{a.city}}/{{city}}{{/with}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1", "{{#with addr as |a|}}{{#with zip as |z|}}{{z}}{{else}}{{a.city}}{{/with}}{{/with}}").is_ok());
+
+        let data = btreemap! {
+            "addr".to_string() => to_json(&btreemap! {
+                "city".to_string() => to_json(&"Beijing".to_owned()),
+                "zip".to_string() => Json::Null
+            })
+        };
+
+        let r0 = handlebars.render("t0", &data);
+        assert_eq!(r0.ok().unwrap(), "Beijing/Beijing".to_string());
+
+        let r1 = handlebars.render("t1", &data);
+        assert_eq!(r1.ok().unwrap(), "Beijing".to_string());
+    }
+
     #[test]
     fn test_with_in_each() {
         let addr = Address {
@@ -1,5 +1,11 @@
+#[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+use serialize::json::Json;
+#[cfg(feature = "serde_type")]
+use serde_json::value::Value as Json;
+
 use render::{RenderContext, RenderError, Helper};
 use registry::Registry;
+use context::{JsonRender, value_type};
 
 pub use self::helper_if::{IF_HELPER, UNLESS_HELPER};
 pub use self::helper_each::EACH_HELPER;
@@ -9,6 +15,28 @@ pub use self::helper_raw::RAW_HELPER;
 #[cfg(feature="partial_legacy")]
 pub use self::helper_partial::{INCLUDE_HELPER, BLOCK_HELPER, PARTIAL_HELPER};
 pub use self::helper_log::LOG_HELPER;
+pub use self::helper_filesize::FILESIZE_HELPER;
+pub use self::helper_eq::EQ_HELPER;
+pub use self::helper_repeat::REPEAT_HELPER;
+pub use self::helper_translate::TRANSLATE_HELPER;
+pub use self::helper_chunk::CHUNK_HELPER;
+pub use self::helper_logic::{AND_HELPER, OR_HELPER, NOT_HELPER};
+pub use self::helper_slug::SLUG_HELPER;
+pub use self::helper_let::LET_HELPER;
+pub use self::helper_json::JSON_HELPER;
+pub use self::helper_set::{DIFFERENCE_HELPER, INTERSECTION_HELPER, UNION_HELPER};
+pub use self::helper_number::NUMBER_HELPER;
+pub use self::helper_default::DEFAULT_HELPER;
+pub use self::helper_range::RANGE_HELPER;
+pub use self::helper_join::JOIN_HELPER;
+pub use self::helper_cmp::{GT_HELPER, LT_HELPER, GTE_HELPER, LTE_HELPER};
+pub use self::helper_case::{UPPER_HELPER, LOWER_HELPER, CAPITALIZE_HELPER};
+pub use self::helper_env::ENV_HELPER;
+pub use self::helper_contains::CONTAINS_HELPER;
+#[cfg(feature = "markdown")]
+pub use self::helper_markdown::MARKDOWN_HELPER;
+#[cfg(feature = "dates")]
+pub use self::helper_date::DATE_HELPER;
 
 /// Helper Definition
 ///
@@ -22,12 +50,12 @@ pub use self::helper_log::LOG_HELPER;
 ///
 /// ## Define an inline helper
 ///
-/// ```ignore
+/// ```
 /// use handlebars::*;
 ///
 /// fn upper(h: &Helper, _: &Handlebars, rc: &mut RenderContext) -> Result<(), RenderError> {
 ///    // get parameter from helper or throw an error
-///    let param = h.param(0).and_then(|v| v.value().as_string()).unwrap_or("");
+///    let param = h.param(0).and_then(|v| v.as_str()).unwrap_or("");
 ///    try!(rc.writer.write(param.to_uppercase().into_bytes().as_ref()));
 ///    Ok(())
 /// }
@@ -45,9 +73,85 @@ pub use self::helper_log::LOG_HELPER;
 /// }
 /// ```
 ///
+/// ## Define a helper returning a value
+///
+/// A helper may implement `call_inner` instead of `call` to return a `Json` value. When used
+/// as a subexpression, like `{{#each (my_helper a)}}` or `{{other v=(my_helper a)}}`, the
+/// value is passed to the outer helper as it is, rather than as its rendered string. When
+/// used directly, the rendered value is written to output.
+///
+/// ```ignore
+/// use handlebars::*;
+///
+/// struct FirstHelper;
+///
+/// impl HelperDef for FirstHelper {
+///     fn call_inner(&self, h: &Helper, _: &Handlebars, _: &mut RenderContext)
+///             -> Result<Option<Json>, RenderError> {
+///         Ok(h.param(0).and_then(|v| v.value().as_array()).and_then(|a| a.first()).cloned())
+///     }
+/// }
+/// ```
+///
+/// ## Define a helper with state
+///
+/// A helper is shared by all renders of a registry, which may run on other threads, so it's
+/// called with `&self` and must be `Send + Sync`. Keep mutable state in a `Mutex` or an atomic
+/// type rather than a `RefCell`. This helper generates ids unique within the registry, like
+/// `{{uid "section"}}` for `section-1`, `section-2` and so on:
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::sync::Mutex;
+/// use handlebars::*;
+///
+/// #[derive(Default)]
+/// struct UidHelper {
+///     counters: Mutex<HashMap<String, usize>>,
+/// }
+///
+/// impl HelperDef for UidHelper {
+///     fn call(&self, h: &Helper, _: &Handlebars, rc: &mut RenderContext)
+///             -> Result<(), RenderError> {
+///         let prefix = try!(h.param_checked(0, "uid")).value().render();
+///         let mut counters = self.counters.lock().unwrap();
+///         let counter = counters.entry(prefix.clone()).or_insert(0);
+///         *counter += 1;
+///         try!(rc.writer.write(format!("{}-{}", prefix, counter).into_bytes().as_ref()));
+///         Ok(())
+///     }
+/// }
+///
+/// let mut handlebars = Handlebars::new();
+/// handlebars.register_helper("uid", Box::new(UidHelper::default()));
+/// assert_eq!(handlebars.render_template("{{uid \"a\"}} {{uid \"a\"}}", &()).unwrap(),
+///            "a-1 a-2");
+/// ```
+///
+/// A closure helper can hold state the same way, like an `Arc<AtomicUsize>` also kept by the
+/// caller to read it after rendering.
 ///
 pub trait HelperDef: Send + Sync {
-    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError>;
+    /// Returns a value instead of writing output, `None` if the helper doesn't
+    /// produce a value
+    fn call_inner(&self,
+                  _: &Helper,
+                  _: &Registry,
+                  _: &mut RenderContext)
+                  -> Result<Option<Json>, RenderError> {
+        Ok(None)
+    }
+
+    fn call(&self, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        // a subexpression calls this when `call_inner` has returned `None`
+        if h.take_call_inner_done() {
+            return Ok(());
+        }
+        if let Some(result) = try!(self.call_inner(h, r, rc)) {
+            try!(rc.writer.write(result.render().into_bytes().as_ref()));
+        }
+        Ok(())
+    }
 }
 
 /// implement HelperDef for bare function so we can use function as helper
@@ -57,6 +161,18 @@ impl<F: Send + Sync + for<'b, 'c, 'd, 'e> Fn(&'b Helper, &'c Registry, &'d mut R
     }
 }
 
+/// Error for a param of unexpected type, like
+/// `Helper "chunk" expected an array for param 0 but got object`
+///
+/// `expected` is the description of accepted types with an article, like "an array".
+pub fn param_type_error(helper: &str, index: usize, expected: &str, value: &Json) -> RenderError {
+    RenderError::new(format!("Helper {:?} expected {} for param {} but got {}",
+                             helper,
+                             expected,
+                             index,
+                             value_type(value)))
+}
+
 mod helper_if;
 mod helper_each;
 mod helper_with;
@@ -65,6 +181,28 @@ mod helper_raw;
 #[cfg(feature="partial_legacy")]
 mod helper_partial;
 mod helper_log;
+mod helper_filesize;
+mod helper_eq;
+mod helper_repeat;
+mod helper_translate;
+mod helper_chunk;
+mod helper_logic;
+mod helper_slug;
+mod helper_let;
+mod helper_json;
+mod helper_set;
+mod helper_number;
+mod helper_default;
+mod helper_range;
+mod helper_join;
+mod helper_cmp;
+mod helper_case;
+mod helper_env;
+mod helper_contains;
+#[cfg(feature = "markdown")]
+mod helper_markdown;
+#[cfg(feature = "dates")]
+mod helper_date;
 
 // pub type HelperDef = for <'a, 'b, 'c> Fn<(&'a Context, &'b Helper, &'b Registry, &'c mut RenderContext), Result<String, RenderError>>;
 //
@@ -77,11 +215,49 @@ mod helper_log;
 mod test {
     use std::collections::BTreeMap;
 
-    use context::JsonRender;
+    #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
+    use serialize::json::Json;
+    #[cfg(feature = "serde_type")]
+    use serde_json::value::Value as Json;
+
+    use context::{JsonRender, to_json};
     use helpers::HelperDef;
     use registry::Registry;
     use render::{RenderContext, RenderError, Renderable, Helper};
 
+    #[test]
+    fn test_param_type_errors() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0", "{{#each name}}{{this}}{{/each}}").is_ok());
+        assert!(r.register_template_string("t1", "{{#each (chunk tags 2) as |c|}}{{/each}}")
+                    .is_ok());
+        assert!(r.register_template_string("t2", "{{#each (chunk list name) as |c|}}{{/each}}")
+                    .is_ok());
+        assert!(r.register_template_string("t3", "{{filesize list}}").is_ok());
+        assert!(r.register_template_string("t4", "{{repeat \"-\" name}}").is_ok());
+        assert!(r.register_template_string("t5", "{{#repeat tags}}x{{/repeat}}").is_ok());
+
+        let mut data = BTreeMap::new();
+        data.insert("name".to_string(), to_json(&"Ning".to_owned()));
+        data.insert("list".to_string(), to_json(&vec![1, 2, 3]));
+        let mut tags = BTreeMap::new();
+        tags.insert("a".to_string(), 1);
+        data.insert("tags".to_string(), to_json(&tags));
+
+        assert_eq!(r.render("t0", &data).unwrap_err().desc,
+                   "Helper \"each\" expected an array or object for param 0 but got string");
+        assert_eq!(r.render("t1", &data).unwrap_err().desc,
+                   "Helper \"chunk\" expected an array for param 0 but got object");
+        assert_eq!(r.render("t2", &data).unwrap_err().desc,
+                   "Helper \"chunk\" expected a positive integer for param 1 but got string");
+        assert_eq!(r.render("t3", &data).unwrap_err().desc,
+                   "Helper \"filesize\" expected a number for param 0 but got array");
+        assert_eq!(r.render("t4", &data).unwrap_err().desc,
+                   "Helper \"repeat\" expected a non-negative integer for param 1 but got string");
+        assert_eq!(r.render("t5", &data).unwrap_err().desc,
+                   "Helper \"repeat\" expected a non-negative integer for param 0 but got object");
+    }
+
     #[derive(Clone, Copy)]
     struct MetaHelper;
 
@@ -123,6 +299,199 @@ mod test {
         assert_eq!(r1.ok().unwrap(), "bar:true->nice".to_string());
     }
 
+    #[test]
+    fn test_typed_subexpression_in_hash() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{show v=(lookup users id)}}").is_ok());
+        assert!(handlebars.register_template_string("t1", "{{show v=(lookup ids 1)}}").is_ok());
+
+        handlebars.register_helper("show",
+                                   Box::new(|h: &Helper,
+                                             _: &Registry,
+                                             rc: &mut RenderContext|
+                                             -> Result<(), RenderError> {
+            let v = h.hash_get("v").unwrap().value();
+            let output = if let Some(m) = v.as_object() {
+                format!("object:{}", m.get("name").unwrap().render())
+            } else if v.is_number() {
+                format!("number:{}", v.render())
+            } else {
+                format!("other:{}", v.render())
+            };
+            try!(rc.writer.write(output.into_bytes().as_ref()));
+            Ok(())
+        }));
+
+        let data = btreemap! {
+            "id".to_string() => to_json(&"u1".to_owned()),
+            "ids".to_string() => to_json(&vec![7, 8]),
+            "users".to_string() => to_json(&btreemap! {
+                "u1".to_string() => btreemap! {
+                    "name".to_string() => "Ning".to_string()
+                }
+            })
+        };
+
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "object:Ning");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "number:8");
+    }
+
+    #[test]
+    fn test_stateful_helpers() {
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct UidHelper {
+            counters: Mutex<HashMap<String, usize>>,
+        }
+
+        impl HelperDef for UidHelper {
+            fn call(&self,
+                    h: &Helper,
+                    _: &Registry,
+                    rc: &mut RenderContext)
+                    -> Result<(), RenderError> {
+                let prefix = try!(h.param_checked(0, "uid")).value().render();
+                let mut counters = self.counters.lock().unwrap();
+                let counter = counters.entry(prefix.clone()).or_insert(0);
+                *counter += 1;
+                try!(rc.writer.write(format!("{}-{}", prefix, counter).into_bytes().as_ref()));
+                Ok(())
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+
+        let mut handlebars = Registry::new();
+        handlebars.register_helper("uid", Box::new(UidHelper::default()));
+        handlebars.register_helper("counted",
+                                   Box::new(move |_: &Helper,
+                                                  _: &Registry,
+                                                  _: &mut RenderContext|
+                                                  -> Result<(), RenderError> {
+            counted.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }));
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#each this}}{{uid \"item\"}} \
+                                                     {{counted this}}{{/each}}{{uid \"list\"}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &vec![1, 2]).unwrap(), "item-1 item-2 list-1");
+        // state is kept across renders
+        assert_eq!(handlebars.render("t0", &vec![1]).unwrap(), "item-3 list-2");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_call_inner_helper() {
+        struct FirstHelper;
+
+        impl HelperDef for FirstHelper {
+            fn call_inner(&self,
+                          h: &Helper,
+                          _: &Registry,
+                          _: &mut RenderContext)
+                          -> Result<Option<Json>, RenderError> {
+                Ok(h.param(0).and_then(|v| v.value().as_array()).and_then(|a| a.first()).cloned())
+            }
+        }
+
+        let mut handlebars = Registry::new();
+        handlebars.register_helper("first", Box::new(FirstHelper));
+        assert!(handlebars.register_template_string("t0", "{{first this}}").is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "{{#each (first this) as |x|}}{{x}}|{{/each}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &vec![1, 2]).unwrap(), "1");
+        assert_eq!(handlebars.render("t1", &vec![vec![1, 2], vec![3]]).unwrap(), "1|2|");
+    }
+
+    #[test]
+    fn test_call_inner_once() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct CountHelper {
+            calls: Arc<AtomicUsize>,
+        }
+
+        impl HelperDef for CountHelper {
+            fn call_inner(&self,
+                          h: &Helper,
+                          _: &Registry,
+                          _: &mut RenderContext)
+                          -> Result<Option<Json>, RenderError> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                Ok(h.param(0).map(|v| v.value().clone()).filter(|v| !v.is_null()))
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut handlebars = Registry::new();
+        handlebars.register_helper("count", Box::new(CountHelper { calls: calls.clone() }));
+        assert!(handlebars.register_template_string("t0", "{{#if (count a)}}y{{else}}n{{/if}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1", "[{{count a}}]").is_ok());
+
+        assert_eq!(handlebars.render("t0", &()).unwrap(), "n");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(handlebars.render("t1", &()).unwrap(), "[]");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(handlebars.render("t0", &btreemap! {"a".to_string() => 1}).unwrap(), "y");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // a helper rendered inside the fallback `call` runs as usual
+        handlebars.register_helper("part",
+                                   Box::new(|h: &Helper,
+                                             r: &Registry,
+                                             rc: &mut RenderContext|
+                                             -> Result<(), RenderError> {
+                                       let name = try!(h.param_checked(0, "part")).value().render();
+                                       match r.get_template(&name) {
+                                           Some(t) => t.render(r, rc),
+                                           None => Ok(()),
+                                       }
+                                   }));
+        assert!(handlebars.register_template_string("p", "{{count 1}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{#if (part \"p\")}}y{{else}}n{{/if}}")
+                    .is_ok());
+        assert_eq!(handlebars.render("t2", &()).unwrap(), "y");
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_helper_block_params() {
+        let mut handlebars = Registry::new();
+        handlebars.register_helper("split",
+                                   Box::new(|h: &Helper,
+                                             r: &Registry,
+                                             rc: &mut RenderContext|
+                                             -> Result<(), RenderError> {
+            let value = h.param(0).unwrap().value().render();
+            let mut map = BTreeMap::new();
+            for (name, part) in h.block_params().into_iter().zip(value.split(' ')) {
+                map.insert(name.to_owned(), part.to_owned());
+            }
+
+            let mut local_rc = rc.derive();
+            local_rc.push_block_context(&map);
+            try!(h.template().unwrap().render(r, &mut local_rc));
+            local_rc.pop_block_context();
+            Ok(())
+        }));
+        assert!(handlebars.register_template_string("t0",
+                                                    "{{#split this as |first middle last|}}{{last}}, {{first}} {{middle}}{{/split}}")
+                    .is_ok());
+
+        assert_eq!(handlebars.render("t0", &"Ning Xiao Sun".to_owned()).unwrap(),
+                   "Sun, Ning Xiao");
+    }
+
     #[test]
     fn test_helper_for_subexpression() {
         let mut handlebars = Registry::new();
@@ -124,7 +124,7 @@
 //! that involves template reference requires you to register those template first with
 //! a name so the registry can find it.
 //!
-//! If you template is small or just to expirement, you can use `template_render` API
+//! If you template is small or just to expirement, you can use `render_template` API
 //! without registration.
 //!
 //! ```
@@ -137,7 +137,7 @@
 //!
 //!   let mut data = BTreeMap::new();
 //!   data.insert("world".to_string(), "世界!".to_string());
-//!   assert_eq!(handlebars.template_render(source, &data).unwrap(),"hello 世界!".to_owned());
+//!   assert_eq!(handlebars.render_template(source, &data).unwrap(),"hello 世界!".to_owned());
 //! }
 //! ```
 //!
@@ -197,7 +197,7 @@
 //!       name: "Ning Sun".to_string(),
 //!       age: 27
 //!   };
-//!   assert_eq!(handlebars.template_render("Hello, {{name}}", &data).unwrap(),
+//!   assert_eq!(handlebars.render_template("Hello, {{name}}", &data).unwrap(),
 //!       "Hello, Ning Sun".to_owned());
 //! }
 //! ```
@@ -206,6 +206,9 @@
 //!
 //! As per the handlebars spec, output using `{{expression}}` is escaped by default (to be precise, the characters `&"<>` are replaced by their respective html / xml entities). However, since the use cases of a rust template engine are probably a bit more diverse than those of a JavaScript one, this implementation allows the user to supply a custom escape function to be used instead. For more information see the `EscapeFn` type and `Handlebars::register_escape_fn()` method.
 //!
+//! For CSV output, use the built-in `csv_escape` with `register_escape_fn` so values rendered by `{{field}}` are quoted as specified in RFC 4180.
+//! For JSON or JavaScript output, `json_escape` makes `"{{field}}"` a valid string literal.
+//!
 //! ### Custom Helper
 //!
 //! Handlebars is nothing without helpers. You can also create your own helpers with rust. Helpers in handlebars-rust are custom struct implements the `HelperDef` trait, concretely, the `call` function. For your convenience, most of stateless helpers can be implemented as bare functions.
@@ -253,7 +256,7 @@
 //!       }));
 //!
 //!   let tpl = "{{simple-helper 1}}\n{{another-simple-helper 2}}\n{{closure-helper 3}}";
-//!   assert_eq!(handlebars.template_render(tpl, &()).unwrap(),
+//!   assert_eq!(handlebars.render_template(tpl, &()).unwrap(),
 //!       "1st helper: 1\n2nd helper: 2\n3rd helper: 3".to_owned());
 //! }
 //! ```
@@ -262,22 +265,50 @@
 //!
 //! You can learn more about helpers by looking into source code of built-in helpers.
 //!
+//! When a helper is not found, the helper registered as `helperMissing`, or
+//! `blockHelperMissing` for a block, is called instead. Its `h.name()` is the
+//! name of the missing helper, so it can skip unknown helpers or report them.
+//!
 //! #### Built-in Helpers
 //!
-//! * `{{#raw}} ... {{/raw}}` escape handlebars expression within the block
+//! * `{{{{raw}}}} ... {{{{/raw}}}}` escape handlebars expression within the block
 //! * `{{#if ...}} ... {{else}} ... {{/if}}` if-else block
 //! * `{{#unless ...}} ... {{else}} .. {{/unless}}` if-not-else block
-//! * `{{#each ...}} ... {{/each}}` iterates over an array or object. Handlebar-rust doesn't support mustach iteration syntax so use this instead.
-//! * `{{#with ...}} ... {{/with}}` change current context. Similar to {{#each}}, used for replace corresponding mustach syntax.
-//! * `{{lookup ... ...}}` get value from array by `@index` or `@key`
+//! * `{{#each ...}} ... {{/each}}` iterates over an array or object. Handlebar-rust doesn't support mustach iteration syntax so use this instead. `@first`, `@last` and `@index` are set in each iteration, and `@key` for objects. Use `accumulate="price"` for a running total of a field as `@running`. Use `@parent` for the context one block up, like `{{@parent.title}}`, and `@root` for the data of the whole render, like `{{@root.site.title}}`.
+//! * `{{#with ...}} ... {{/with}}` change current context. Similar to {{#each}}, used for replace corresponding mustach syntax. Use `{{#with user as |u|}}{{u.name}}{{/with}}` to bind the value to a block param.
+//! * `{{lookup ... ...}}` get value from array by index or from object by key, like `{{lookup list @index}}` or `{{lookup obj keyVar}}`
 //! * `{{> ...}}` include template with name
-//! * `{{log ...}}` log value with rust logger, default level: INFO. Currently you cannot change the level.
+//! * `{{log ...}}` log values with rust logger, like `{{log "user" user.name level="warn"}}`. Default level: INFO.
+//! * `{{eq ... ...}}` renders `true` if the first param equals any of the others, use it as subexpression like `{{#if (eq status "active" "pending")}}`. Add `ignoreCase=true` to compare strings case-insensitively
+//! * `{{gt ... ...}}`, `{{lt ... ...}}`, `{{gte ... ...}}` and `{{lte ... ...}}` compare two numbers or two strings, use them as subexpression like `{{#if (gt count 10)}}`
+//! * `{{filesize ...}}` format a byte count as human-readable size, like `1.2 MB`. Use `binary=true` for 1024 based units and `precision` for decimals
+//! * `{{repeat ...}}` repeat a string N times like `{{repeat "=" 10}}`, or render the block N times like `{{#repeat 3}}...{{/repeat}}`
+//! * `{{t ...}}` render a message of current language registered by `register_messages`, like `{{t "greeting" name=user.name}}`
+//! * `{{chunk ...}}` split an array into arrays of N items, like `{{#each (chunk items 3) as |row|}}`
+//! * `{{and ...}}`, `{{or ...}}` and `{{not ...}}` render `true` for truthy combinations of params, use them as subexpression like `{{#if (and isAdmin (not isBanned))}}`, where they return a boolean
+//! * `{{slug ...}}` turn a string into a URL slug like `my-post-title`. Use `sep` to change the separator and `ascii=true` to transliterate accented letters
+//! * `{{#let ...}} ... {{/let}}` name values within the block, like `{{#let total=(add a b)}}{{total}}{{/let}}`
+//! * `{{json ...}}` serialize a value as JSON, use `pretty=true` for indented output
+//! * `{{difference ... ...}}`, `{{intersection ... ...}}` and `{{union ... ...}}` set operations of two arrays, use them as subexpression like `{{#each (difference new old)}}`
+//! * `{{number ...}}` format a number with thousands separators, like `{{number price decimals=2 thousands=","}}` for `1,234.50`. Use `decimalPoint` to change the decimal separator
+//! * `{{default ...}}` return the first truthy param, or the last one if none is truthy, like `{{default user.nickname user.name "Anonymous"}}`
+//! * `{{range ...}}` return integers from start up to end, like `{{#each (range 1 5)}}{{this}}{{/each}}` for `1234`. Use `step` to skip numbers
+//! * `{{join ...}}` join items of an array with a separator, like `{{join names ", " last=" and "}}` for `a, b and c`
+//! * `{{upper ...}}`, `{{lower ...}}` and `{{capitalize ...}}` change the case of a string, `capitalize` only uppercases the first letter. Use them as subexpression like `{{upper (default name "guest")}}`
+//! * `{{contains ... ...}}` check if an array has an item, an object has a key or a string has a substring, use it as subexpression like `{{#if (contains roles "admin")}}`
+//! * `{{env ...}}` return an environment variable, like `{{env "HOME" default="/tmp"}}`. Only available after `register_env_helper` is called, since it exposes the process environment to templates
+//! * `{{#markdown}} ... {{/markdown}}` render the block and convert it from Markdown to HTML. Requires the `markdown` feature
+//! * `{{date ...}}` format a unix timestamp or RFC3339 string, like `{{date createdAt format="%Y-%m-%d" tz="+08:00"}}`. The default format is RFC3339 in UTC. Requires the `dates` feature
 //!
 //! ### Template inheritance
 //!
 //! Handlebarsjs partial system is fully supported in this implementation.
 //! Check [example](https://github.com/sunng87/handlebars-rust/blob/master/examples/partials.rs#L49) for detail.
 //!
+//! A partial can also be defined from a template string in render data with the
+//! `{{*partial "name" source}}` decorator. The snippet is rendered with the same
+//! data and helpers as the template, so don't use it for untrusted content.
+//!
 //!
 
 #![allow(dead_code)]
@@ -304,23 +335,32 @@ extern crate rustc_serialize as serialize;
 #[cfg(feature = "serde_type")]
 extern crate serde_json;
 
-pub use self::template::Template;
+#[cfg(feature = "markdown")]
+extern crate pulldown_cmark;
+#[cfg(feature = "dates")]
+extern crate chrono;
+
+pub use self::template::{Template, TemplateMapping};
 pub use self::error::{TemplateError, TemplateFileError, TemplateRenderError};
-pub use self::registry::{EscapeFn, no_escape, html_escape, Registry as Handlebars};
-pub use self::render::{Renderable, Evaluable, RenderError, RenderContext, Helper, ContextJson,
-                       Directive as Decorator};
+pub use self::registry::{EscapeFn, EscapeMode, TruthyFn, NumberFormatFn, LineEnding, NumberLocale,
+                         Registry as Handlebars};
+pub use self::escape::{no_escape, html_escape, csv_escape, json_escape};
+pub use self::render::{Renderable, Evaluable, RenderError, RenderErrorKind, RenderContext, Helper,
+                       ContextJson, Directive as Decorator};
 pub use self::helpers::HelperDef;
 pub use self::directives::DirectiveDef as DecoratorDef;
-pub use self::context::{Context, JsonRender, to_json};
+pub use self::context::{Context, JsonRender, ValueType, to_json, value_type};
+pub use self::profile::{RenderProfile, ProfileEntry};
 
 mod grammar;
 mod template;
 mod error;
 mod registry;
+mod escape;
 mod render;
 mod helpers;
 mod context;
 mod support;
+mod profile;
 mod directives;
-#[cfg(not(feature="partial_legacy"))]
 mod partial;
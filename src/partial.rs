@@ -1,9 +1,87 @@
+use std::borrow::Cow;
+#[cfg(not(feature="partial_legacy"))]
 use std::collections::BTreeMap;
+#[cfg(not(feature="partial_legacy"))]
 use std::iter::FromIterator;
 
 use registry::Registry;
-use render::{RenderError, RenderContext, Directive, Evaluable, Renderable};
+#[cfg(not(feature="partial_legacy"))]
+use context::Context;
+use render::{RenderError, RenderErrorKind, RenderContext, Renderable};
+#[cfg(not(feature="partial_legacy"))]
+use render::{Directive, Evaluable};
+use support::str::StringWriter;
+use template::Template;
 
+/// A partial found by `find_partial`
+pub struct FoundPartial<'a> {
+    /// name of the partial with aliases resolved
+    pub name: &'a str,
+    pub template: Cow<'a, Template>,
+    /// partial depth to render the template with
+    pub depth: usize,
+    /// true if output of the partial may be served from the partial cache
+    pub cacheable: bool,
+}
+
+/// Find the template of `{{> name}}` and check it may be included here
+///
+/// Partials defined in templates, like with `{{#*inline}}`, are found before
+/// templates of registry, which are reloaded in dev mode. `fallback`, the
+/// content of a partial block, is used when neither is found. Returns an error
+/// if the partial is the current template or nested too deep, or if it's not
+/// found in strict mode.
+pub fn find_partial<'a>(name: &'a str,
+                        fallback: Option<&'a Template>,
+                        r: &'a Registry,
+                        rc: &RenderContext)
+                        -> Result<Option<FoundPartial<'a>>, RenderError> {
+    let tname = try!(r.resolve_partial_alias(name));
+    if rc.is_current_template(name) || rc.is_current_template(tname) {
+        return Err(RenderError::new("Cannot include self in >"));
+    }
+
+    let inline = rc.get_partial(name);
+    // only partials of registry are cached, not inline ones of the same name
+    // or partial blocks
+    let cacheable = inline.is_none() && fallback.is_none() && r.is_partial_cacheable(tname) &&
+                    !r.is_dev_mode();
+    let template = match inline {
+        Some(t) => Cow::Owned(t),
+        None => {
+            match try!(r.reload_template(tname)) {
+                Some(t) => Cow::Owned(t),
+                None => {
+                    match r.get_template(tname).or(fallback) {
+                        Some(t) => Cow::Borrowed(t),
+                        None if r.is_strict_mode() => {
+                            let kind = RenderErrorKind::PartialNotFound(name.to_owned());
+                            return Err(RenderError::from_kind(kind));
+                        }
+                        None => return Ok(None),
+                    }
+                }
+            }
+        }
+    };
+
+    let depth = rc.get_partial_depth() + 1;
+    if depth > r.get_max_render_depth() {
+        return Err(rc.error(format!("Partial {:?} exceeds max render depth {}, it may include \
+                                     itself",
+                                    name,
+                                    r.get_max_render_depth())));
+    }
+
+    Ok(Some(FoundPartial {
+                name: tname,
+                template: template,
+                depth: depth,
+                cacheable: cacheable,
+            }))
+}
+
+#[cfg(not(feature="partial_legacy"))]
 pub fn expand_partial(d: &Directive,
                       r: &Registry,
                       rc: &mut RenderContext)
@@ -14,51 +92,124 @@ pub fn expand_partial(d: &Directive,
         try!(t.eval(r, rc));
     }
 
-    if rc.is_current_template(d.name()) {
-        return Err(RenderError::new("Cannot include self in >"));
+    // a partial block renders its own content when the partial is not found,
+    // like `{{#> missing}}default{{/missing}}`
+    let found = match try!(find_partial(d.name(), d.template(), r, rc)) {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+
+    let mut local_rc = rc.derive();
+    local_rc.set_partial_depth(found.depth);
+    let context_param = d.params().get(0).and_then(|p| p.path());
+    if let Some(p) = context_param {
+        let old_path = local_rc.get_path().clone();
+        local_rc.promote_local_vars();
+        let new_path = format!("{}/{}", old_path, p);
+        local_rc.set_path(new_path);
+    };
+
+    // @partial-block
+    if let Some(t) = d.template() {
+        local_rc.set_partial("@partial-block".to_string(), t.clone());
+    }
+
+    // hash params are merged onto the current context, as a new
+    // context of the partial, like `{{> card user class="big"}}`
+    let hash = d.hash();
+    if !hash.is_empty() {
+        let hash_ctx =
+            BTreeMap::from_iter(hash.iter().map(|(k, v)| (k.clone(), v.value().clone())));
+        let mut partial_ctx = {
+            Context::wraps(local_rc.current_context_value()).extend(&hash_ctx)
+        };
+        let mut partial_rc = local_rc.derive_with_context(&mut partial_ctx);
+        return render_partial_template(d, &found, r, &mut partial_rc);
     }
 
+    render_partial_template(d, &found, r, &mut local_rc)
+}
 
-    let tname = d.name();
-    let partial = rc.get_partial(tname);
-    let render_template = partial.as_ref().or(r.get_template(tname)).or(d.template());
-    match render_template {
-        Some(t) => {
+#[cfg(not(feature="partial_legacy"))]
+fn render_partial_template(d: &Directive,
+                           found: &FoundPartial,
+                           r: &Registry,
+                           rc: &mut RenderContext)
+                           -> Result<(), RenderError> {
+    if let Some(indent) = d.indent() {
+        let mut sw = StringWriter::new();
+        {
             let mut local_rc = rc.derive();
-            let context_param = d.params().get(0).and_then(|p| p.path());
-            if let Some(p) = context_param {
-                let old_path = local_rc.get_path().clone();
-                local_rc.promote_local_vars();
-                let new_path = format!("{}/{}", old_path, p);
-                local_rc.set_path(new_path);
-            };
-
-            // @partial-block
-            if let Some(t) = d.template() {
-                local_rc.set_partial("@partial-block".to_string(), t.clone());
-            }
+            local_rc.writer = &mut sw;
+            try!(render_partial_output(found, r, &mut local_rc));
+        }
+        let output = indent_lines(&try!(sw.into_string()), indent);
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        return Ok(());
+    }
 
-            let hash = d.hash();
-            let r = if hash.is_empty() {
-                t.render(r, &mut local_rc)
-            } else {
-                let hash_ctx =
-                    BTreeMap::from_iter(hash.iter().map(|(k, v)| (k.clone(), v.value().clone())));
-                {
-                    let mut ctx_ref = local_rc.context_mut();
-                    *ctx_ref = ctx_ref.extend(&hash_ctx);
-                }
-                t.render(r, &mut local_rc)
-            };
+    render_partial_output(found, r, rc)
+}
 
-            r
+/// Render a found partial, from the partial cache if it's cacheable
+pub fn render_partial_output(found: &FoundPartial,
+                             r: &Registry,
+                             rc: &mut RenderContext)
+                             -> Result<(), RenderError> {
+    match rc.partial_cache_key() {
+        Some(key) if found.cacheable => {
+            render_cached_partial(found.name, &found.template, key, r, rc)
         }
-        None => Ok(()),
+        _ => found.template.render(r, rc),
     }
+}
+
+/// Prefix every line of partial output with indentation, except the empty
+/// line after a final line break
+#[cfg(not(feature="partial_legacy"))]
+fn indent_lines(output: &str, indent: &str) -> String {
+    let mut indented = String::with_capacity(output.len());
+    let mut line_start = true;
+    for c in output.chars() {
+        if line_start {
+            indented.push_str(indent);
+        }
+        indented.push(c);
+        line_start = c == '\n';
+    }
+    indented
+}
 
+/// Render a cacheable partial, reusing output cached in registry for the same
+/// context
+fn render_cached_partial(name: &str,
+                         t: &Template,
+                         key: String,
+                         r: &Registry,
+                         rc: &mut RenderContext)
+                         -> Result<(), RenderError> {
+    let output = match r.get_cached_partial(name, &key) {
+        Some(output) => output,
+        None => {
+            let mut sw = StringWriter::new();
+            {
+                let mut local_rc = rc.derive();
+                local_rc.writer = &mut sw;
+                try!(t.render(r, &mut local_rc));
+            }
+            let output = try!(sw.into_string());
+            if !rc.is_halted() {
+                r.set_cached_partial(name, key, output.clone());
+            }
+            output
+        }
+    };
+
+    try!(rc.writer.write(output.into_bytes().as_ref()));
+    Ok(())
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature="partial_legacy")))]
 mod test {
     use registry::Registry;
 
@@ -91,6 +242,30 @@ mod test {
         assert_eq!(handlebars.render("t9", &1).ok().unwrap(), "2".to_string());
     }
 
+    #[test]
+    fn test_partial_hash_context() {
+        use context::to_json;
+
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("card", "{{name}}:{{class}};").is_ok());
+        assert!(handlebars.register_template_string("t0", "{{> card user class=\"big\"}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1", "{{#each people}}{{> card class=@index}}{{/each}}").is_ok());
+        assert!(handlebars.register_template_string("t2", "{{> card user}}|{{> card name=\"x\"}}|{{name}}").is_ok());
+
+        let person = |name: &str| btreemap! {"name".to_string() => name.to_string()};
+        let data = btreemap! {
+            "name".to_string() => to_json(&"root".to_owned()),
+            "user".to_string() => to_json(&person("u")),
+            "people".to_string() => to_json(&vec![person("p0"), person("p1")])
+        };
+
+        assert_eq!(handlebars.render("t0", &data).unwrap(), "u:big;");
+        assert_eq!(handlebars.render("t1", &data).unwrap(), "p0:0;p1:1;");
+        // hash params don't leak out of the partial
+        assert_eq!(handlebars.render("t2", &data).unwrap(), "u:;|x:;|root");
+    }
+
     #[test]
     fn test_include_partial_block() {
         let t0 = "hello {{> @partial-block}}";
@@ -116,6 +291,25 @@ mod test {
         assert!(r0.is_err());
     }
 
+    #[test]
+    fn test_recursive_partials() {
+        let mut handlebars = Registry::new();
+        assert!(handlebars.register_template_string("t0", "{{> a}}").is_ok());
+        assert!(handlebars.register_template_string("a", "a{{> b}}").is_ok());
+        assert!(handlebars.register_template_string("b", "b{{> a}}").is_ok());
+
+        let err = handlebars.render("t0", &true).unwrap_err();
+        assert_eq!(err.desc,
+                   "Partial \"a\" exceeds max render depth 100, it may include itself");
+
+        handlebars.set_max_render_depth(3);
+        assert_eq!(handlebars.render("t0", &true).unwrap_err().desc,
+                   "Partial \"b\" exceeds max render depth 3, it may include itself");
+
+        assert!(handlebars.register_template_string("b", "b{{#if false}}{{> a}}{{/if}}").is_ok());
+        assert_eq!(handlebars.render("t0", &true).unwrap(), "ab");
+    }
+
     #[test]
     fn test_issue_143() {
         let main_template = "one{{> two }}three{{> two }}";
@@ -128,4 +322,47 @@ mod test {
         let r0 = handlebars.render("template", &true);
         assert_eq!(r0.ok().unwrap(), "one--- two ---three--- two ---");
     }
+
+    #[test]
+    fn test_indent_partials() {
+        let mut handlebars = Registry::new();
+        handlebars.set_indent_partials(true);
+        assert!(handlebars.register_template_string("t0", "a:\n  {{> p}}\nb:\n  {{> p x}}c\n")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "list:\n{{#each this}}\n  {{> p}}\n{{/each}}")
+                    .is_ok());
+        assert!(handlebars.register_template_string("p", "x: 1\n\ny: 2\n").is_ok());
+
+        assert_eq!(handlebars.render("t0", &true).unwrap(),
+                   "a:\n  x: 1\n  \n  y: 2\nb:\n  x: 1\n\ny: 2\nc\n");
+        assert_eq!(handlebars.render("t1", &vec![1, 2]).unwrap(),
+                   "list:\n\n  x: 1\n  \n  y: 2\n\n  x: 1\n  \n  y: 2\n");
+
+        // templates registered with it disabled keep the text around partials
+        handlebars.set_indent_partials(false);
+        assert!(handlebars.register_template_string("t0", "a:\n  {{> p}}\nb").is_ok());
+        assert_eq!(handlebars.render("t0", &true).unwrap(), "a:\n  x: 1\n\ny: 2\n\nb");
+    }
+
+    #[test]
+    fn test_indent_partials_in_standalone_blocks() {
+        let mut handlebars = Registry::new();
+        handlebars.set_strip_standalone_blocks(true);
+        handlebars.set_indent_partials(true);
+        assert!(handlebars.register_template_string("t0", "{{#if this}}\n  {{> p}}\n{{/if}}\n")
+                    .is_ok());
+        assert!(handlebars.register_template_string("t1",
+                                                    "list:\n{{#each this}}\n  {{> p}}\n{{/each}}\n")
+                    .is_ok());
+        assert!(handlebars.register_template_string("p", "x: 1\ny: 2\n").is_ok());
+
+        assert_eq!(handlebars.render("t0", &true).unwrap(), "  x: 1\n  y: 2\n");
+        assert_eq!(handlebars.render("t0", &false).unwrap(), "");
+        assert_eq!(handlebars.render("t1", &vec![1, 2]).unwrap(),
+                   "list:\n  x: 1\n  y: 2\n  x: 1\n  y: 2\n");
+        assert_eq!(handlebars.render_template("{{#if this}}\n  {{> p}}\n{{/if}}\n", &true)
+                       .unwrap(),
+                   "  x: 1\n  y: 2\n");
+    }
 }
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Calls and time spent in a helper or partial
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ProfileEntry {
+    /// number of calls
+    pub calls: usize,
+    /// cumulative time, including helpers and partials nested in it
+    pub duration: Duration,
+}
+
+/// Report of time spent in each helper and partial during a render
+///
+/// Helpers are recorded by name. Partials are prefixed with `>`, like `> header`.
+#[derive(Debug, Clone, Default)]
+pub struct RenderProfile {
+    entries: BTreeMap<String, ProfileEntry>,
+}
+
+impl RenderProfile {
+    pub fn new() -> RenderProfile {
+        RenderProfile { entries: BTreeMap::new() }
+    }
+
+    /// Add a call and its duration to the entry of `name`
+    pub fn record(&mut self, name: &str, duration: Duration) {
+        if let Some(entry) = self.entries.get_mut(name) {
+            entry.calls += 1;
+            entry.duration += duration;
+            return;
+        }
+        self.entries.insert(name.to_owned(),
+                            ProfileEntry {
+                                calls: 1,
+                                duration: duration,
+                            });
+    }
+
+    /// Return the entry of a helper or partial
+    pub fn get(&self, name: &str) -> Option<&ProfileEntry> {
+        self.entries.get(name)
+    }
+
+    /// Return all entries, ordered by name
+    pub fn entries(&self) -> &BTreeMap<String, ProfileEntry> {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use profile::RenderProfile;
+    use std::time::Duration;
+
+    #[test]
+    fn test_record() {
+        let mut profile = RenderProfile::new();
+        profile.record("each", Duration::from_millis(2));
+        profile.record("each", Duration::from_millis(3));
+        profile.record("> row", Duration::from_millis(1));
+
+        let each = profile.get("each").unwrap();
+        assert_eq!(each.calls, 2);
+        assert_eq!(each.duration, Duration::from_millis(5));
+        assert_eq!(profile.get("> row").unwrap().calls, 1);
+        assert!(profile.get("if").is_none());
+        assert_eq!(profile.entries().keys().collect::<Vec<_>>(), vec!["> row", "each"]);
+    }
+}
@@ -1,27 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::io::prelude::*;
-use std::fs::File;
-use std::path::Path;
+use std::io::Error as IOError;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::ptr;
 
 #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
-use serialize::json::ToJson;
+use serialize::json::{Json, ToJson};
 #[cfg(feature = "serde_type")]
-use serde_json::value::ToJson;
-
-use regex::{Regex, Captures};
+use serde_json::value::{Value as Json, ToJson};
 
 use template::Template;
-use render::{Renderable, RenderError, RenderContext};
-use context::Context;
+use render::{Renderable, RenderError, RenderErrorKind, RenderContext};
+use context::{self, Context, JsonRender, JsonTruthy};
+use profile::RenderProfile;
 use helpers::{self, HelperDef};
 use directives::{self, DirectiveDef};
 use support::str::StringWriter;
+use support::line_ending::LineEndingWriter;
 use error::{TemplateError, TemplateFileError, TemplateRenderError};
+use escape::{html_escape, no_escape};
 
 
-lazy_static!{
-    static ref DEFAULT_REPLACE: Regex = Regex::new(">|<|\"|&").unwrap();
-}
+static UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Number of partial outputs cached before the cache is cleared
+const PARTIAL_CACHE_CAPACITY: usize = 1024;
 
 /// This type represents an *escape fn*, that is a function who's purpose it is
 /// to escape potentially problematic characters in a string.
@@ -30,26 +37,140 @@ lazy_static!{
 /// parameters (and because traits cannot be aliased using `type`).
 pub type EscapeFn = Box<Fn(&str) -> String + Send + Sync>;
 
-/// The default *escape fn* replaces the characters `&"<>`
-/// with the equivalent html / xml entities.
-pub fn html_escape(data: &str) -> String {
-    DEFAULT_REPLACE.replace_all(data, |cap: &Captures| {
-            match cap.get(0).map(|m| m.as_str()) {
-                    Some("<") => "&lt;",
-                    Some(">") => "&gt;",
-                    Some("\"") => "&quot;",
-                    Some("&") => "&amp;",
-                    _ => unreachable!(),
-                }
-                .to_owned()
-        })
-        .into_owned()
+/// This type represents a *truthy fn*, deciding if a value is considered true
+/// by `if`, `unless`, `each` and `with`.
+pub type TruthyFn = Box<Fn(&Json) -> bool + Send + Sync>;
+
+/// This type represents a *number format fn*, rendering numbers of
+/// expressions like `{{price}}`.
+pub type NumberFormatFn = Box<Fn(&Json) -> String + Send + Sync>;
+
+/// Escaping of expression output for a single render, used by `Registry::render_full`
+#[derive(Clone, Copy)]
+pub enum EscapeMode {
+    /// Escape with `html_escape`
+    Html,
+    /// Don't escape, like `no_escape`
+    NoEscape,
+    /// Escape with a given function, like `csv_escape`
+    Custom(fn(&str) -> String),
+}
+
+impl EscapeMode {
+    pub fn escape(&self, data: &str) -> String {
+        match *self {
+            EscapeMode::Html => html_escape(data),
+            EscapeMode::NoEscape => no_escape(data),
+            EscapeMode::Custom(f) => f(data),
+        }
+    }
+}
+
+/// Line ending style of rendered output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    /// Keep line endings as they are in templates and data
+    Preserve,
+    /// Convert all line endings to `\n`
+    Lf,
+    /// Convert all line endings to `\r\n`
+    CrLf,
+}
+
+/// Separators for rendering numbers, used by `Registry::set_number_locale`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberLocale {
+    /// separator between groups of thousands, like `,` in `1,234`
+    pub group_separator: String,
+    /// separator of the fractional part, like `.` in `0.5`
+    pub decimal_separator: String,
+}
+
+impl NumberLocale {
+    pub fn new<S: Into<String>>(group_separator: S, decimal_separator: S) -> NumberLocale {
+        NumberLocale {
+            group_separator: group_separator.into(),
+            decimal_separator: decimal_separator.into(),
+        }
+    }
+
+    /// Format a number rendered in plain format, like `-1234.5`
+    ///
+    /// Numbers in exponent notation are returned as they are.
+    pub fn format(&self, number: &str) -> String {
+        if number.contains(&['e', 'E'][..]) {
+            return number.to_owned();
+        }
+
+        let (sign, unsigned) = if number.starts_with('-') {
+            ("-", &number[1..])
+        } else {
+            ("", number)
+        };
+        let (int_part, frac_part) = match unsigned.find('.') {
+            Some(idx) => (&unsigned[..idx], Some(&unsigned[idx + 1..])),
+            None => (unsigned, None),
+        };
+
+        let mut buf = String::from(sign);
+        for (i, c) in int_part.chars().enumerate() {
+            if i > 0 && (int_part.len() - i) % 3 == 0 {
+                buf.push_str(&self.group_separator);
+            }
+            buf.push(c);
+        }
+        if let Some(frac) = frac_part {
+            buf.push_str(&self.decimal_separator);
+            buf.push_str(frac);
+        }
+        buf
+    }
+}
+
+/// Options of a single render call
+#[derive(Default)]
+struct RenderOptions<'a> {
+    /// path of data used as root
+    path: Option<&'a str>,
+    /// partials taking precedence over registered templates
+    partials: Option<&'a HashMap<String, Template>>,
+    /// collects time spent in helpers and partials
+    profile: Option<Rc<RefCell<RenderProfile>>>,
+    /// local variables set for the root template
+    locals: Option<&'a HashMap<String, Json>>,
+    /// escaping in place of the registered *escape fn*
+    escape: Option<EscapeMode>,
+}
+
+/// Collect files with extension in a directory recursively
+fn find_template_files(dir: &Path,
+                       extension: &str,
+                       files: &mut Vec<PathBuf>)
+                       -> ::std::io::Result<()> {
+    for entry in try!(fs::read_dir(dir)) {
+        let path = try!(entry).path();
+        if path.is_dir() {
+            try!(find_template_files(&path, extension, files));
+        } else if path.extension().map(|e| e == extension) == Some(true) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Prefix the message of an IO error with the file it's from
+fn io_error_in_file(e: IOError, path: &Path) -> IOError {
+    IOError::new(e.kind(), format!("{}: {}", path.display(), e))
 }
 
-/// `EscapeFn` that donot change any thing. Useful when using in a non-html
-/// environment.
-pub fn no_escape(data: &str) -> String {
-    data.to_owned()
+/// Name of a template file by its path relative to root, with `/` separators and
+/// without extension
+fn template_name_in_dir(root: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file).with_extension("");
+    let segs: Vec<String> = relative.components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    segs.join("/")
 }
 
 /// The single entry point of your Handlebars templates
@@ -57,20 +178,68 @@ pub fn no_escape(data: &str) -> String {
 /// It maintains compiled templates and registered helpers.
 pub struct Registry {
     templates: HashMap<String, Template>,
+    template_files: HashMap<String, PathBuf>,
     helpers: HashMap<String, Box<HelperDef + 'static>>,
+    memoized_helpers: HashSet<String>,
+    cacheable_partials: HashSet<String>,
+    partial_aliases: HashMap<String, String>,
+    partial_cache: Mutex<HashMap<(String, String), String>>,
+    static_outputs: HashMap<String, String>,
     directives: HashMap<String, Box<DirectiveDef + 'static>>,
     escape_fn: EscapeFn,
+    truthy_fn: Option<TruthyFn>,
+    no_escape: bool,
     source_map: bool,
+    line_ending: LineEnding,
+    helper_path_fallback: bool,
+    strict_mode: bool,
+    max_render_depth: usize,
+    max_path_segments: usize,
+    max_repeat_count: u64,
+    max_repeat_output: usize,
+    strip_standalone_blocks: bool,
+    indent_partials: bool,
+    dev_mode: bool,
+    bom: bool,
+    messages: HashMap<String, HashMap<String, String>>,
+    language: Option<String>,
+    profiling: bool,
+    number_locale: Option<NumberLocale>,
+    number_format_fn: Option<NumberFormatFn>,
 }
 
 impl Registry {
     pub fn new() -> Registry {
         let r = Registry {
             templates: HashMap::new(),
+            template_files: HashMap::new(),
             helpers: HashMap::new(),
+            memoized_helpers: HashSet::new(),
+            cacheable_partials: HashSet::new(),
+            partial_aliases: HashMap::new(),
+            partial_cache: Mutex::new(HashMap::new()),
+            static_outputs: HashMap::new(),
             directives: HashMap::new(),
             escape_fn: Box::new(html_escape),
+            truthy_fn: None,
+            no_escape: false,
             source_map: true,
+            line_ending: LineEnding::Preserve,
+            helper_path_fallback: false,
+            strict_mode: false,
+            max_render_depth: 100,
+            max_path_segments: context::DEFAULT_MAX_PATH_SEGMENTS,
+            max_repeat_count: 10000,
+            max_repeat_output: 1024 * 1024,
+            strip_standalone_blocks: false,
+            indent_partials: false,
+            dev_mode: false,
+            bom: false,
+            messages: HashMap::new(),
+            language: None,
+            profiling: false,
+            number_locale: None,
+            number_format_fn: None,
         };
 
         r.setup_builtins()
@@ -88,8 +257,39 @@ impl Registry {
         self.register_helper("block", Box::new(helpers::BLOCK_HELPER));
         self.register_helper("partial", Box::new(helpers::PARTIAL_HELPER));
         self.register_helper("log", Box::new(helpers::LOG_HELPER));
+        self.register_helper("filesize", Box::new(helpers::FILESIZE_HELPER));
+        self.register_helper("eq", Box::new(helpers::EQ_HELPER));
+        self.register_helper("repeat", Box::new(helpers::REPEAT_HELPER));
+        self.register_helper("t", Box::new(helpers::TRANSLATE_HELPER));
+        self.register_helper("chunk", Box::new(helpers::CHUNK_HELPER));
+        self.register_helper("and", Box::new(helpers::AND_HELPER));
+        self.register_helper("or", Box::new(helpers::OR_HELPER));
+        self.register_helper("not", Box::new(helpers::NOT_HELPER));
+        self.register_helper("slug", Box::new(helpers::SLUG_HELPER));
+        self.register_helper("let", Box::new(helpers::LET_HELPER));
+        self.register_helper("json", Box::new(helpers::JSON_HELPER));
+        self.register_helper("difference", Box::new(helpers::DIFFERENCE_HELPER));
+        self.register_helper("intersection", Box::new(helpers::INTERSECTION_HELPER));
+        self.register_helper("union", Box::new(helpers::UNION_HELPER));
+        self.register_helper("number", Box::new(helpers::NUMBER_HELPER));
+        self.register_helper("default", Box::new(helpers::DEFAULT_HELPER));
+        self.register_helper("range", Box::new(helpers::RANGE_HELPER));
+        self.register_helper("join", Box::new(helpers::JOIN_HELPER));
+        self.register_helper("gt", Box::new(helpers::GT_HELPER));
+        self.register_helper("lt", Box::new(helpers::LT_HELPER));
+        self.register_helper("gte", Box::new(helpers::GTE_HELPER));
+        self.register_helper("lte", Box::new(helpers::LTE_HELPER));
+        self.register_helper("upper", Box::new(helpers::UPPER_HELPER));
+        self.register_helper("lower", Box::new(helpers::LOWER_HELPER));
+        self.register_helper("capitalize", Box::new(helpers::CAPITALIZE_HELPER));
+        self.register_helper("contains", Box::new(helpers::CONTAINS_HELPER));
+        #[cfg(feature = "markdown")]
+        self.register_helper("markdown", Box::new(helpers::MARKDOWN_HELPER));
+        #[cfg(feature = "dates")]
+        self.register_helper("date", Box::new(helpers::DATE_HELPER));
 
         self.register_decorator("inline", Box::new(directives::INLINE_DIRECTIVE));
+        self.register_decorator("partial", Box::new(directives::PARTIAL_DIRECTIVE));
         self
     }
 
@@ -102,8 +302,39 @@ impl Registry {
         self.register_helper("lookup", Box::new(helpers::LOOKUP_HELPER));
         self.register_helper("raw", Box::new(helpers::RAW_HELPER));
         self.register_helper("log", Box::new(helpers::LOG_HELPER));
+        self.register_helper("filesize", Box::new(helpers::FILESIZE_HELPER));
+        self.register_helper("eq", Box::new(helpers::EQ_HELPER));
+        self.register_helper("repeat", Box::new(helpers::REPEAT_HELPER));
+        self.register_helper("t", Box::new(helpers::TRANSLATE_HELPER));
+        self.register_helper("chunk", Box::new(helpers::CHUNK_HELPER));
+        self.register_helper("and", Box::new(helpers::AND_HELPER));
+        self.register_helper("or", Box::new(helpers::OR_HELPER));
+        self.register_helper("not", Box::new(helpers::NOT_HELPER));
+        self.register_helper("slug", Box::new(helpers::SLUG_HELPER));
+        self.register_helper("let", Box::new(helpers::LET_HELPER));
+        self.register_helper("json", Box::new(helpers::JSON_HELPER));
+        self.register_helper("difference", Box::new(helpers::DIFFERENCE_HELPER));
+        self.register_helper("intersection", Box::new(helpers::INTERSECTION_HELPER));
+        self.register_helper("union", Box::new(helpers::UNION_HELPER));
+        self.register_helper("number", Box::new(helpers::NUMBER_HELPER));
+        self.register_helper("default", Box::new(helpers::DEFAULT_HELPER));
+        self.register_helper("range", Box::new(helpers::RANGE_HELPER));
+        self.register_helper("join", Box::new(helpers::JOIN_HELPER));
+        self.register_helper("gt", Box::new(helpers::GT_HELPER));
+        self.register_helper("lt", Box::new(helpers::LT_HELPER));
+        self.register_helper("gte", Box::new(helpers::GTE_HELPER));
+        self.register_helper("lte", Box::new(helpers::LTE_HELPER));
+        self.register_helper("upper", Box::new(helpers::UPPER_HELPER));
+        self.register_helper("lower", Box::new(helpers::LOWER_HELPER));
+        self.register_helper("capitalize", Box::new(helpers::CAPITALIZE_HELPER));
+        self.register_helper("contains", Box::new(helpers::CONTAINS_HELPER));
+        #[cfg(feature = "markdown")]
+        self.register_helper("markdown", Box::new(helpers::MARKDOWN_HELPER));
+        #[cfg(feature = "dates")]
+        self.register_helper("date", Box::new(helpers::DATE_HELPER));
 
         self.register_decorator("inline", Box::new(directives::INLINE_DIRECTIVE));
+        self.register_decorator("partial", Box::new(directives::PARTIAL_DIRECTIVE));
         self
     }
 
@@ -117,6 +348,256 @@ impl Registry {
         self.source_map = enable;
     }
 
+    /// Normalize line endings of rendered output
+    ///
+    /// Templates authored on different platforms may contain mixed `\n` and
+    /// `\r\n`. With `LineEnding::Lf` or `LineEnding::CrLf`, every line ending
+    /// written by a render call is converted to the given style.
+    ///
+    /// Default is `LineEnding::Preserve`.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+        self.clear_partial_cache();
+    }
+
+    /// Write a UTF-8 byte order mark at the beginning of rendered output
+    ///
+    /// Some tools, like Excel for CSV files, expect a BOM to detect UTF-8. The
+    /// BOM is written once by each render call of the registry, not by partials.
+    ///
+    /// Default is false.
+    pub fn emit_bom(&mut self, enable: bool) {
+        self.bom = enable;
+    }
+
+    /// Set separators for numbers rendered by expressions like `{{price}}`
+    ///
+    /// Numbers passed to helpers are not affected. Default is `None`, numbers
+    /// are rendered as they are, like `1234567.5`.
+    pub fn set_number_locale(&mut self, locale: Option<NumberLocale>) {
+        self.number_locale = locale;
+        self.clear_partial_cache();
+    }
+
+    /// Return the separators for rendering numbers, if set
+    pub fn get_number_locale(&self) -> Option<&NumberLocale> {
+        self.number_locale.as_ref()
+    }
+
+    /// Set a *number format fn* for numbers rendered by expressions like
+    /// `{{price}}`
+    ///
+    /// The function is called with number values only, like
+    /// `|n| format!("{:.2}", n.as_f64().unwrap())` for two decimals. Separators
+    /// of the number locale, if set, are applied to its output. Numbers passed
+    /// to helpers are not affected.
+    ///
+    /// By default, numbers are rendered as they are, like `0.30000000000000004`.
+    pub fn set_number_format_fn<F: 'static + Fn(&Json) -> String + Send + Sync>(&mut self,
+                                                                                format_fn: F) {
+        self.number_format_fn = Some(Box::new(format_fn));
+        self.clear_partial_cache();
+    }
+
+    /// Restore the default rendering of numbers
+    pub fn unset_number_format_fn(&mut self) {
+        self.number_format_fn = None;
+        self.clear_partial_cache();
+    }
+
+    /// Render a number value of an expression with the *number format fn* and
+    /// number locale
+    pub fn format_number(&self, value: &Json) -> String {
+        let rendered = match self.number_format_fn {
+            Some(ref f) => f(value),
+            None => value.render(),
+        };
+        match self.number_locale {
+            Some(ref locale) => locale.format(&rendered),
+            None => rendered,
+        }
+    }
+
+    /// Turn on profiling of helpers and partials for `render_with_profile`
+    ///
+    /// Default is false, and renders are not timed at all.
+    pub fn enable_profiling(&mut self, enable: bool) {
+        self.profiling = enable;
+    }
+
+    /// Render `{{foo bar}}` as the path `foo` when `foo` is not a helper
+    ///
+    /// Useful when `foo` is likely data mistakenly written with an argument.
+    /// The fallback only applies to non-block expressions with a single param
+    /// and no hash, and only if no `helperMissing` is registered. Arguments are
+    /// ignored, and a missing value renders as empty string.
+    ///
+    /// Default is false, an undefined helper is an error.
+    pub fn helper_path_fallback_enable(&mut self, enable: bool) {
+        self.helper_path_fallback = enable;
+        self.clear_partial_cache();
+    }
+
+    /// Return true if undefined helpers fall back to path lookup
+    pub fn is_helper_path_fallback_enabled(&self) -> bool {
+        self.helper_path_fallback
+    }
+
+    /// Return an error when an expression like `{{foo.bar}}` refers to a
+    /// value that doesn't exist in the data, instead of rendering empty string
+    ///
+    /// A value that exists but is null still renders as empty string. Params
+    /// of helpers are not checked. A missing partial like `{{> missing}}` is
+    /// an error too, while a partial block `{{#> missing}}..{{/missing}}`
+    /// renders its own content.
+    ///
+    /// Default is false.
+    pub fn set_strict_mode(&mut self, enable: bool) {
+        self.strict_mode = enable;
+        self.clear_partial_cache();
+    }
+
+    /// Return true if strict mode is enabled
+    pub fn is_strict_mode(&self) -> bool {
+        self.strict_mode
+    }
+
+    /// Set how many partials may be nested in a render, like `{{> a}}` in a
+    /// partial included by another one
+    ///
+    /// A partial including itself, directly or through other partials, fails
+    /// with an error at this depth instead of overflowing the stack. Not
+    /// checked for the `partial_legacy` partial helpers.
+    ///
+    /// Default is 100.
+    pub fn set_max_render_depth(&mut self, depth: usize) {
+        self.max_render_depth = depth;
+        self.clear_partial_cache();
+    }
+
+    /// Return the max depth of nested partials
+    pub fn get_max_render_depth(&self) -> usize {
+        self.max_render_depth
+    }
+
+    /// Set how many segments, counting `..`, a path like `a.b.c` may have
+    ///
+    /// A longer path, which is likely from an untrusted template, is not
+    /// navigated and resolves to null, so it can't make a render do unbounded
+    /// work. `Context::navigate` called outside of rendering uses the default.
+    ///
+    /// Default is 256.
+    pub fn set_max_path_segments(&mut self, max_segments: usize) {
+        self.max_path_segments = max_segments;
+        self.clear_partial_cache();
+    }
+
+    /// Return the max number of segments of a path
+    pub fn get_max_path_segments(&self) -> usize {
+        self.max_path_segments
+    }
+
+    /// Set how many times `{{#repeat n}}` may render its block, and how many
+    /// numbers `(range a b)` may return
+    ///
+    /// A larger count from template data fails with an error, so a template
+    /// can't make a render run for a very long time.
+    ///
+    /// Default is 10000.
+    pub fn set_max_repeat_count(&mut self, count: u64) {
+        self.max_repeat_count = count;
+        self.clear_partial_cache();
+    }
+
+    /// Return the max count of `{{#repeat}}` blocks
+    pub fn get_max_repeat_count(&self) -> u64 {
+        self.max_repeat_count
+    }
+
+    /// Set how many bytes `{{repeat "-" n}}` may write
+    ///
+    /// Default is 1MiB.
+    pub fn set_max_repeat_output(&mut self, bytes: usize) {
+        self.max_repeat_output = bytes;
+        self.clear_partial_cache();
+    }
+
+    /// Return the max output of inline `{{repeat}}`
+    pub fn get_max_repeat_output(&self) -> usize {
+        self.max_repeat_output
+    }
+
+    /// Remove lines holding only a block helper tag from templates registered
+    /// afterwards
+    ///
+    /// With this enabled, a `{{#if}}` block on its own lines that renders
+    /// nothing doesn't leave a blank line in output. See
+    /// `Template::strip_standalone_blocks`.
+    ///
+    /// Default is false.
+    pub fn set_strip_standalone_blocks(&mut self, enable: bool) {
+        self.strip_standalone_blocks = enable;
+    }
+
+    /// Indent output of partials on their own line in templates registered
+    /// afterwards
+    ///
+    /// With this enabled, every line of `  {{> item}}` output is prefixed with
+    /// the two spaces before the tag, like Handlebars.js does. See
+    /// `Template::indent_standalone_partials`. Not supported with the
+    /// `partial_legacy` feature.
+    ///
+    /// Default is false.
+    pub fn set_indent_partials(&mut self, enable: bool) {
+        self.indent_partials = enable;
+    }
+
+    /// Reload templates registered from files on every render
+    ///
+    /// With dev mode on, a template registered by `register_template_file` or
+    /// `register_templates_directory` is read and compiled again each time it
+    /// is rendered or included as a partial, so edits show up without
+    /// restarting. Output of static templates and cacheable partials is not
+    /// cached. Templates registered from strings are not affected.
+    ///
+    /// Default is false, and templates are compiled only once.
+    pub fn set_dev_mode(&mut self, enable: bool) {
+        self.dev_mode = enable;
+    }
+
+    /// Return true if dev mode is on
+    pub fn is_dev_mode(&self) -> bool {
+        self.dev_mode
+    }
+
+    /// Register a message catalog of a language for the `t` helper
+    ///
+    /// Messages are handlebars templates rendered with hash params of the
+    /// helper, like `{{t "greeting" name=user.name}}`. Registering a catalog
+    /// for the same language again adds to or replaces its messages.
+    pub fn register_messages(&mut self, lang: &str, messages: HashMap<String, String>) {
+        if let Some(catalog) = self.messages.get_mut(lang) {
+            catalog.extend(messages);
+        } else {
+            self.messages.insert(lang.to_owned(), messages);
+        }
+        self.clear_partial_cache();
+    }
+
+    /// Set the current language of messages rendered by the `t` helper
+    pub fn set_language(&mut self, lang: &str) {
+        self.language = Some(lang.to_owned());
+        self.clear_partial_cache();
+    }
+
+    /// Return a message of current language
+    pub fn get_message(&self, key: &str) -> Option<&String> {
+        self.language
+            .as_ref()
+            .and_then(|lang| self.messages.get(lang))
+            .and_then(|m| m.get(key))
+    }
+
     /// Register a template string
     ///
     /// Returns `TemplateError` if there is syntax error on parsing template.
@@ -126,11 +607,25 @@ impl Registry {
                                        -> Result<(), TemplateError>
         where S: AsRef<str>
     {
-        try!(Template::compile_with_name(tpl_str, name.to_owned(), self.source_map)
-                 .and_then(|t| Ok(self.templates.insert(name.to_string(), t))));
+        let tpl = try!(self.compile_template(name, tpl_str));
+        match tpl.static_output() {
+            Some(output) => self.static_outputs.insert(name.to_string(), output),
+            None => self.static_outputs.remove(name),
+        };
+        self.templates.insert(name.to_string(), tpl);
+        self.template_files.remove(name);
+        self.clear_partial_cache();
         Ok(())
     }
 
+    fn compile_template<S>(&self, name: &str, tpl_str: S) -> Result<Template, TemplateError>
+        where S: AsRef<str>
+    {
+        let mut tpl = try!(Template::compile_with_name(tpl_str, name.to_owned(), self.source_map));
+        tpl.strip_standalone_lines(self.strip_standalone_blocks, self.indent_partials);
+        Ok(tpl)
+    }
+
     /// Register a partial string
     ///
     /// A named partial will be added to the registry. It will overwrite template with
@@ -142,15 +637,61 @@ impl Registry {
     }
 
     /// Register a template from a path
+    ///
+    /// Returns `TemplateFileError::IOError` with the path in its message if the
+    /// file can't be read, or `TemplateFileError::TemplateError` with line and
+    /// column if the template has syntax error.
     pub fn register_template_file<P>(&mut self,
                                      name: &str,
                                      tpl_path: P)
                                      -> Result<(), TemplateFileError>
         where P: AsRef<Path>
     {
-        let mut file =
-            try!(File::open(tpl_path).map_err(|e| TemplateFileError::IOError(e, name.to_owned())));
-        self.register_template_source(name, &mut file)
+        let path = tpl_path.as_ref();
+        let mut buf = String::new();
+        try!(File::open(path).and_then(|mut f| f.read_to_string(&mut buf)).map_err(|e| {
+            TemplateFileError::IOError(io_error_in_file(e, path), name.to_owned())
+        }));
+        try!(self.register_template_string(name, buf));
+        self.template_files.insert(name.to_owned(), path.to_path_buf());
+        Ok(())
+    }
+
+    /// Register all templates with given extension in a directory and its
+    /// subdirectories
+    ///
+    /// Templates are named by their path relative to `dir_path` without the
+    /// extension, so `users/card.hbs` is registered as `users/card`. Extension
+    /// can be given with or without the leading dot. Returns error of the
+    /// first file, in path order, that can't be read or parsed. Like
+    /// `register_template_file`, `TemplateFileError::IOError` carries the
+    /// template name with the path in its message. A directory that can't be
+    /// listed gives `TemplateFileError::DirectoryIOError`.
+    pub fn register_templates_directory<P>(&mut self,
+                                           extension: &str,
+                                           dir_path: P)
+                                           -> Result<(), TemplateFileError>
+        where P: AsRef<Path>
+    {
+        let extension = extension.trim_matches('.');
+        let root = dir_path.as_ref();
+        let mut files = Vec::new();
+        try!(find_template_files(root, extension, &mut files)
+                 .map_err(|e| TemplateFileError::DirectoryIOError(e, root.display().to_string())));
+        files.sort();
+
+        for file in files {
+            let name = template_name_in_dir(root, &file);
+            let mut buf = String::new();
+            try!(File::open(&file).and_then(|mut f| f.read_to_string(&mut buf)).map_err(|e| {
+                TemplateFileError::IOError(io_error_in_file(e, &file), name.clone())
+            }));
+            try!(self.register_template_string(&name, buf).map_err(|e| {
+                TemplateFileError::TemplateErrorInFile(e, file.display().to_string())
+            }));
+            self.template_files.insert(name, file);
+        }
+        Ok(())
     }
 
     /// Register a template from `std::io::Read` source
@@ -158,16 +699,39 @@ impl Registry {
                                     name: &str,
                                     tpl_source: &mut Read)
                                     -> Result<(), TemplateFileError> {
+        self.register_template_reader(name, tpl_source)
+    }
+
+    /// Register a template read from a reader, like a file or a network stream
+    ///
+    /// The whole source is read before compiling. Returns
+    /// `TemplateFileError::IOError` if reading fails or the source is not
+    /// valid UTF-8, and `TemplateFileError::TemplateError` on syntax error.
+    pub fn register_template_reader(&mut self,
+                                    name: &str,
+                                    reader: &mut Read)
+                                    -> Result<(), TemplateFileError> {
         let mut buf = String::new();
-        try!(tpl_source.read_to_string(&mut buf)
-                       .map_err(|e| TemplateFileError::IOError(e, name.to_owned())));
+        try!(reader.read_to_string(&mut buf)
+                   .map_err(|e| TemplateFileError::IOError(e, name.to_owned())));
         try!(self.register_template_string(name, buf));
         Ok(())
     }
 
     /// remove a template from the registry
-    pub fn unregister_template(&mut self, name: &str) {
-        self.templates.remove(name);
+    ///
+    /// Returns the removed template if any.
+    pub fn unregister_template(&mut self, name: &str) -> Option<Template> {
+        let removed = self.templates.remove(name);
+        self.template_files.remove(name);
+        self.static_outputs.remove(name);
+        self.clear_partial_cache();
+        removed
+    }
+
+    /// Return true if a template is registered with given name
+    pub fn has_template(&self, name: &str) -> bool {
+        self.templates.contains_key(name)
     }
 
     /// register a helper
@@ -175,14 +739,155 @@ impl Registry {
                            name: &str,
                            def: Box<HelperDef + 'static>)
                            -> Option<Box<HelperDef + 'static>> {
+        self.memoized_helpers.remove(name);
+        self.clear_partial_cache();
         self.helpers.insert(name.to_string(), def)
     }
 
+    /// register a helper whose output is memoized during a render
+    ///
+    /// Within a single top-level render, the helper is called only once for each
+    /// distinct combination of params and hash, following calls reuse the
+    /// output. Use it for expensive helpers that only depend on their arguments.
+    /// Block helpers are never memoized.
+    pub fn register_memoized_helper(&mut self,
+                                    name: &str,
+                                    def: Box<HelperDef + 'static>)
+                                    -> Option<Box<HelperDef + 'static>> {
+        let old = self.register_helper(name, def);
+        self.memoized_helpers.insert(name.to_string());
+        old
+    }
+
+    /// Register the `env` helper, like `{{env "HOME" default="/tmp"}}`, which
+    /// returns the value of an environment variable
+    ///
+    /// It's not registered by default because a template could read any
+    /// variable of the process with it, so don't enable it for untrusted
+    /// templates.
+    pub fn register_env_helper(&mut self) {
+        self.register_helper("env", Box::new(helpers::ENV_HELPER));
+    }
+
+    /// Return true if the helper is registered as memoized
+    pub fn is_memoized_helper(&self, name: &str) -> bool {
+        self.memoized_helpers.contains(name)
+    }
+
+    /// Make `{{> from}}` render the partial `to`, like `header` to `header_v2`
+    ///
+    /// Aliases are resolved in chain, so `to` can be an alias too. They apply to
+    /// registered templates only, inline partials are always found by their own
+    /// name.
+    pub fn register_partial_alias(&mut self, from: &str, to: &str) {
+        self.partial_aliases.insert(from.to_string(), to.to_string());
+        self.clear_partial_cache();
+    }
+
+    /// Remove an alias added by `register_partial_alias`
+    pub fn unregister_partial_alias(&mut self, from: &str) {
+        self.partial_aliases.remove(from);
+        self.clear_partial_cache();
+    }
+
+    /// Returns the partial name an alias resolves to, or the name itself if it
+    /// isn't an alias
+    ///
+    /// Returns `RenderError` if aliases form a cycle.
+    pub fn resolve_partial_alias<'a>(&'a self, name: &'a str) -> Result<&'a str, RenderError> {
+        let mut resolved = name;
+        // a chain without cycle has at most as many steps as aliases
+        for _ in 0..self.partial_aliases.len() + 1 {
+            match self.partial_aliases.get(resolved) {
+                Some(to) => resolved = to,
+                None => return Ok(resolved),
+            }
+        }
+        Err(RenderError::new(format!("Cycle in aliases of partial {:?}", name)))
+    }
+
+    /// Mark a partial as cacheable, so its output is reused across renders
+    ///
+    /// The output of `{{> name}}` is cached by the partial name, the context
+    /// it's rendered with, including hash params, and `@` variables. When they
+    /// change, the partial is rendered again. Only mark partials whose output
+    /// depends on nothing else, like a static footer: parent paths and `@root`
+    /// are not part of the cache key. Partial blocks, partials shadowed by an
+    /// inline partial of the same name and partials inside blocks with block
+    /// params are never cached.
+    ///
+    /// The cache is cleared when templates, helpers or decorators are
+    /// registered or removed, when a setting that changes output, like the
+    /// *escape fn* or the language, is changed, and when it holds too many
+    /// outputs. The escape mode given to `render_full` is part of the key.
+    ///
+    /// Looking up the cache serializes the whole context the partial is
+    /// rendered with, so it only pays off for partials that cost more to
+    /// render than their context costs to serialize.
+    pub fn set_partial_cacheable(&mut self, name: &str, cacheable: bool) {
+        if cacheable {
+            self.cacheable_partials.insert(name.to_string());
+        } else {
+            self.cacheable_partials.remove(name);
+        }
+        self.clear_partial_cache();
+    }
+
+    /// Return true if output of the partial is cached
+    pub fn is_partial_cacheable(&self, name: &str) -> bool {
+        self.cacheable_partials.contains(name)
+    }
+
+    /// Get the cached output of a partial by its name and context key
+    pub(crate) fn get_cached_partial(&self, name: &str, key: &str) -> Option<String> {
+        self.partial_cache
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(&(name.to_owned(), key.to_owned())).cloned())
+    }
+
+    /// Cache output of a partial by its name and context key
+    pub(crate) fn set_cached_partial(&self, name: &str, key: String, output: String) {
+        if let Ok(mut cache) = self.partial_cache.lock() {
+            if cache.len() >= PARTIAL_CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert((name.to_owned(), key), output);
+        }
+    }
+
+    /// Drop all cached partial output
+    pub fn clear_partial_cache(&self) {
+        if let Ok(mut cache) = self.partial_cache.lock() {
+            cache.clear();
+        }
+    }
+
+    /// Write output of a registered template without expressions, computed
+    /// when it's registered
+    ///
+    /// Returns false if the template is not static.
+    fn write_static_output(&self, t: &Template, writer: &mut Write) -> Result<bool, RenderError> {
+        let output = match t.name {
+            Some(ref name) if !self.dev_mode => {
+                // only the registered template, not another one of the same name
+                match (self.templates.get(name), self.static_outputs.get(name)) {
+                    (Some(registered), Some(output)) if ptr::eq(registered, t) => output,
+                    _ => return Ok(false),
+                }
+            }
+            _ => return Ok(false),
+        };
+        try!(writer.write_all(output.as_bytes()));
+        Ok(true)
+    }
+
     /// register a decorator
     pub fn register_decorator(&mut self,
                               name: &str,
                               def: Box<DirectiveDef + 'static>)
                               -> Option<Box<DirectiveDef + 'static>> {
+        self.clear_partial_cache();
         self.directives.insert(name.to_string(), def)
     }
 
@@ -190,11 +895,13 @@ impl Registry {
     pub fn register_escape_fn<F: 'static + Fn(&str) -> String + Send + Sync>(&mut self,
                                                                              escape_fn: F) {
         self.escape_fn = Box::new(escape_fn);
+        self.clear_partial_cache();
     }
 
     /// Restore the default *escape fn*.
     pub fn unregister_escape_fn(&mut self) {
         self.escape_fn = Box::new(html_escape);
+        self.clear_partial_cache();
     }
 
     /// Get a reference to the current *escape fn*.
@@ -202,11 +909,84 @@ impl Registry {
         &*self.escape_fn
     }
 
+    /// Turn off escaping of all expressions, for plain text templates like
+    /// emails or config files
+    ///
+    /// `{{expression}}` renders like `{{{expression}}}`, and the registered
+    /// *escape fn* is not used. An `EscapeMode` given to `render_full` still
+    /// applies.
+    ///
+    /// Default is false.
+    pub fn set_no_escape(&mut self, enable: bool) {
+        self.no_escape = enable;
+        self.clear_partial_cache();
+    }
+
+    /// Return true if escaping of expressions is turned off
+    pub fn is_no_escape(&self) -> bool {
+        self.no_escape
+    }
+
+    /// Register a *truthy fn* to decide if a value is true in `if`, `unless`,
+    /// `each` and `with`
+    ///
+    /// By default, `null`, `false`, zero, empty string, empty array and empty
+    /// object are false, as `JsonTruthy::is_truthy` does.
+    pub fn set_truthy_fn<F: 'static + Fn(&Json) -> bool + Send + Sync>(&mut self, truthy_fn: F) {
+        self.truthy_fn = Some(Box::new(truthy_fn));
+        self.clear_partial_cache();
+    }
+
+    /// Restore the default *truthy fn*.
+    pub fn unset_truthy_fn(&mut self) {
+        self.truthy_fn = None;
+        self.clear_partial_cache();
+    }
+
+    /// Return true if the value is considered true by the current *truthy fn*
+    pub fn is_truthy(&self, value: &Json) -> bool {
+        match self.truthy_fn {
+            Some(ref f) => f(value),
+            None => value.is_truthy(),
+        }
+    }
+
     /// Return a registered template,
     pub fn get_template(&self, name: &str) -> Option<&Template> {
         self.templates.get(name)
     }
 
+    /// Read and compile again a template registered from a file, in dev mode
+    ///
+    /// Returns `None` if dev mode is off or the template is not registered
+    /// from a file. Used in place of `get_template` to render the latest source.
+    /// A failure to read the file is a `RenderErrorKind::Io` error, and a syntax
+    /// error a `RenderErrorKind::Template` error with its line and column.
+    pub fn reload_template(&self, name: &str) -> Result<Option<Template>, RenderError> {
+        let path = match self.template_files.get(name) {
+            Some(path) if self.dev_mode => path,
+            _ => return Ok(None),
+        };
+
+        let mut buf = String::new();
+        try!(File::open(path).and_then(|mut f| f.read_to_string(&mut buf)).map_err(|e| {
+            let mut err = RenderError::from(e);
+            err.desc = format!("Failed to reload {}: {}", path.display(), err.desc);
+            err
+        }));
+        let tpl = try!(self.compile_template(name, buf).map_err(|e| {
+            let desc = format!("Failed to reload {}: {}", path.display(), e.reason);
+            let (line_no, column_no) = (e.line_no, e.column_no);
+            let mut err = RenderError::from_kind(RenderErrorKind::Template(Box::new(e)));
+            err.desc = desc;
+            err.template_name = Some(name.to_owned());
+            err.line_no = line_no;
+            err.column_no = column_no;
+            err
+        }));
+        Ok(Some(tpl))
+    }
+
     /// Return a registered helper
     pub fn get_helper(&self, name: &str) -> Option<&Box<HelperDef + 'static>> {
         self.helpers.get(name)
@@ -222,9 +1002,19 @@ impl Registry {
         &self.templates
     }
 
+    /// Return names of all templates registered, in no particular order
+    pub fn template_names(&self) -> impl Iterator<Item = &String> {
+        self.templates.keys()
+    }
+
     /// Unregister all templates
+    ///
+    /// Helpers and decorators are kept.
     pub fn clear_templates(&mut self) {
         self.templates.clear();
+        self.template_files.clear();
+        self.static_outputs.clear();
+        self.clear_partial_cache();
     }
 
 
@@ -237,11 +1027,40 @@ impl Registry {
     pub fn render<T>(&self, name: &str, data: &T) -> Result<String, RenderError>
         where T: ToJson
     {
+        let ctx = Context::wraps(data);
+        self.render_full(name, &ctx, &HashMap::new(), None, None)
+    }
+
+    /// Render a registered template with all options of a single render
+    ///
+    /// * `locals` are local variables of the root template, `{"user": ..}` is
+    ///   available as `{{@user}}`. Inside blocks like `each`, they are reached as
+    ///   `{{@../user}}`, and local variables set by the block, like `@index`, take
+    ///   precedence.
+    /// * `partials_override` take precedence over registered templates of the same
+    ///   name. Partials defined with `{{#*inline}}` take precedence over both.
+    /// * `escape_override` replaces the registered *escape fn* for this render.
+    ///   Triple-stash expressions `{{{...}}}` are never escaped.
+    ///
+    /// The registry is not modified.
+    pub fn render_full(&self,
+                       name: &str,
+                       ctx: &Context,
+                       locals: &HashMap<String, Json>,
+                       partials_override: Option<&HashMap<String, Template>>,
+                       escape_override: Option<EscapeMode>)
+                       -> Result<String, RenderError> {
+        let options = RenderOptions {
+            partials: partials_override,
+            locals: Some(locals),
+            escape: escape_override,
+            ..RenderOptions::default()
+        };
         let mut writer = StringWriter::new();
         {
-            try!(self.renderw(name, data, &mut writer));
+            try!(self.renderw_context_at(name, ctx, &options, &mut writer));
         }
-        Ok(writer.to_string())
+        Ok(try!(writer.into_string()))
     }
 
 
@@ -249,69 +1068,331 @@ impl Registry {
     pub fn renderw<T>(&self, name: &str, data: &T, writer: &mut Write) -> Result<(), RenderError>
         where T: ToJson
     {
-        self.get_template(&name.to_string())
-            .ok_or(RenderError::new(format!("Template not found: {}", name)))
-            .and_then(|t| {
-                let mut ctx = Context::wraps(data);
-                let mut local_helpers = HashMap::new();
-                let mut render_context = RenderContext::new(&mut ctx, &mut local_helpers, writer);
-                render_context.root_template = t.name.clone();
-                t.render(self, &mut render_context)
-            })
+        let ctx = Context::wraps(data);
+        self.renderw_with_context(name, &ctx, writer)
     }
 
-    /// render a template string using current registry without register it
-    pub fn template_render<T>(&self,
-                              template_string: &str,
-                              data: &T)
-                              -> Result<String, TemplateRenderError>
-        where T: ToJson
+    /// Render a registered template and stream the output into `writer`
+    ///
+    /// Unlike `render`, the output is never buffered into a `String`, so large
+    /// pages can be written straight into a file or a response body.
+    pub fn render_to_write<T, W>(&self,
+                                 name: &str,
+                                 data: &T,
+                                 writer: &mut W)
+                                 -> Result<(), RenderError>
+        where T: ToJson,
+              W: Write
     {
+        let ctx = Context::wraps(data);
+        self.renderw_with_context(name, &ctx, writer)
+    }
+
+    /// Render a registered template with a prepared `Context` into a string
+    ///
+    /// The context data is borrowed rather than converted on each call, so a large
+    /// dataset can be wrapped once (or borrowed with `Context::borrowed`) and rendered
+    /// with many templates.
+    pub fn render_with_context(&self, name: &str, ctx: &Context) -> Result<String, RenderError> {
         let mut writer = StringWriter::new();
         {
-            try!(self.template_renderw(template_string, data, &mut writer));
+            try!(self.renderw_with_context(name, ctx, &mut writer));
         }
-        Ok(writer.to_string())
+        Ok(try!(writer.into_string()))
     }
 
-    /// render a template string using current registry without register it
-    pub fn template_renderw<T>(&self,
-                               template_string: &str,
-                               data: &T,
-                               writer: &mut Write)
-                               -> Result<(), TemplateRenderError>
+    /// Render every registered template with the same data
+    ///
+    /// Returns rendered output or error of each template by name. Data is converted
+    /// only once for all templates.
+    pub fn render_all<T>(&self, data: &T) -> HashMap<String, Result<String, RenderError>>
         where T: ToJson
     {
-        let tpl = try!(Template::compile(template_string));
-        let mut ctx = Context::wraps(data);
-        let mut local_helpers = HashMap::new();
-        let mut render_context = RenderContext::new(&mut ctx, &mut local_helpers, writer);
-        tpl.render(self, &mut render_context).map_err(TemplateRenderError::from)
+        self.render_all_matching(data, |_| true)
     }
 
-    /// render a template source using current registry without register it
-    pub fn template_renderw2<T>(&self,
-                                template_source: &mut Read,
-                                data: &T,
+    /// Render registered templates accepted by `filter` with the same data
+    ///
+    /// Useful to skip templates only used as partials, for example
+    /// `r.render_all_matching(&data, |name| !name.starts_with('_'))`.
+    pub fn render_all_matching<T, F>(&self,
+                                     data: &T,
+                                     filter: F)
+                                     -> HashMap<String, Result<String, RenderError>>
+        where T: ToJson,
+              F: Fn(&str) -> bool
+    {
+        let ctx = Context::wraps(data);
+        self.templates
+            .keys()
+            .filter(|name| filter(name))
+            .map(|name| (name.clone(), self.render_with_context(name, &ctx)))
+            .collect()
+    }
+
+    /// Render a registered template with a prepared `Context` to the `std::io::Write`
+    pub fn renderw_with_context(&self,
+                                name: &str,
+                                ctx: &Context,
                                 writer: &mut Write)
-                                -> Result<(), TemplateRenderError>
+                                -> Result<(), RenderError> {
+        self.renderw_context_at(name, ctx, &RenderOptions::default(), writer)
+    }
+
+    /// Render a registered template as if the value at `path` is the root of data
+    ///
+    /// For example, with `path` set to `user.profile`, `{{name}}` in the template
+    /// resolves to `user.profile.name` of the data. This avoids building a trimmed
+    /// data structure for rendering a fragment.
+    pub fn render_at<T>(&self, name: &str, data: &T, path: &str) -> Result<String, RenderError>
         where T: ToJson
     {
-        let mut tpl_str = String::new();
-        try!(template_source.read_to_string(&mut tpl_str).map_err(|e| {
-            TemplateRenderError::IOError(e, "Unamed template source".to_owned())
-        }));
-        self.template_renderw(&tpl_str, data, writer)
+        let mut writer = StringWriter::new();
+        {
+            try!(self.renderw_at(name, data, path, &mut writer));
+        }
+        Ok(try!(writer.into_string()))
     }
-}
 
-#[cfg(test)]
-mod test {
-    use registry::Registry;
-    use render::{RenderContext, Renderable, RenderError, Helper};
+    /// Render a registered template from given `path` of data to the `std::io::Write`
+    pub fn renderw_at<T>(&self,
+                         name: &str,
+                         data: &T,
+                         path: &str,
+                         writer: &mut Write)
+                         -> Result<(), RenderError>
+        where T: ToJson
+    {
+        let ctx = Context::wraps(data);
+        let options = RenderOptions { path: Some(path), ..RenderOptions::default() };
+        self.renderw_context_at(name, &ctx, &options, writer)
+    }
+
+    /// Render a registered template with some partials replaced
+    ///
+    /// Partials in `overrides` take precedence over registered templates of
+    /// the same name, for this render only. The registry is not modified.
+    pub fn render_with_partial_overrides<T>(&self,
+                                            name: &str,
+                                            data: &T,
+                                            overrides: &HashMap<String, Template>)
+                                            -> Result<String, RenderError>
+        where T: ToJson
+    {
+        let ctx = Context::wraps(data);
+        let options = RenderOptions { partials: Some(overrides), ..RenderOptions::default() };
+        let mut writer = StringWriter::new();
+        {
+            try!(self.renderw_context_at(name, &ctx, &options, &mut writer));
+        }
+        Ok(try!(writer.into_string()))
+    }
+
+    /// Render a registered template, and report time spent in its helpers and partials
+    ///
+    /// The report is `None` unless profiling is turned on by `enable_profiling`.
+    pub fn render_with_profile<T>(&self,
+                                  name: &str,
+                                  data: &T)
+                                  -> Result<(String, Option<RenderProfile>), RenderError>
+        where T: ToJson
+    {
+        if !self.profiling {
+            return self.render(name, data).map(|s| (s, None));
+        }
+
+        let ctx = Context::wraps(data);
+        let profile = Rc::new(RefCell::new(RenderProfile::new()));
+        let options = RenderOptions { profile: Some(profile.clone()), ..RenderOptions::default() };
+        let mut writer = StringWriter::new();
+        {
+            try!(self.renderw_context_at(name, &ctx, &options, &mut writer));
+        }
+        let output = try!(writer.into_string());
+        let report = profile.borrow().clone();
+        Ok((output, Some(report)))
+    }
+
+    fn renderw_context_at(&self,
+                          name: &str,
+                          ctx: &Context,
+                          options: &RenderOptions,
+                          writer: &mut Write)
+                          -> Result<(), RenderError> {
+        if let Some(t) = try!(self.reload_template(name)) {
+            return self.render_template_at(&t, ctx, options, writer);
+        }
+        self.get_template(&name.to_string())
+            .ok_or(RenderError::from_kind(RenderErrorKind::TemplateNotFound(name.to_owned())))
+            .and_then(|t| self.render_template_at(t, ctx, options, writer))
+    }
+
+    fn render_template_at(&self,
+                          t: &Template,
+                          ctx: &Context,
+                          options: &RenderOptions,
+                          writer: &mut Write)
+                          -> Result<(), RenderError> {
+        if self.bom {
+            try!(writer.write_all(UTF8_BOM));
+        }
+        if self.line_ending != LineEnding::Preserve {
+            let mut line_ending_writer = LineEndingWriter::new(writer, self.line_ending);
+            try!(self.render_template_at_inner(t, ctx, options, &mut line_ending_writer));
+            try!(line_ending_writer.flush());
+            return Ok(());
+        }
+        self.render_template_at_inner(t, ctx, options, writer)
+    }
+
+    fn render_template_at_inner(&self,
+                                t: &Template,
+                                ctx: &Context,
+                                options: &RenderOptions,
+                                writer: &mut Write)
+                                -> Result<(), RenderError> {
+        if try!(self.write_static_output(t, writer)) {
+            return Ok(());
+        }
+
+        // decorators may modify the context, this copy is only cloned
+        // when they do so
+        let mut local_ctx = Context::borrowed(ctx.data());
+        let mut local_helpers = HashMap::new();
+        let mut render_context = RenderContext::new(&mut local_ctx, &mut local_helpers, writer);
+        render_context.root_template = t.name.clone();
+        render_context.set_max_path_segments(self.max_path_segments);
+        render_context.set_root_data(ctx.data());
+        if let Some(p) = options.path {
+            let new_path = format!("{}/{}", render_context.get_path(), p);
+            render_context.set_path(new_path);
+        }
+        if let Some(partials) = options.partials {
+            for (name, partial) in partials.iter() {
+                render_context.set_partial(name.clone(), partial.clone());
+            }
+        }
+        if let Some(ref profile) = options.profile {
+            render_context.set_profile(profile.clone());
+        }
+        if let Some(locals) = options.locals {
+            for (name, value) in locals.iter() {
+                let var_name = if name.starts_with('@') {
+                    name.clone()
+                } else {
+                    format!("@{}", name)
+                };
+                render_context.set_local_var(var_name, value.clone());
+            }
+        }
+        if let Some(escape) = options.escape {
+            render_context.set_escape_mode(escape);
+        }
+        t.render(self, &mut render_context)
+    }
+
+    /// render a template string using current registry without register it
+    pub fn template_render<T>(&self,
+                              template_string: &str,
+                              data: &T)
+                              -> Result<String, TemplateRenderError>
+        where T: ToJson
+    {
+        self.render_template(template_string, data)
+    }
+
+    /// render a template string using current registry without register it
+    pub fn template_renderw<T>(&self,
+                               template_string: &str,
+                               data: &T,
+                               writer: &mut Write)
+                               -> Result<(), TemplateRenderError>
+        where T: ToJson
+    {
+        self.render_template_string(template_string, &Context::wraps(data), writer)
+    }
+
+    /// Compile a template string like a registered one, and render it
+    fn render_template_string(&self,
+                              template_string: &str,
+                              ctx: &Context,
+                              writer: &mut Write)
+                              -> Result<(), TemplateRenderError> {
+        let mut tpl = try!(Template::compile(template_string));
+        tpl.strip_standalone_lines(self.strip_standalone_blocks, self.indent_partials);
+        self.render_template_at(&tpl, ctx, &RenderOptions::default(), writer)
+            .map_err(TemplateRenderError::from)
+    }
+
+    /// Render a template string once, without registering it
+    ///
+    /// The template is parsed on every call and discarded afterwards, so the
+    /// names of registered templates are not affected. Returns
+    /// `TemplateRenderError::TemplateError` if the template has syntax error,
+    /// and `TemplateRenderError::RenderError` if it fails to render.
+    ///
+    /// ```
+    /// use handlebars::Handlebars;
+    ///
+    /// let handlebars = Handlebars::new();
+    /// let output = handlebars.render_template("hello {{this}}", &"world".to_owned()).unwrap();
+    /// assert_eq!(output, "hello world");
+    /// assert!(handlebars.render_template("hello {{this", &()).is_err());
+    /// ```
+    pub fn render_template<T>(&self,
+                              template_string: &str,
+                              data: &T)
+                              -> Result<String, TemplateRenderError>
+        where T: ToJson
+    {
+        let mut writer = StringWriter::new();
+        {
+            try!(self.render_template_to_write(template_string, data, &mut writer));
+        }
+        writer.into_string().map_err(|e| TemplateRenderError::from(RenderError::from(e)))
+    }
+
+    /// Render a template string once, without registering it, and stream the
+    /// output into `writer`
+    ///
+    /// Errors are the same as `render_template`.
+    pub fn render_template_to_write<T, W>(&self,
+                                          template_string: &str,
+                                          data: &T,
+                                          writer: &mut W)
+                                          -> Result<(), TemplateRenderError>
+        where T: ToJson,
+              W: Write
+    {
+        self.render_template_string(template_string, &Context::wraps(data), writer)
+    }
+
+    /// render a template source using current registry without register it
+    pub fn template_renderw2<T>(&self,
+                                template_source: &mut Read,
+                                data: &T,
+                                writer: &mut Write)
+                                -> Result<(), TemplateRenderError>
+        where T: ToJson
+    {
+        let mut tpl_str = String::new();
+        try!(template_source.read_to_string(&mut tpl_str).map_err(|e| {
+            TemplateRenderError::IOError(e, "Unamed template source".to_owned())
+        }));
+        self.render_template_string(&tpl_str, &Context::wraps(data), writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use registry::{Registry, LineEnding, NumberLocale};
+    use escape::csv_escape;
+    use render::{RenderContext, Renderable, RenderError, Helper, Directive};
     use helpers::HelperDef;
+    use context::{Context, JsonRender, to_json};
     use support::str::StringWriter;
-    #[cfg(feature = "partial_legacy")]
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use error::TemplateRenderError;
 
     #[derive(Clone, Copy)]
@@ -338,21 +1419,32 @@ mod test {
         assert!(r.register_template_string("index2", "<h2></h2>").is_ok());
 
         assert_eq!(r.templates.len(), 2);
+        assert!(r.has_template("index"));
 
-        r.unregister_template("index");
+        let removed = r.unregister_template("index");
+        assert_eq!(removed.and_then(|t| t.name), Some("index".to_owned()));
+        assert!(!r.has_template("index"));
+        assert!(r.unregister_template("index").is_none());
         assert_eq!(r.templates.len(), 1);
+        assert_eq!(r.template_names().collect::<Vec<_>>(), vec!["index2"]);
 
+        let helpers = r.helpers.len();
         r.clear_templates();
         assert_eq!(r.templates.len(), 0);
+        assert_eq!(r.template_names().count(), 0);
+        assert_eq!(r.helpers.len(), helpers);
+        assert!(r.get_decorator("inline").is_some());
 
         r.register_helper("dummy", Box::new(DUMMY_HELPER));
 
-        // built-in helpers plus 1
+        // built-in helpers plus 1, not counting the optional markdown and date helpers
+        let count = r.helpers.len() - if cfg!(feature = "markdown") { 1 } else { 0 } -
+                    if cfg!(feature = "dates") { 1 } else { 0 };
         #[cfg(feature = "partial_legacy")]
-        assert_eq!(r.helpers.len(), 10 + 1);
+        assert_eq!(count, 36 + 1);
 
         #[cfg(not(feature = "partial_legacy"))]
-        assert_eq!(r.helpers.len(), 7 + 1);
+        assert_eq!(count, 33 + 1);
     }
 
     #[test]
@@ -366,8 +1458,785 @@ mod test {
             r.renderw("index", &(), &mut sw).ok().unwrap();
         }
 
-        assert_eq!("<h1></h1>".to_string(), sw.to_string());
+        assert_eq!("<h1></h1>".to_string(), sw.into_string().unwrap());
+
+    }
+
+    #[test]
+    fn test_strip_standalone_blocks() {
+        let source = "<p>\n  {{#if show}}\n  shown\n  {{/if}}\n</p>\n";
+        let data = btreemap! {
+            "show".to_string() => false
+        };
+
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0", source).is_ok());
+        assert_eq!(r.render("t0", &data).unwrap(), "<p>\n  \n</p>\n");
+
+        r.set_strip_standalone_blocks(true);
+        assert!(r.register_template_string("t0", source).is_ok());
+        assert_eq!(r.render("t0", &data).unwrap(), "<p>\n</p>\n");
+        assert_eq!(r.render_template(source, &data).unwrap(), "<p>\n</p>\n");
+
+        let data = btreemap! {
+            "show".to_string() => true
+        };
+        assert_eq!(r.render("t0", &data).unwrap(), "<p>\n  shown\n</p>\n");
+    }
+
+    #[test]
+    fn test_strip_standalone_nested_blocks() {
+        let mut r = Registry::new();
+        r.set_strip_standalone_blocks(true);
+
+        let source = "x\n{{#if c}}\n  {{#if a}}\n  A\n  {{/if}}\n{{/if}}\nend\n";
+        assert!(r.register_template_string("nested", source).is_ok());
+        let data = btreemap! {
+            "c".to_string() => true,
+            "a".to_string() => false
+        };
+        assert_eq!(r.render("nested", &data).unwrap(), "x\nend\n");
+        let data = btreemap! {
+            "c".to_string() => true,
+            "a".to_string() => true
+        };
+        assert_eq!(r.render("nested", &data).unwrap(), "x\n  A\nend\n");
+
+        let source = "{{#if a}}\nA\n{{/if}}\n  {{#if b}}\n  B\n  {{/if}}\nend\n";
+        assert!(r.register_template_string("adjacent", source).is_ok());
+        let data = btreemap! {
+            "a".to_string() => false,
+            "b".to_string() => false
+        };
+        assert_eq!(r.render("adjacent", &data).unwrap(), "end\n");
+        let data = btreemap! {
+            "a".to_string() => true,
+            "b".to_string() => true
+        };
+        assert_eq!(r.render("adjacent", &data).unwrap(), "A\n  B\nend\n");
+    }
+
+    #[test]
+    #[cfg(not(feature = "partial_legacy"))]
+    fn test_render_full() {
+        use std::collections::HashMap;
+        use template::Template;
+        use registry::EscapeMode;
+
+        let mut r = Registry::new();
+        let source = "{{> header}}{{#each items}}{{this}}{{@../sep}}{{/each}}";
+        assert!(r.register_template_string("t0", source).is_ok());
+        assert!(r.register_template_string("header", "<h1>{{title}}</h1>").is_ok());
+
+        let data = btreemap! {
+            "title".to_string() => to_json(&"a&b".to_owned()),
+            "items".to_string() => to_json(&vec!["<x>".to_owned(), "y".to_owned()])
+        };
+        let ctx = Context::wraps(&data);
+
+        let mut locals = HashMap::new();
+        locals.insert("sep".to_owned(), to_json(&";".to_owned()));
+        let mut partials = HashMap::new();
+        partials.insert("header".to_owned(), Template::compile("[{{title}}]").unwrap());
+
+        assert_eq!(r.render_full("t0", &ctx, &locals, None, None).unwrap(),
+                   "<h1>a&amp;b</h1>&lt;x&gt;;y;");
+        assert_eq!(r.render_full("t0",
+                                 &ctx,
+                                 &locals,
+                                 Some(&partials),
+                                 Some(EscapeMode::NoEscape))
+                       .unwrap(),
+                   "[a&b]<x>;y;");
+        assert_eq!(r.render_full("t0", &ctx, &locals, None, Some(EscapeMode::Custom(csv_escape)))
+                       .unwrap(),
+                   "<h1>a&b</h1><x>;y;");
+
+        // registry is not modified
+        assert_eq!(r.render("header", &data).unwrap(), "<h1>a&amp;b</h1>");
+    }
+
+    #[test]
+    fn test_render_static_template() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("footer", "<footer>{{! c }}2017</footer>").is_ok());
+
+        assert_eq!(r.static_outputs.len(), 1);
+        assert_eq!(r.render("footer", &()).unwrap(), "<footer>2017</footer>");
+        assert_eq!(r.render("footer", &()).unwrap(), "<footer>2017</footer>");
+
+        // output is computed again when the template changes
+        assert!(r.register_template_string("footer", "<footer>2018</footer>").is_ok());
+        assert_eq!(r.render("footer", &()).unwrap(), "<footer>2018</footer>");
+        assert!(r.register_template_string("footer", "<footer>{{this}}</footer>").is_ok());
+        assert_eq!(r.render("footer", &2019).unwrap(), "<footer>2019</footer>");
+        assert!(r.static_outputs.is_empty());
+    }
+
+    #[test]
+    fn test_render_to_write() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("index", "<h1>{{this}}</h1>").is_ok());
+
+        let mut buf: Vec<u8> = Vec::new();
+        r.render_to_write("index", &"hello".to_owned(), &mut buf).unwrap();
+        assert_eq!(b"<h1>hello</h1>".to_vec(), buf);
+
+        let mut buf: Vec<u8> = Vec::new();
+        assert!(r.render_to_write("missing", &(), &mut buf).is_err());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_render_with_context() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0", "{{hello}}{{*foo}}{{hello}}").is_ok());
+        assert!(r.register_template_string("t1", "<p>{{hello}}</p>").is_ok());
+        r.register_decorator("foo",
+                             Box::new(|_: &Directive,
+                                       _: &Registry,
+                                       rc: &mut RenderContext|
+                                       -> Result<(), RenderError> {
+            if let Some(ref mut m) = rc.context_mut().data_mut().as_object_mut() {
+                m.insert("hello".to_string(), to_json(&"war".to_owned()));
+            }
+            Ok(())
+        }));
+
+        let data = to_json(&btreemap! {
+            "hello".to_string() => "world".to_string()
+        });
+        let ctx = Context::borrowed(&data);
+
+        assert_eq!(r.render_with_context("t0", &ctx).unwrap(), "worldwar");
+        // modification by decorator doesn't leak into the shared data
+        assert_eq!(r.render_with_context("t1", &ctx).unwrap(), "<p>world</p>");
+        assert_eq!(ctx.data(), &data);
+    }
+
+    #[test]
+    fn test_render_at() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0", "{{name}}, {{#each langs}}{{this}} {{/each}}")
+                    .is_ok());
+
+        let data = btreemap! {
+            "user".to_string() => btreemap! {
+                "profile".to_string() => to_json(&btreemap! {
+                    "name".to_string() => to_json(&"Ning".to_owned()),
+                    "langs".to_string() => to_json(&vec!["rust".to_owned(), "clojure".to_owned()])
+                })
+            }
+        };
+
+        assert_eq!(r.render_at("t0", &data, "user.profile").unwrap(),
+                   "Ning, rust clojure ");
+        assert_eq!(r.render_at("t0", &data, "user.notfound").unwrap(), ", ");
+    }
+
+    #[test]
+    fn test_line_ending() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0", "a\r\nb\n{{this}}\r\n").is_ok());
+        let data = "c\nd\re".to_owned();
+
+        assert_eq!(r.render("t0", &data).unwrap(), "a\r\nb\nc\nd\re\r\n");
+
+        r.set_line_ending(LineEnding::Lf);
+        assert_eq!(r.render("t0", &data).unwrap(), "a\nb\nc\nd\re\n");
+        assert_eq!(r.render_template("1\r\n2\r", &()).unwrap(), "1\n2\r");
+
+        r.set_line_ending(LineEnding::CrLf);
+        assert_eq!(r.render("t0", &data).unwrap(), "a\r\nb\r\nc\r\nd\re\r\n");
+    }
+
+    #[test]
+    #[cfg(not(feature = "partial_legacy"))]
+    fn test_render_with_partial_overrides() {
+        use std::collections::HashMap;
+        use template::Template;
+
+        let mut r = Registry::new();
+        assert!(r.register_template_string("layout", "<main>{{> content}}</main>").is_ok());
+        assert!(r.register_template_string("content", "A:{{name}}").is_ok());
+
+        let data = btreemap! {
+            "name".to_string() => "Ning".to_string()
+        };
+
+        let mut overrides = HashMap::new();
+        overrides.insert("content".to_string(),
+                         Template::compile("B:{{name}}").ok().unwrap());
+
+        assert_eq!(r.render_with_partial_overrides("layout", &data, &overrides).unwrap(),
+                   "<main>B:Ning</main>");
+        assert_eq!(r.render("layout", &data).unwrap(), "<main>A:Ning</main>");
+        assert_eq!(r.render("content", &data).unwrap(), "A:Ning");
+    }
+
+    #[test]
+    #[cfg(not(feature = "partial_legacy"))]
+    fn test_cacheable_partial() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let mut r = Registry::new();
+        r.register_helper("count",
+                          Box::new(move |_: &Helper,
+                                         _: &Registry,
+                                         _: &mut RenderContext|
+                                         -> Result<(), RenderError> {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }));
+        assert!(r.register_template_string("footer", "{{count \"footer\"}}(c) {{year}}").is_ok());
+        assert!(r.register_template_string("page", "{{title}}|{{> footer}}").is_ok());
+        r.set_partial_cacheable("footer", true);
+
+        let data = btreemap! {
+            "title".to_string() => "A".to_string(),
+            "year".to_string() => "2017".to_string()
+        };
+        assert_eq!(r.render("page", &data).unwrap(), "A|(c) 2017");
+        assert_eq!(r.render("page", &data).unwrap(), "A|(c) 2017");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let data = btreemap! {
+            "title".to_string() => "B".to_string(),
+            "year".to_string() => "2018".to_string()
+        };
+        assert_eq!(r.render("page", &data).unwrap(), "B|(c) 2018");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        assert!(r.register_template_string("footer", "{{count \"footer\"}}{{year}}").is_ok());
+        assert_eq!(r.render("page", &data).unwrap(), "B|2018");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
 
+        r.set_partial_cacheable("footer", false);
+        assert_eq!(r.render("page", &data).unwrap(), "B|2018");
+        assert_eq!(r.render("page", &data).unwrap(), "B|2018");
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    #[cfg(not(feature = "partial_legacy"))]
+    fn test_cacheable_partial_key() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("footer", "global footer{{@index}}").is_ok());
+        assert!(r.register_template_string("a", "{{> footer}}").is_ok());
+        assert!(r.register_template_string("b",
+                                           "{{#*inline \"footer\"}}inline footer{{/inline}}\
+                                            {{> footer}}")
+                    .is_ok());
+        assert!(r.register_template_string("c", "{{#each this}}{{> footer}},{{/each}}").is_ok());
+        assert!(r.register_template_string("d", "{{#each this as |x|}}{{> footer}},{{/each}}")
+                    .is_ok());
+        r.set_partial_cacheable("footer", true);
+
+        // inline partials of the same name are not served from cache
+        assert_eq!(r.render("a", &()).unwrap(), "global footer");
+        assert_eq!(r.render("b", &()).unwrap(), "inline footer");
+
+        // `@` variables are part of the key, even with the same context
+        assert_eq!(r.render("c", &vec![1, 1]).unwrap(), "global footer0,global footer1,");
+        assert_eq!(r.render("d", &vec![1, 1]).unwrap(), "global footer0,global footer1,");
+
+        // the cache is cleared when a helper is registered
+        assert!(r.register_template_string("footer", "{{stamp 1}}").is_ok());
+        r.register_helper("stamp",
+                          Box::new(|_: &Helper,
+                                    _: &Registry,
+                                    rc: &mut RenderContext|
+                                    -> Result<(), RenderError> {
+            try!(rc.writer.write("old".as_bytes()));
+            Ok(())
+        }));
+        assert_eq!(r.render("a", &()).unwrap(), "old");
+        r.register_helper("stamp",
+                          Box::new(|_: &Helper,
+                                    _: &Registry,
+                                    rc: &mut RenderContext|
+                                    -> Result<(), RenderError> {
+            try!(rc.writer.write("new".as_bytes()));
+            Ok(())
+        }));
+        assert_eq!(r.render("a", &()).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_max_path_segments() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0",
+                                           "{{a.b}}|{{a.b.c}}|{{#with a}}{{../a.b.c}}{{/with}}")
+                    .is_ok());
+        let data = btreemap! {
+            "a".to_string() => btreemap! {
+                "b".to_string() => btreemap! {"c".to_string() => 1}
+            }
+        };
+
+        assert_eq!(r.get_max_path_segments(), 256);
+        assert_eq!(r.render("t0", &data).unwrap(), "[object]|1|1");
+
+        r.set_max_path_segments(2);
+        assert_eq!(r.get_max_path_segments(), 2);
+        assert_eq!(r.render("t0", &data).unwrap(), "[object]||");
+
+        r.set_strict_mode(true);
+        assert!(r.render("t0", &data).is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "partial_legacy"))]
+    fn test_cacheable_partial_settings() {
+        use std::collections::HashMap;
+        use registry::EscapeMode;
+        use escape::no_escape;
+
+        let mut r = Registry::new();
+        assert!(r.register_template_string("footer", "{{#if x}}{{x}}{{/if}}").is_ok());
+        assert!(r.register_template_string("page", "{{> footer}}").is_ok());
+        r.set_partial_cacheable("footer", true);
+
+        let data = btreemap! {"x".to_string() => "<b>".to_string()};
+        let ctx = Context::wraps(&data);
+        assert_eq!(r.render("page", &data).unwrap(), "&lt;b&gt;");
+        assert_eq!(r.render_full("page", &ctx, &HashMap::new(), None, Some(EscapeMode::NoEscape))
+                       .unwrap(),
+                   "<b>");
+        assert_eq!(r.render("page", &data).unwrap(), "&lt;b&gt;");
+
+        r.register_escape_fn(no_escape);
+        assert_eq!(r.render("page", &data).unwrap(), "<b>");
+        r.unregister_escape_fn();
+        assert_eq!(r.render("page", &data).unwrap(), "&lt;b&gt;");
+        r.set_no_escape(true);
+        assert_eq!(r.render("page", &data).unwrap(), "<b>");
+        r.set_no_escape(false);
+
+        r.set_truthy_fn(|_| false);
+        assert_eq!(r.render("page", &data).unwrap(), "");
+    }
+
+    #[test]
+    fn test_register_template_reader() {
+        use std::io::Cursor;
+        use error::TemplateFileError;
+
+        let mut r = Registry::new();
+        let mut source = Cursor::new("Hello {{name}}".as_bytes());
+        assert!(r.register_template_reader("t0", &mut source).is_ok());
+
+        let data = btreemap! {
+            "name".to_string() => "Ning".to_string()
+        };
+        assert_eq!(r.render("t0", &data).unwrap(), "Hello Ning");
+
+        let mut source = Cursor::new("{{#if}}".as_bytes());
+        match r.register_template_reader("t1", &mut source) {
+            Err(TemplateFileError::TemplateError(e)) => {
+                assert_eq!(e.template_name, Some("t1".to_owned()))
+            }
+            _ => panic!("Error expected"),
+        }
+
+        let mut source = Cursor::new(vec![0xffu8, 0xfe]);
+        match r.register_template_reader("t2", &mut source) {
+            Err(TemplateFileError::IOError(_, name)) => assert_eq!(name, "t2"),
+            _ => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn test_register_template_file() {
+        use std::env;
+        use std::fs::{self, File};
+        use std::io::Write;
+        use error::TemplateFileError;
+
+        let path = env::temp_dir().join(format!("handlebars-file-test-{}.hbs",
+                                                ::std::process::id()));
+        File::create(&path).unwrap().write_all(b"Hello {{name}}").unwrap();
+
+        let mut r = Registry::new();
+        assert!(r.register_template_file("t0", &path).is_ok());
+        let data = btreemap! {
+            "name".to_string() => "Ning".to_string()
+        };
+        assert_eq!(r.render("t0", &data).unwrap(), "Hello Ning");
+
+        File::create(&path).unwrap().write_all(b"Hello\n{{#if}}").unwrap();
+        match r.register_template_file("t1", &path) {
+            Err(TemplateFileError::TemplateError(e)) => {
+                assert_eq!(e.template_name, Some("t1".to_owned()));
+                assert_eq!(e.line_no, Some(2));
+            }
+            _ => panic!("Error expected"),
+        }
+
+        fs::remove_file(&path).unwrap();
+        match r.register_template_file("t2", &path) {
+            Err(e @ TemplateFileError::IOError(..)) => {
+                assert!(e.to_string().contains(&path.display().to_string()));
+            }
+            _ => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn test_register_templates_directory() {
+        use std::env;
+        use std::fs::{self, File};
+        use std::io::Write;
+        use error::TemplateFileError;
+
+        let dir = env::temp_dir().join(format!("handlebars-dir-test-{}", ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("users/admin")).unwrap();
+        for &(path, content) in &[("index.hbs", "{{> users/card}}"),
+                                  ("users/card.hbs", "card of {{name}}"),
+                                  ("users/admin/panel.hbs", "panel"),
+                                  ("users/notes.txt", "not a template")] {
+            File::create(dir.join(path)).unwrap().write_all(content.as_bytes()).unwrap();
+        }
+
+        let mut r = Registry::new();
+        assert!(r.register_templates_directory(".hbs", &dir).is_ok());
+        let mut names: Vec<&String> = r.get_templates().keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["index", "users/admin/panel", "users/card"]);
+
+        let data = btreemap! {
+            "name".to_string() => "Ning".to_string()
+        };
+        assert_eq!(r.render("index", &data).unwrap(), "card of Ning");
+
+        File::create(dir.join("users/broken.hbs")).unwrap().write_all(b"{{#if}}").unwrap();
+        let mut r = Registry::new();
+        match r.register_templates_directory("hbs", &dir) {
+            Err(TemplateFileError::TemplateErrorInFile(e, path)) => {
+                assert_eq!(e.template_name, Some("users/broken".to_owned()));
+                assert!(path.ends_with("broken.hbs"));
+            }
+            _ => panic!("Error expected"),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+        match r.register_templates_directory("hbs", &dir) {
+            Err(TemplateFileError::DirectoryIOError(_, path)) => {
+                assert_eq!(path, dir.display().to_string());
+            }
+            _ => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn test_dev_mode() {
+        use std::env;
+        use std::fs::{self, File};
+        use std::io::Write;
+        use render::RenderErrorKind;
+
+        let dir = env::temp_dir().join(format!("handlebars-dev-test-{}", ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        File::create(dir.join("page.hbs")).unwrap().write_all(b"{{> part}} page").unwrap();
+        File::create(dir.join("part.hbs")).unwrap().write_all(b"part").unwrap();
+
+        let mut r = Registry::new();
+        assert!(r.register_templates_directory("hbs", &dir).is_ok());
+        assert!(r.register_template_file("single", dir.join("part.hbs")).is_ok());
+        assert_eq!(r.render("page", &()).unwrap(), "part page");
+        assert_eq!(r.render("single", &()).unwrap(), "part");
+
+        File::create(dir.join("page.hbs")).unwrap().write_all(b"{{> part}} new page").unwrap();
+        File::create(dir.join("part.hbs")).unwrap().write_all(b"new part").unwrap();
+        assert_eq!(r.render("page", &()).unwrap(), "part page");
+
+        r.set_dev_mode(true);
+        assert_eq!(r.render("page", &()).unwrap(), "new part new page");
+        assert_eq!(r.render("single", &()).unwrap(), "new part");
+
+        File::create(dir.join("page.hbs")).unwrap().write_all(b"{{#if}}").unwrap();
+        let err = r.render("page", &()).unwrap_err();
+        assert!(err.desc.starts_with("Failed to reload"));
+        assert_eq!((err.line_no, err.column_no), (Some(1), Some(8)));
+        match *err.kind() {
+            RenderErrorKind::Template(ref e) => assert_eq!(e.line_no, Some(1)),
+            _ => panic!("unexpected error kind {:?}", err.kind()),
+        }
+
+        // registering from a string replaces the file
+        assert!(r.register_template_string("page", "string page").is_ok());
+        assert_eq!(r.render("page", &()).unwrap(), "string page");
+
+        fs::remove_dir_all(&dir).unwrap();
+        let err = r.render("single", &()).unwrap_err();
+        assert!(err.desc.starts_with("Failed to reload"));
+        match *err.kind() {
+            RenderErrorKind::Io(_) => {}
+            _ => panic!("unexpected error kind {:?}", err.kind()),
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "partial_legacy"))]
+    fn test_dev_mode_inline_partial() {
+        use std::env;
+        use std::fs::{self, File};
+        use std::io::Write;
+
+        let dir = env::temp_dir()
+            .join(format!("handlebars-dev-inline-test-{}", ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        File::create(dir.join("page.hbs"))
+            .unwrap()
+            .write_all(b"{{#*inline \"part\"}}inline{{/inline}}{{> part}}")
+            .unwrap();
+        File::create(dir.join("part.hbs")).unwrap().write_all(b"part").unwrap();
+
+        let mut r = Registry::new();
+        assert!(r.register_templates_directory("hbs", &dir).is_ok());
+        r.set_dev_mode(true);
+
+        // a partial defined in the template is used without reading the file
+        fs::remove_file(dir.join("part.hbs")).unwrap();
+        assert_eq!(r.render("page", &()).unwrap(), "inline");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_partial_alias() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("header", "old {{title}}").is_ok());
+        assert!(r.register_template_string("header_v2", "new {{title}}").is_ok());
+        assert!(r.register_template_string("header_v3", "newer {{title}}").is_ok());
+        assert!(r.register_template_string("page", "{{> header}}|body").is_ok());
+
+        let data = btreemap! {
+            "title".to_string() => "Home".to_string()
+        };
+        assert_eq!(r.render("page", &data).unwrap(), "old Home|body");
+
+        r.register_partial_alias("header", "header_v2");
+        assert_eq!(r.render("page", &data).unwrap(), "new Home|body");
+
+        r.register_partial_alias("header_v2", "header_v3");
+        assert_eq!(r.resolve_partial_alias("header").unwrap(), "header_v3");
+        assert_eq!(r.render("page", &data).unwrap(), "newer Home|body");
+
+        r.register_partial_alias("header_v3", "header");
+        assert!(r.resolve_partial_alias("header").is_err());
+        assert!(r.render("page", &data).is_err());
+
+        r.unregister_partial_alias("header_v3");
+        r.unregister_partial_alias("header_v2");
+        r.unregister_partial_alias("header");
+        assert_eq!(r.render("page", &data).unwrap(), "old Home|body");
+    }
+
+    #[test]
+    fn test_render_with_profile() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("row", "{{this}};").is_ok());
+        let tpl = "{{#each this}}{{#if (eq this 2)}}{{> row}}{{/if}}{{/each}}";
+        assert!(r.register_template_string("t0", tpl).is_ok());
+
+        let data = vec![1, 2, 3];
+        let (output, report) = r.render_with_profile("t0", &data).unwrap();
+        assert_eq!(output, "2;");
+        assert!(report.is_none());
+
+        r.enable_profiling(true);
+        let (output, report) = r.render_with_profile("t0", &data).unwrap();
+        assert_eq!(output, "2;");
+        let report = report.unwrap();
+        assert_eq!(report.get("each").unwrap().calls, 1);
+        assert_eq!(report.get("if").unwrap().calls, 3);
+        assert_eq!(report.get("eq").unwrap().calls, 3);
+        assert!(report.get("with").is_none());
+        #[cfg(not(feature = "partial_legacy"))]
+        assert_eq!(report.get("> row").unwrap().calls, 1);
+        #[cfg(feature = "partial_legacy")]
+        assert_eq!(report.get(">").unwrap().calls, 1);
+    }
+
+    #[test]
+    fn test_number_locale() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0",
+                                           "{{price}}|{{{count}}}|{{small}}|{{neg}}|{{text}}")
+                    .is_ok());
+
+        let data = btreemap! {
+            "price".to_string() => to_json(&1234567.5),
+            "count".to_string() => to_json(&1234),
+            "small".to_string() => to_json(&12),
+            "neg".to_string() => to_json(&-9876543),
+            "text".to_string() => to_json(&"1234".to_owned())
+        };
+
+        assert_eq!(r.render("t0", &data).unwrap(), "1234567.5|1234|12|-9876543|1234");
+
+        r.set_number_locale(Some(NumberLocale::new(".", ",")));
+        assert_eq!(r.render("t0", &data).unwrap(),
+                   "1.234.567,5|1.234|12|-9.876.543|1234");
+
+        r.set_number_locale(None);
+        assert_eq!(r.render("t0", &data).unwrap(), "1234567.5|1234|12|-9876543|1234");
+    }
+
+    #[test]
+    fn test_number_format_fn() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0", "{{sum}}|{{{count}}}|{{text}}|{{len sum}}")
+                    .is_ok());
+        r.register_helper("len",
+                          Box::new(|h: &Helper,
+                                    _: &Registry,
+                                    rc: &mut RenderContext|
+                                    -> Result<(), RenderError> {
+            let len = h.param(0).unwrap().value().render().len();
+            try!(rc.writer.write(len.to_string().into_bytes().as_ref()));
+            Ok(())
+        }));
+
+        let data = btreemap! {
+            "sum".to_string() => to_json(&(0.1 + 0.2)),
+            "count".to_string() => to_json(&1234),
+            "text".to_string() => to_json(&"0.5".to_owned())
+        };
+        assert_eq!(r.render("t0", &data).unwrap(), "0.30000000000000004|1234|0.5|19");
+
+        r.set_number_format_fn(|n| format!("{:.2}", n.as_f64().unwrap()));
+        assert_eq!(r.render("t0", &data).unwrap(), "0.30|1234.00|0.5|19");
+
+        r.set_number_locale(Some(NumberLocale::new(".", ",")));
+        assert_eq!(r.render("t0", &data).unwrap(), "0,30|1.234,00|0.5|19");
+
+        r.set_number_locale(None);
+        r.unset_number_format_fn();
+        assert_eq!(r.render("t0", &data).unwrap(), "0.30000000000000004|1234|0.5|19");
+    }
+
+    #[test]
+    fn test_render_all() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("index", "<h1>{{title}}</h1>").is_ok());
+        assert!(r.register_template_string("about", "<p>{{author}}</p>").is_ok());
+        assert!(r.register_template_string("broken", "{{#each}}{{/each}}").is_ok());
+        assert!(r.register_template_string("_footer", "by {{author}}").is_ok());
+
+        let data = btreemap! {
+            "title".to_string() => "Home".to_string(),
+            "author".to_string() => "Ning".to_string()
+        };
+
+        let outputs = r.render_all(&data);
+        assert_eq!(outputs.len(), 4);
+        assert_eq!(outputs["index"].as_ref().unwrap(), "<h1>Home</h1>");
+        assert_eq!(outputs["about"].as_ref().unwrap(), "<p>Ning</p>");
+        assert_eq!(outputs["_footer"].as_ref().unwrap(), "by Ning");
+        assert!(outputs["broken"].is_err());
+
+        let outputs = r.render_all_matching(&data, |name| !name.starts_with('_'));
+        assert_eq!(outputs.len(), 3);
+        assert!(!outputs.contains_key("_footer"));
+    }
+
+    #[test]
+    fn test_emit_bom() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("row", "{{this}};").is_ok());
+        assert!(r.register_template_string("t0", "{{#each this}}{{> row}}{{/each}}").is_ok());
+
+        let data = vec![1, 2];
+        assert_eq!(r.render("t0", &data).unwrap(), "1;2;");
+
+        r.emit_bom(true);
+        let output = r.render("t0", &data).unwrap();
+        assert!(output.as_bytes().starts_with(&[0xEF, 0xBB, 0xBF]));
+        assert_eq!(output, "\u{feff}1;2;");
+        assert_eq!(r.render_template("{{this.[0]}}", &data).unwrap(), "\u{feff}1");
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0", "{{x}}|{{y}}").is_ok());
+        assert!(r.register_template_string("t1", "{{x}}\n {{z.a}}").is_ok());
+        assert!(r.register_template_string("t2", "{{#each this}}{{@key}}{{/each}}").is_ok());
+
+        let data = btreemap! {
+            "x".to_string() => None,
+            "y".to_string() => Some("1".to_string())
+        };
+
+        assert_eq!(r.render("t1", &data).unwrap(), "\n ");
+
+        r.set_strict_mode(true);
+        assert_eq!(r.render("t0", &data).unwrap(), "|1");
+        assert_eq!(r.render("t2", &data).unwrap(), "xy");
+
+        let e = r.render("t1", &data).unwrap_err();
+        assert_eq!(e.desc, "Variable \"z.a\" not found in strict mode");
+        assert_eq!(e.line_no, Some(2));
+        assert_eq!(e.column_no, Some(2));
+    }
+
+    #[test]
+    fn test_helper_path_fallback() {
+        let mut r = Registry::new();
+        assert!(r.register_template_string("t0", "{{title 1}}").is_ok());
+        assert!(r.register_template_string("t1", "{{subtitle 1}}").is_ok());
+        assert!(r.register_template_string("t2", "{{title 1 2}}").is_ok());
+
+        let data = btreemap! {
+            "title".to_string() => "<Hello>".to_string()
+        };
+
+        assert!(r.render("t0", &data).is_err());
+
+        r.helper_path_fallback_enable(true);
+        assert_eq!(r.render("t0", &data).unwrap(), "&lt;Hello&gt;");
+        assert_eq!(r.render("t1", &data).unwrap(), "");
+        assert!(r.render("t2", &data).is_err());
+
+        r.helper_path_fallback_enable(false);
+        assert!(r.render("t0", &data).is_err());
+    }
+
+    #[test]
+    fn test_memoized_helper() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let mut r = Registry::new();
+        r.register_memoized_helper("gravatar",
+                                   Box::new(move |h: &Helper,
+                                                  _: &Registry,
+                                                  rc: &mut RenderContext|
+                                                  -> Result<(), RenderError> {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let output = format!("avatar/{}", h.param(0).unwrap().value().render());
+            try!(rc.writer.write(output.into_bytes().as_ref()));
+            Ok(())
+        }));
+        assert!(r.register_template_string("t0",
+                                           "{{gravatar a}},{{gravatar a}},{{gravatar b}}")
+                    .is_ok());
+
+        let data = btreemap! {
+            "a".to_string() => "x".to_string(),
+            "b".to_string() => "y".to_string()
+        };
+        assert_eq!(r.render("t0", &data).unwrap(), "avatar/x,avatar/x,avatar/y");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // cache only lives for one render
+        assert_eq!(r.render("t0", &data).unwrap(), "avatar/x,avatar/x,avatar/y");
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
     }
 
     #[test]
@@ -389,6 +2258,21 @@ mod test {
         assert_eq!("&quot;&lt;&gt;&amp;", r.render("test", &input).unwrap());
     }
 
+    #[test]
+    fn test_no_escape() {
+        let mut r = Registry::new();
+        r.register_template_string("t0", "{{a}}|{{{a}}}|{{json a}}|{{lookup this \"a\"}}")
+            .unwrap();
+
+        let data = btreemap! {"a".to_string() => "<b>".to_string()};
+        assert_eq!(r.render("t0", &data).unwrap(),
+                   "&lt;b&gt;|<b>|&quot;&lt;b&gt;&quot;|<b>");
+
+        r.set_no_escape(true);
+        assert!(r.is_no_escape());
+        assert_eq!(r.render("t0", &data).unwrap(), "<b>|<b>|\"<b>\"|<b>");
+    }
+
     #[test]
     #[cfg(feature="partial_legacy")]
     fn test_template_render() {
@@ -408,7 +2292,7 @@ mod test {
             r.template_renderw("{{> index}}", &{}, &mut sw).unwrap();
         }
 
-        assert_eq!("<h1></h1>".to_string(), sw.to_string());
+        assert_eq!("<h1></h1>".to_string(), sw.into_string().unwrap());
 
         // fail for template error
         match r.template_render("{{ hello", &{}).unwrap_err() {
@@ -426,4 +2310,45 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_render_template() {
+        let r = Registry::new();
+
+        assert_eq!(r.render_template("hello {{this}}", &"world".to_owned()).unwrap(),
+                   "hello world");
+        assert!(r.get_templates().is_empty());
+
+        let mut sw = StringWriter::new();
+        r.render_template_to_write("{{#each this}}{{this}}{{/each}}", &vec![1, 2], &mut sw)
+            .unwrap();
+        assert_eq!(sw.into_string().unwrap(), "12");
+
+        match r.render_template("{{#if a}}", &()).unwrap_err() {
+            TemplateRenderError::TemplateError(e) => assert_eq!(e.line_no, Some(1)),
+            _ => panic!("should be a template error"),
+        }
+        let mut sw = StringWriter::new();
+        match r.render_template_to_write("{{missing 1}}", &(), &mut sw).unwrap_err() {
+            TemplateRenderError::RenderError(e) => {
+                assert_eq!(e.desc, "Helper not defined: \"missing\"")
+            }
+            _ => panic!("should be a render error"),
+        }
+    }
+
+    #[test]
+    fn test_render_template_strip_standalone() {
+        let mut r = Registry::new();
+        r.set_strip_standalone_blocks(true);
+        let source = "<p>\n  {{#if this}}\n  shown\n  {{/if}}\n</p>\n";
+
+        assert_eq!(r.render_template(source, &false).unwrap(), "<p>\n</p>\n");
+        let mut sw = StringWriter::new();
+        r.render_template_to_write(source, &true, &mut sw).unwrap();
+        assert_eq!(sw.into_string().unwrap(), "<p>\n  shown\n</p>\n");
+        let mut sw = StringWriter::new();
+        r.template_renderw2(&mut source.as_bytes(), &false, &mut sw).unwrap();
+        assert_eq!(sw.into_string().unwrap(), "<p>\n</p>\n");
+    }
 }
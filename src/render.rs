@@ -1,9 +1,12 @@
 use std::collections::{HashMap, BTreeMap, VecDeque};
+use std::cell::{Cell, RefCell};
 use std::error;
 use std::fmt;
 use std::rc::Rc;
+use std::time::Instant;
 use std::io::Write;
 use std::io::Error as IOError;
+use std::string::FromUtf8Error;
 
 #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
 use serialize::json::{ToJson, Json};
@@ -11,28 +14,83 @@ use serialize::json::{ToJson, Json};
 use serde_json::value::{Value as Json, ToJson};
 
 use template::{Template, TemplateElement, Parameter, HelperTemplate, TemplateMapping, BlockParam,
-               Directive as DirectiveTemplate};
+               Subexpression, Directive as DirectiveTemplate};
 use template::TemplateElement::*;
-use registry::Registry;
-use context::{Context, JsonRender};
+use registry::{Registry, EscapeMode};
+use context::{self, Context, JsonRender};
+use error::TemplateError;
 use helpers::HelperDef;
 use support::str::StringWriter;
-#[cfg(not(feature="partial_legacy"))]
 use partial;
+use profile::RenderProfile;
+
+/// Category of a `RenderError`
+#[derive(Debug)]
+pub enum RenderErrorKind {
+    HelperNotFound(String),
+    DirectiveNotFound(String),
+    PartialNotFound(String),
+    TemplateNotFound(String),
+    Io(IOError),
+    /// a template failed to compile, like when it's reloaded in dev mode
+    Template(Box<TemplateError>),
+    Other(String),
+}
+
+impl Clone for RenderErrorKind {
+    fn clone(&self) -> RenderErrorKind {
+        match *self {
+            RenderErrorKind::HelperNotFound(ref n) => RenderErrorKind::HelperNotFound(n.clone()),
+            RenderErrorKind::DirectiveNotFound(ref n) => {
+                RenderErrorKind::DirectiveNotFound(n.clone())
+            }
+            RenderErrorKind::PartialNotFound(ref n) => RenderErrorKind::PartialNotFound(n.clone()),
+            RenderErrorKind::TemplateNotFound(ref n) => {
+                RenderErrorKind::TemplateNotFound(n.clone())
+            }
+            // io::Error is not Clone, keep its kind and message
+            RenderErrorKind::Io(ref e) => {
+                RenderErrorKind::Io(IOError::new(e.kind(), e.to_string()))
+            }
+            RenderErrorKind::Template(ref e) => {
+                RenderErrorKind::Template(Box::new(TemplateError {
+                                                       reason: e.reason.clone(),
+                                                       template_name: e.template_name.clone(),
+                                                       line_no: e.line_no,
+                                                       column_no: e.column_no,
+                                                   }))
+            }
+            RenderErrorKind::Other(ref d) => RenderErrorKind::Other(d.clone()),
+        }
+    }
+}
 
 /// Error when rendering data on template.
 #[derive(Debug, Clone)]
 pub struct RenderError {
     pub desc: String,
+    kind: RenderErrorKind,
     pub template_name: Option<String>,
+    /// name of the top-level template being rendered, when the error comes from
+    /// a partial, this differs from `template_name`
+    pub root_template: Option<String>,
     pub line_no: Option<usize>,
     pub column_no: Option<usize>,
 }
 
 impl fmt::Display for RenderError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        match (self.line_no, self.column_no) {
-            (Some(line), Some(col)) => {
+        match (self.line_no, self.column_no, self.partial_root()) {
+            (Some(line), Some(col), Some(root)) => {
+                write!(f,
+                       "Error in partial \"{}\" (while rendering \"{}\") line {}, col {}: {}",
+                       self.template_name.as_ref().unwrap(),
+                       root,
+                       line,
+                       col,
+                       self.desc)
+            }
+            (Some(line), Some(col), None) => {
                 write!(f,
                        "Error rendering \"{}\" line {}, col {}: {}",
                        self.template_name.as_ref().unwrap_or(&"Unnamed template".to_owned()),
@@ -40,6 +98,13 @@ impl fmt::Display for RenderError {
                        col,
                        self.desc)
             }
+            (_, _, Some(root)) => {
+                write!(f,
+                       "Error in partial \"{}\" (while rendering \"{}\"): {}",
+                       self.template_name.as_ref().unwrap(),
+                       root,
+                       self.desc)
+            }
             _ => write!(f, "{}", self.desc),
         }
 
@@ -50,23 +115,78 @@ impl error::Error for RenderError {
     fn description(&self) -> &str {
         &self.desc[..]
     }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match self.kind {
+            RenderErrorKind::Io(ref e) => Some(e),
+            RenderErrorKind::Template(ref e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match self.kind {
+            RenderErrorKind::Io(ref e) => Some(e),
+            RenderErrorKind::Template(ref e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
 }
 
 impl From<IOError> for RenderError {
-    fn from(_: IOError) -> RenderError {
-        RenderError::new("IO Error")
+    fn from(e: IOError) -> RenderError {
+        RenderError::from_kind(RenderErrorKind::Io(e))
+    }
+}
+
+impl From<FromUtf8Error> for RenderError {
+    fn from(_: FromUtf8Error) -> RenderError {
+        RenderError::new("Invalid UTF-8 in rendered output")
     }
 }
 
 impl RenderError {
     pub fn new<T: AsRef<str>>(desc: T) -> RenderError {
+        RenderError::from_kind(RenderErrorKind::Other(desc.as_ref().to_owned()))
+    }
+
+    /// Create an error of given kind, `desc` is generated from the kind
+    pub fn from_kind(kind: RenderErrorKind) -> RenderError {
+        let desc = match kind {
+            RenderErrorKind::HelperNotFound(ref name) => format!("Helper not defined: {:?}", name),
+            RenderErrorKind::DirectiveNotFound(ref name) => {
+                format!("Directive not defined: {:?}", name)
+            }
+            RenderErrorKind::PartialNotFound(ref name) => format!("Partial not found: {:?}", name),
+            RenderErrorKind::TemplateNotFound(ref name) => format!("Template not found: {}", name),
+            RenderErrorKind::Io(ref e) => format!("IO Error: {}", e),
+            RenderErrorKind::Template(ref e) => e.to_string(),
+            RenderErrorKind::Other(ref desc) => desc.clone(),
+        };
+
         RenderError {
-            desc: desc.as_ref().to_owned(),
+            desc: desc,
+            kind: kind,
             template_name: None,
+            root_template: None,
             line_no: None,
             column_no: None,
         }
     }
+
+    /// Returns the category of this error
+    pub fn kind(&self) -> &RenderErrorKind {
+        &self.kind
+    }
+
+    /// Returns the name of root template if the error occurred in another template,
+    /// typically a partial
+    fn partial_root(&self) -> Option<&String> {
+        match (self.template_name.as_ref(), self.root_template.as_ref()) {
+            (Some(t), Some(r)) if t != r => Some(r),
+            _ => None,
+        }
+    }
 }
 
 /// The context of a render call
@@ -74,16 +194,31 @@ impl RenderError {
 /// this context stores information of a render and a writer where generated
 /// content is written to.
 ///
-pub struct RenderContext<'a> {
+pub struct RenderContext<'a, 'b> {
     partials: HashMap<String, Template>,
     path: String,
     local_path_root: VecDeque<String>,
     local_variables: HashMap<String, Json>,
     local_helpers: &'a mut HashMap<String, Rc<Box<HelperDef + 'static>>>,
     default_var: Json,
-    block_context: VecDeque<Context>,
+    block_context: VecDeque<Context<'b>>,
+    /// value of `this` without a path in the context, set by `set_base_value`
+    base_value: Option<Context<'b>>,
+    memoized_outputs: Rc<RefCell<HashMap<String, String>>>,
+    /// set by `set_halt`, shared with derived render contexts
+    halted: Rc<Cell<bool>>,
+    profile: Option<Rc<RefCell<RenderProfile>>>,
+    escape_mode: Option<EscapeMode>,
+    /// line and column of the template element being rendered
+    position: Option<(usize, usize)>,
+    /// number of partials being expanded around the current position
+    partial_depth: usize,
+    /// max number of segments of paths navigated in the context
+    max_path_segments: usize,
     /// the context
-    context: &'a mut Context,
+    context: &'a mut Context<'b>,
+    /// data of the whole render, `@root`, when the context is replaced
+    root: Option<&'b Json>,
     /// the `Write` where page is generated
     pub writer: &'a mut Write,
     /// current template name
@@ -93,12 +228,12 @@ pub struct RenderContext<'a> {
     pub disable_escape: bool,
 }
 
-impl<'a> RenderContext<'a> {
+impl<'a, 'b> RenderContext<'a, 'b> {
     /// Create a render context from a `Write`
-    pub fn new(ctx: &'a mut Context,
+    pub fn new(ctx: &'a mut Context<'b>,
                local_helpers: &'a mut HashMap<String, Rc<Box<HelperDef + 'static>>>,
                w: &'a mut Write)
-               -> RenderContext<'a> {
+               -> RenderContext<'a, 'b> {
         RenderContext {
             partials: HashMap::new(),
             path: ".".to_string(),
@@ -107,7 +242,16 @@ impl<'a> RenderContext<'a> {
             local_helpers: local_helpers,
             default_var: Json::Null,
             block_context: VecDeque::new(),
+            base_value: None,
+            memoized_outputs: Rc::new(RefCell::new(HashMap::new())),
+            halted: Rc::new(Cell::new(false)),
+            profile: None,
+            escape_mode: None,
+            position: None,
+            partial_depth: 0,
+            max_path_segments: context::DEFAULT_MAX_PATH_SEGMENTS,
             context: ctx,
+            root: None,
             writer: w,
             current_template: None,
             root_template: None,
@@ -115,7 +259,7 @@ impl<'a> RenderContext<'a> {
         }
     }
 
-    pub fn derive(&mut self) -> RenderContext {
+    pub fn derive<'c>(&'c mut self) -> RenderContext<'c, 'b> {
         RenderContext {
             partials: self.partials.clone(),
             path: self.path.clone(),
@@ -125,14 +269,38 @@ impl<'a> RenderContext<'a> {
             root_template: self.root_template.clone(),
             default_var: self.default_var.clone(),
             block_context: self.block_context.clone(),
+            base_value: self.base_value.clone(),
+            memoized_outputs: self.memoized_outputs.clone(),
+            halted: self.halted.clone(),
+            profile: self.profile.clone(),
+            escape_mode: self.escape_mode,
+            position: self.position,
+            partial_depth: self.partial_depth,
+            max_path_segments: self.max_path_segments,
 
             disable_escape: self.disable_escape,
             local_helpers: self.local_helpers,
             context: self.context,
+            root: self.root,
             writer: self.writer,
         }
     }
 
+    /// Record time spent in helpers and partials into `profile`
+    pub fn set_profile(&mut self, profile: Rc<RefCell<RenderProfile>>) {
+        self.profile = Some(profile);
+    }
+
+    fn profile_start(&self) -> Option<Instant> {
+        self.profile.as_ref().map(|_| Instant::now())
+    }
+
+    fn profile_record(&self, name: &str, start: Instant) {
+        if let Some(ref profile) = self.profile {
+            profile.borrow_mut().record(name, start.elapsed());
+        }
+    }
+
     pub fn get_partial(&self, name: &str) -> Option<Template> {
         self.partials.get(name).map(|t| t.clone())
     }
@@ -141,12 +309,75 @@ impl<'a> RenderContext<'a> {
         self.partials.insert(name, result);
     }
 
+    /// Returns the number of partials being expanded around the current position
+    pub fn get_partial_depth(&self) -> usize {
+        self.partial_depth
+    }
+
+    pub fn set_partial_depth(&mut self, depth: usize) {
+        self.partial_depth = depth;
+    }
+
     pub fn get_path(&self) -> &String {
         &self.path
     }
 
+    /// Returns the path of the current context in render data, like
+    /// `items.[2]` in an iteration of `{{#each items}}`
+    ///
+    /// The path of the root context is `.`. Unlike `get_path`, the path
+    /// doesn't start with `./`. Useful for helpers reporting where in data they
+    /// are rendered.
+    pub fn current_path(&self) -> &str {
+        if self.path.starts_with("./") {
+            &self.path[2..]
+        } else {
+            &self.path
+        }
+    }
+
+    /// Returns the value of the current context, `this` in the template
+    ///
+    /// Inside `{{#each}}` or `{{#with}}` it's the item or the value of the
+    /// block, so a helper can use the current scope without being passed
+    /// `this`. Block params, like `u` in `{{#with user as |u|}}`, are not part
+    /// of it; use `block_param_value` for them.
+    pub fn current_context_value(&self) -> &Json {
+        match self.base_value {
+            Some(ref base) => base.data(),
+            None => {
+                self.context.navigate_limited(&self.path,
+                                              &self.local_path_root,
+                                              "this",
+                                              self.max_path_segments)
+            }
+        }
+    }
+
     pub fn set_path(&mut self, path: String) {
         self.path = path;
+        self.base_value = None;
+    }
+
+    /// Set the value `this` refers to, when it has no path in the context,
+    /// like an item of a subexpression's result in `{{#each (range 1 3)}}`
+    ///
+    /// Paths like `name` are resolved in this value, and paths starting with
+    /// `../` in the context, so the path of this render context is kept.
+    /// `set_path` clears it.
+    pub fn set_base_value<T>(&mut self, value: &T)
+        where T: ToJson
+    {
+        self.base_value = Some(Context::wraps(value));
+    }
+
+    /// Base value to resolve a path in, unless the path starts with `../`
+    fn base_value_for(&self, path: &str) -> Option<&Context<'b>> {
+        if path.starts_with("..") {
+            None
+        } else {
+            self.base_value.as_ref()
+        }
     }
 
     pub fn get_local_path_root(&self) -> &VecDeque<String> {
@@ -161,20 +392,107 @@ impl<'a> RenderContext<'a> {
         self.local_path_root.pop_front();
     }
 
+    /// Create an error located at the template element being rendered
+    ///
+    /// The error has current template name, line and column set, so helpers
+    /// can report precise locations.
+    ///
+    /// ```
+    /// use handlebars::*;
+    ///
+    /// fn positive(h: &Helper, _: &Handlebars, rc: &mut RenderContext) -> Result<(), RenderError> {
+    ///     match h.param(0).and_then(|v| v.value().as_f64()) {
+    ///         Some(n) if n > 0.0 => Ok(()),
+    ///         _ => Err(rc.error("a positive number expected")),
+    ///     }
+    /// }
+    /// ```
+    pub fn error<T: AsRef<str>>(&self, desc: T) -> RenderError {
+        let mut e = RenderError::new(desc);
+        e.template_name = self.current_template.clone();
+        e.root_template = self.root_template.clone();
+        if let Some((line, col)) = self.position {
+            e.line_no = Some(line);
+            e.column_no = Some(col);
+        }
+        e
+    }
+
+    /// Set the max number of segments of paths navigated in the context
+    pub(crate) fn set_max_path_segments(&mut self, max_segments: usize) {
+        self.max_path_segments = max_segments;
+    }
+
+    /// Escape with given mode instead of the *escape fn* of registry
+    pub fn set_escape_mode(&mut self, escape_mode: EscapeMode) {
+        self.escape_mode = Some(escape_mode);
+    }
+
+    /// Escape `data` for output, as `{{expression}}` does, unless escaping
+    /// is disabled
+    pub fn escape(&self, registry: &Registry, data: String) -> String {
+        if self.disable_escape {
+            data
+        } else if let Some(mode) = self.escape_mode {
+            mode.escape(&data)
+        } else if registry.is_no_escape() {
+            data
+        } else {
+            registry.get_escape_fn()(&data)
+        }
+    }
+
     pub fn set_local_var(&mut self, name: String, value: Json) {
         self.local_variables.insert(name, value);
     }
 
+    /// Render a template, like the `else` branch of a helper, with extra local
+    /// variables
+    ///
+    /// A local `reason` is available as `{{@reason}}` in the template, and
+    /// shadows a local of the same name until the template is rendered. Other
+    /// locals and the context are unchanged, so a block helper can render its
+    /// inverse when a collection is empty and tell why:
+    ///
+    /// ```ignore
+    /// let locals = btreemap! {"reason".to_string() => to_json(&"empty".to_owned())};
+    /// if let Some(t) = h.inverse() {
+    ///     try!(rc.render_with_locals(t, r, &locals));
+    /// }
+    /// ```
+    pub fn render_with_locals(&mut self,
+                              template: &Template,
+                              registry: &Registry,
+                              locals: &BTreeMap<String, Json>)
+                              -> Result<(), RenderError> {
+        let mut local_rc = self.derive();
+        for (name, value) in locals.iter() {
+            let var_name = if name.starts_with('@') {
+                name.clone()
+            } else {
+                format!("@{}", name)
+            };
+            local_rc.set_local_var(var_name, value.clone());
+        }
+        template.render(registry, &mut local_rc)
+    }
+
     pub fn clear_local_vars(&mut self) {
         self.local_variables.clear();
     }
 
+    /// Move `@` variables one level up, like `@index` to `@../index`, before
+    /// entering a new block scope. Named variables like those of `let` are kept.
     pub fn promote_local_vars(&mut self) {
         let mut new_map: HashMap<String, Json> = HashMap::new();
         for key in self.local_variables.keys() {
             let mut new_key = String::new();
-            new_key.push_str("@../");
-            new_key.push_str(&key[1..]);
+            if key.starts_with('@') {
+                new_key.push_str("@../");
+                new_key.push_str(&key[1..]);
+            } else {
+                new_key.push_str(key);
+            }
 
             let v = self.local_variables
                 .get(key)
@@ -198,6 +516,8 @@ impl<'a> RenderContext<'a> {
                     .unwrap()
                     .clone();
                 new_map.insert(new_key, v);
+            } else if !key.starts_with('@') {
+                new_map.insert(key.clone(), self.local_variables.get(key).unwrap().clone());
             }
         }
         self.local_variables = new_map;
@@ -217,13 +537,31 @@ impl<'a> RenderContext<'a> {
         self.block_context.push_front(Context::wraps(ctx));
     }
 
+    /// Push block params, like `item` and `idx` of `as |item idx|`, without
+    /// cloning their values again
+    pub(crate) fn push_block_params(&mut self, params: Vec<(String, Json)>) {
+        self.block_context.push_front(Context::from_pairs(params));
+    }
+
     pub fn pop_block_context(&mut self) {
         self.block_context.pop_front();
     }
 
+    /// Returns the value of a block param, like `u` in
+    /// `{{#with user as |u|}}`, or a path into it like `u.name`
+    ///
+    /// Block params of inner blocks shadow those of outer ones. Returns `None`
+    /// if no enclosing block defines the param, or its value is null.
+    pub fn block_param_value(&self, name: &str) -> Option<&Json> {
+        self.evaluate_in_block_context(name)
+    }
+
     pub fn evaluate_in_block_context(&self, local_path: &str) -> Option<&Json> {
         for bc in self.block_context.iter() {
-            let v = bc.navigate(".", &self.local_path_root, local_path);
+            let v = bc.navigate_limited(".",
+                                        &self.local_path_root,
+                                        local_path,
+                                        self.max_path_segments);
             if !v.is_null() {
                 return Some(v);
             }
@@ -239,14 +577,65 @@ impl<'a> RenderContext<'a> {
             .unwrap_or(false)
     }
 
-    pub fn context(&self) -> &Context {
+    pub fn context(&self) -> &Context<'b> {
         self.context
     }
 
-    pub fn context_mut(&mut self) -> &mut Context {
+    /// Returns the data of the whole render, which `@root` refers to
+    ///
+    /// It's the root of the context unless set by `set_root_data`. A render
+    /// context derived with another context keeps the data of its parent.
+    pub fn root_data(&self) -> &Json {
+        match self.root {
+            Some(data) => data,
+            None => self.context.data(),
+        }
+    }
+
+    /// Set the data `@root` refers to
+    pub fn set_root_data(&mut self, data: &'b Json) {
+        self.root = Some(data);
+    }
+
+    pub fn context_mut(&mut self) -> &mut Context<'b> {
         self.context
     }
 
+    /// Derive a render context that renders given context data, like the
+    /// context of a partial called with hash params
+    ///
+    /// Paths are resolved from the root of `ctx`. The context of this render
+    /// context is not changed.
+    pub fn derive_with_context<'c>(&'c mut self,
+                                   ctx: &'c mut Context<'b>)
+                                   -> RenderContext<'c, 'b> {
+        let mut local_rc = self.derive();
+        local_rc.context = ctx;
+        local_rc.path = ".".to_string();
+        local_rc.local_path_root.clear();
+        local_rc.base_value = None;
+        local_rc
+    }
+
+    /// Render a partial by name to the writer, like `{{> name}}` in a template
+    ///
+    /// Partials defined in templates, like with `{{#*inline}}`, are found
+    /// before templates of the registry, and partial aliases are resolved. The
+    /// partial renders the current context; use `derive_with_context` to render
+    /// it with other data. Like `{{> name}}`, a missing partial renders nothing
+    /// unless strict mode is enabled, and output of a cacheable partial is
+    /// cached.
+    pub fn render_partial(&mut self, name: &str, registry: &Registry) -> Result<(), RenderError> {
+        let found = match try!(partial::find_partial(name, None, registry, self)) {
+            Some(found) => found,
+            None => return Ok(()),
+        };
+
+        let mut local_rc = self.derive();
+        local_rc.partial_depth = found.depth;
+        partial::render_partial_output(&found, registry, &mut local_rc)
+    }
+
     pub fn register_local_helper(&mut self,
                                  name: &str,
                                  def: Box<HelperDef + 'static>)
@@ -261,9 +650,62 @@ impl<'a> RenderContext<'a> {
     pub fn get_local_helper(&self, name: &str) -> Option<Rc<Box<HelperDef + 'static>>> {
         self.local_helpers.get(name).map(|r| r.clone())
     }
+
+    fn get_memoized_output(&self, key: &str) -> Option<String> {
+        self.memoized_outputs.borrow().get(key).cloned()
+    }
+
+    fn set_memoized_output(&mut self, key: String, output: String) {
+        self.memoized_outputs.borrow_mut().insert(key, output);
+    }
+
+    /// Key of cached partial output rendered here: the escape mode, the
+    /// current context and `@` variables, or `None` inside blocks with block
+    /// params or a base value
+    ///
+    /// The whole current context is serialized for the key on every include.
+    pub(crate) fn partial_cache_key(&self) -> Option<String> {
+        if !self.block_context.is_empty() || self.base_value.is_some() {
+            return None;
+        }
+
+        let mut key = if self.disable_escape {
+            "none".to_owned()
+        } else {
+            match self.escape_mode {
+                None => "registry".to_owned(),
+                Some(EscapeMode::Html) => "html".to_owned(),
+                Some(EscapeMode::NoEscape) => "none".to_owned(),
+                Some(EscapeMode::Custom(f)) => format!("{:p}", f as *const ()),
+            }
+        };
+        key.push('\u{0}');
+        key.push_str(&self.current_context_value().to_string());
+        let locals: BTreeMap<&String, &Json> = self.local_variables.iter().collect();
+        for (name, value) in locals {
+            key.push_str(&format!("\u{0}{}={}", name, value));
+        }
+        Some(key)
+    }
+
+    /// Stop rendering the rest of the template, without an error
+    ///
+    /// Elements after the current one are skipped at every level: the rest of
+    /// the helper's block, enclosing blocks, partials and the templates
+    /// including them. Output written so far, including by the helper calling
+    /// this, is kept, and the render returns `Ok`. It applies to render
+    /// contexts derived from this one, and their parents, within one render.
+    pub fn set_halt(&mut self) {
+        self.halted.set(true);
+    }
+
+    /// Returns true if rendering is stopped by `set_halt`
+    pub fn is_halted(&self) -> bool {
+        self.halted.get()
+    }
 }
 
-impl<'a> fmt::Debug for RenderContext<'a> {
+impl<'a, 'b> fmt::Debug for RenderContext<'a, 'b> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(f,
                "partials: {:?}, path: {:?}, local_variables: {:?}, current_template: {:?}, \
@@ -280,10 +722,11 @@ impl<'a> fmt::Debug for RenderContext<'a> {
 
 /// Json wrapper that holds the Json value and reference path information
 ///
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ContextJson {
     path: Option<String>,
-    value: Json,
+    /// shared by params of the same path in one helper call
+    value: Rc<Json>,
 }
 
 impl ContextJson {
@@ -302,6 +745,34 @@ impl ContextJson {
     pub fn value(&self) -> &Json {
         &self.value
     }
+
+    /// Returns the value if it's a string
+    pub fn as_str(&self) -> Option<&str> {
+        context::as_string(&self.value)
+    }
+
+    /// Returns the value if it's an integer that fits in `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        self.value.as_i64()
+    }
+
+    /// Returns the value if it's a number, converted to `f64`
+    pub fn as_f64(&self) -> Option<f64> {
+        self.value.as_f64()
+    }
+
+    /// Returns the value if it's a boolean
+    ///
+    /// Other values are not converted by truthiness, use `JsonTruthy` or
+    /// `Registry::is_truthy` for that.
+    pub fn as_bool(&self) -> Option<bool> {
+        context::as_bool(&self.value)
+    }
+
+    /// Returns the items if the value is an array
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        self.value.as_array()
+    }
 }
 
 /// Render-time Helper data when using in a helper definition
@@ -309,10 +780,47 @@ pub struct Helper<'a> {
     name: &'a str,
     params: Vec<ContextJson>,
     hash: BTreeMap<String, ContextJson>,
+    hash_order: &'a [String],
     block_param: &'a Option<BlockParam>,
     template: &'a Option<Template>,
     inverse: &'a Option<Template>,
     block: bool,
+    /// set when `call_inner` has returned `None` for this call already, so the
+    /// default `HelperDef::call` doesn't run it again
+    call_inner_done: Cell<bool>,
+}
+
+/// Find an evaluated param of the same path, so a path used several times in
+/// one helper call, like `{{cmp x x}}`, is only navigated once and its value
+/// is shared rather than cloned
+fn find_evaluated_param<'c>(p: &Parameter,
+                            params: &[Parameter],
+                            evaluated: &'c [ContextJson])
+                            -> Option<&'c ContextJson> {
+    match p {
+        &Parameter::Name(_) => {
+            params.iter()
+                .zip(evaluated.iter())
+                .find(|&(ep, _)| ep == p)
+                .map(|(_, v)| v)
+        }
+        _ => None,
+    }
+}
+
+/// Same as `find_evaluated_param`, for hash values evaluated so far
+fn find_evaluated_hash<'c>(p: &Parameter,
+                           hash: &BTreeMap<String, Parameter>,
+                           evaluated: &'c BTreeMap<String, ContextJson>)
+                           -> Option<&'c ContextJson> {
+    match p {
+        &Parameter::Name(_) => {
+            hash.iter()
+                .find(|&(k, ep)| ep == p && evaluated.contains_key(k))
+                .and_then(|(k, _)| evaluated.get(k))
+        }
+        _ => None,
+    }
 }
 
 impl<'a, 'b> Helper<'a> {
@@ -320,15 +828,22 @@ impl<'a, 'b> Helper<'a> {
                      registry: &Registry,
                      rc: &'b mut RenderContext)
                      -> Result<Helper<'a>, RenderError> {
-        let mut evaluated_params = Vec::new();
+        let mut evaluated_params: Vec<ContextJson> = Vec::new();
         for p in ht.params.iter() {
-            let r = try!(p.expand(registry, rc));
+            let r = match find_evaluated_param(p, &ht.params, &evaluated_params) {
+                Some(v) => v.clone(),
+                None => try!(p.expand(registry, rc)),
+            };
             evaluated_params.push(r);
         }
 
-        let mut evaluated_hash = BTreeMap::new();
+        let mut evaluated_hash: BTreeMap<String, ContextJson> = BTreeMap::new();
         for (k, p) in ht.hash.iter() {
-            let r = try!(p.expand(registry, rc));
+            let r = match find_evaluated_param(p, &ht.params, &evaluated_params)
+                .or_else(|| find_evaluated_hash(p, &ht.hash, &evaluated_hash)) {
+                Some(v) => v.clone(),
+                None => try!(p.expand(registry, rc)),
+            };
             evaluated_hash.insert(k.clone(), r);
         }
 
@@ -336,14 +851,27 @@ impl<'a, 'b> Helper<'a> {
                name: &ht.name,
                params: evaluated_params,
                hash: evaluated_hash,
+               hash_order: &ht.hash_order,
                block_param: &ht.block_param,
                template: &ht.template,
                inverse: &ht.inverse,
                block: ht.block,
+               call_inner_done: Cell::new(false),
            })
     }
 
+    /// Returns true once if `call_inner` has returned `None` for this call
+    /// already
+    pub(crate) fn take_call_inner_done(&self) -> bool {
+        let done = self.call_inner_done.get();
+        self.call_inner_done.set(false);
+        done
+    }
+
     /// Returns helper name
+    ///
+    /// In a `helperMissing` or `blockHelperMissing` helper, this is the name of
+    /// the helper that was not found.
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -374,6 +902,46 @@ impl<'a, 'b> Helper<'a> {
         self.params.get(idx)
     }
 
+    /// Returns nth helper param, or an error like
+    /// `Param 1 not found for helper "echo"` if it's not given
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use handlebars::*;
+    ///
+    /// fn echo(h: &Helper, _: &Handlebars, rc: &mut RenderContext) -> Result<(), RenderError> {
+    ///     let v = try!(h.param_checked(0, "echo")).value().render();
+    ///     try!(rc.writer.write(v.into_bytes().as_ref()));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn param_checked(&self,
+                         idx: usize,
+                         helper_name: &str)
+                         -> Result<&ContextJson, RenderError> {
+        self.params.get(idx).ok_or_else(|| {
+            RenderError::new(format!("Param {} not found for helper {:?}", idx, helper_name))
+        })
+    }
+
+    /// Returns nth helper param rendered as string, like it would be output by `{{abc}}`
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use handlebars::*;
+    ///
+    /// fn upper(h: &Helper, _: &Handlebars, rc: &mut RenderContext) -> Result<(), RenderError> {
+    ///     let v = h.param_rendered(0).unwrap_or_default();
+    ///     try!(rc.writer.write(v.to_uppercase().into_bytes().as_ref()));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn param_rendered(&self, idx: usize) -> Option<String> {
+        self.params.get(idx).map(|v| v.value().render())
+    }
+
     /// Returns hash, resolved within the context
     pub fn hash(&self) -> &BTreeMap<String, ContextJson> {
         &self.hash
@@ -400,6 +968,59 @@ impl<'a, 'b> Helper<'a> {
         self.hash.get(key)
     }
 
+    /// Return hash value of a given key, or an error like
+    /// `Hash param "v" not found for helper "my_helper"` if it's not given
+    pub fn hash_get_checked(&self,
+                            key: &str,
+                            helper_name: &str)
+                            -> Result<&ContextJson, RenderError> {
+        self.hash.get(key).ok_or_else(|| {
+            RenderError::new(format!("Hash param {:?} not found for helper {:?}", key, helper_name))
+        })
+    }
+
+    /// Returns number of hash entries
+    pub fn hash_len(&self) -> usize {
+        self.hash.len()
+    }
+
+    /// Returns keys of hash, ordered by key
+    pub fn hash_keys(&self) -> impl Iterator<Item = &str> {
+        self.hash.keys().map(|k| k.as_ref())
+    }
+
+    /// Returns hash entries ordered by key, resolved within the context
+    ///
+    /// ## Example
+    ///
+    /// To render `{{attrs class="btn" id=name}}` as html attributes:
+    ///
+    /// ```
+    /// use handlebars::*;
+    ///
+    /// fn attrs_helper(h: &Helper, _: &Handlebars, rc: &mut RenderContext)
+    ///                 -> Result<(), RenderError> {
+    ///     for (key, value) in h.hash_iter() {
+    ///         let attr = format!(" {}=\"{}\"", key, value.value().render());
+    ///         try!(rc.writer.write(attr.into_bytes().as_ref()));
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn hash_iter(&self) -> impl Iterator<Item = (&str, &ContextJson)> {
+        self.hash.iter().map(|(k, v)| (k.as_ref(), v))
+    }
+
+    /// Returns hash entries in the order they are written in the template,
+    /// resolved within the context
+    ///
+    /// Use this instead of `hash_iter` when the output should follow the
+    /// template, like html attributes. A key written twice appears once, at
+    /// its first position, with the last value.
+    pub fn hash_pairs(&self) -> impl Iterator<Item = (&str, &ContextJson)> {
+        self.hash_order.iter().filter_map(move |k| self.hash.get(k).map(|v| (k.as_ref(), v)))
+    }
+
     /// Returns the default inner template if the helper is a block helper.
     ///
     /// Typically you will render the template via: `template.render(registry, render_context)`
@@ -409,10 +1030,55 @@ impl<'a, 'b> Helper<'a> {
     }
 
     /// Returns the template of `else` branch if any
+    ///
+    /// Render it with `RenderContext::render_with_locals` to pass local
+    /// variables to it, or push a block context to render it with other data:
+    ///
+    /// ```ignore
+    /// rc.push_block_context(&reasons);
+    /// let result = h.inverse().map(|t| t.render(r, rc)).unwrap_or(Ok(()));
+    /// rc.pop_block_context();
+    /// ```
     pub fn inverse(&self) -> Option<&Template> {
         (*self.inverse).as_ref().map(|t| t)
     }
 
+    /// Render the inner template to a string, or an empty string if there's no
+    /// inner template
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use handlebars::*;
+    ///
+    /// fn upper(h: &Helper, r: &Handlebars, rc: &mut RenderContext) -> Result<(), RenderError> {
+    ///     let inner = try!(h.render_template(r, rc));
+    ///     try!(rc.writer.write(inner.to_uppercase().into_bytes().as_ref()));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn render_template(&self,
+                           registry: &Registry,
+                           rc: &mut RenderContext)
+                           -> Result<String, RenderError> {
+        match self.template() {
+            Some(t) => t.renders(registry, rc),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Render the template of `else` branch to a string, or an empty string if
+    /// there's no `else` branch
+    pub fn render_inverse(&self,
+                          registry: &Registry,
+                          rc: &mut RenderContext)
+                          -> Result<String, RenderError> {
+        match self.inverse() {
+            Some(t) => t.renders(registry, rc),
+            None => Ok(String::new()),
+        }
+    }
+
     /// Returns if the helper is a block one `{{#helper}}{{/helper}}` or not `{{helper 123}}`
     pub fn is_block(&self) -> bool {
         self.block
@@ -436,6 +1102,25 @@ impl<'a, 'b> Helper<'a> {
             None
         }
     }
+
+    /// Return names of all block params, like `["a", "b", "c"]` for `as |a b c|`
+    ///
+    /// Empty if the helper has no block param.
+    pub fn block_params(&self) -> Vec<&str> {
+        let params: Vec<&Parameter> = match *self.block_param {
+            Some(BlockParam::Single(ref p)) => vec![p],
+            Some(BlockParam::Pair((ref p1, ref p2))) => vec![p1, p2],
+            Some(BlockParam::List(ref l)) => l.iter().collect(),
+            None => Vec::new(),
+        };
+        params.into_iter()
+            .filter_map(|p| if let Parameter::Name(ref s) = *p {
+                            Some(s.as_ref())
+                        } else {
+                            None
+                        })
+            .collect()
+    }
 }
 
 /// Render-time Decorator data when using in a decorator definition
@@ -444,6 +1129,7 @@ pub struct Directive<'a> {
     params: Vec<ContextJson>,
     hash: BTreeMap<String, ContextJson>,
     template: &'a Option<Template>,
+    indent: &'a Option<String>,
 }
 
 impl<'a, 'b> Directive<'a> {
@@ -470,6 +1156,7 @@ impl<'a, 'b> Directive<'a> {
                params: evaluated_params,
                hash: evaluated_hash,
                template: &dt.template,
+               indent: &dt.indent,
            })
     }
 
@@ -502,6 +1189,12 @@ impl<'a, 'b> Directive<'a> {
     pub fn template(&self) -> Option<&Template> {
         (*self.template).as_ref().map(|t| t)
     }
+
+    /// Returns the indentation of a standalone partial, when partials are
+    /// indented
+    pub fn indent(&self) -> Option<&String> {
+        self.indent.as_ref()
+    }
 }
 
 /// Render trait
@@ -518,7 +1211,7 @@ pub trait Renderable {
             try!(self.render(registry, &mut local_rc));
         }
 
-        let s = sw.to_string();
+        let s = try!(sw.into_string());
         Ok(s)
     }
 }
@@ -547,7 +1240,7 @@ impl Parameter {
                     try!(t.as_template().render(registry, &mut local_rc));
                 }
 
-                Ok(local_writer.to_string())
+                Ok(try!(local_writer.into_string()))
             }
             &Parameter::Literal(ref j) => Ok(j.render()),
         }
@@ -558,49 +1251,172 @@ impl Parameter {
                   rc: &mut RenderContext)
                   -> Result<ContextJson, RenderError> {
         match self {
+            &Parameter::Name(ref name) if root_path(name).is_some() => {
+                let value = root_path(name).and_then(|p| navigate_root(rc, p));
+                Ok(ContextJson {
+                       path: None,
+                       value: Rc::new(value.unwrap_or(Json::Null)),
+                   })
+            }
             &Parameter::Name(ref name) => {
-                Ok(rc.get_local_var(&name).map_or_else(|| {
-                                                           ContextJson {
-                                                               path: Some(name.to_owned()),
-                                                               value: rc.evaluate_in_block_context(name).map_or_else(|| {rc.context().navigate(rc.get_path(), rc.get_local_path_root(), name).clone()}, |v| v.clone()),
-                                                           }
-
-                                                       },
-                                                       |v| {
-                                                           ContextJson {
-                                                               path: None,
-                                                               value: v.clone(),
-                                                           }
-                                                       }))
+                if let Some(v) = rc.get_local_var(&name) {
+                    return Ok(ContextJson {
+                                  path: None,
+                                  value: Rc::new(v.clone()),
+                              });
+                }
+
+                let name = parent_alias(name).unwrap_or_else(|| name.to_owned());
+                let value = match rc.evaluate_in_block_context(&name) {
+                    Some(v) => v.clone(),
+                    None => {
+                        // values in the base value have no path in the context
+                        if let Some(base) = rc.base_value_for(&name) {
+                            let value = base.navigate_limited(".",
+                                                              &VecDeque::new(),
+                                                              &name,
+                                                              rc.max_path_segments)
+                                .clone();
+                            return Ok(ContextJson {
+                                          path: None,
+                                          value: Rc::new(value),
+                                      });
+                        }
+                        rc.context()
+                            .navigate_limited(rc.get_path(),
+                                              rc.get_local_path_root(),
+                                              &name,
+                                              rc.max_path_segments)
+                            .clone()
+                    }
+                };
+                Ok(ContextJson {
+                       path: Some(name),
+                       value: Rc::new(value),
+                   })
             }
             &Parameter::Literal(ref j) => {
                 Ok(ContextJson {
                        path: None,
-                       value: j.clone(),
+                       value: Rc::new(j.clone()),
                    })
             }
-            &Parameter::Subexpression(_) => {
+            &Parameter::Subexpression(ref t) => {
+                if t.is_helper() {
+                    if let Some(value) = try!(expand_helper_subexpression(t, registry, rc)) {
+                        return Ok(ContextJson {
+                                      path: None,
+                                      value: Rc::new(value),
+                                  });
+                    }
+                }
+
                 let text_value = try!(self.expand_as_name(registry, rc));
                 Ok(ContextJson {
                        path: None,
-                       value: Json::String(text_value),
+                       value: Rc::new(Json::String(text_value)),
                    })
             }
         }
     }
 }
 
-impl Renderable for Template {
-    fn render(&self, registry: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
-        rc.current_template = self.name.clone();
-        let iter = self.elements.iter();
-        let mut idx = 0;
-        for t in iter {
-            try!(t.render(registry, rc).map_err(|mut e| {
-                // add line/col number if the template has mapping data
-                if e.line_no.is_none() {
+/// `@parent` refers to the context one block up, like `../`. Returns the path
+/// of `@parent` and `@parent.foo` as `..` and `../foo`.
+fn parent_alias(name: &str) -> Option<String> {
+    if name == "@parent" {
+        Some("..".to_owned())
+    } else if name.starts_with("@parent.") || name.starts_with("@parent/") {
+        Some(format!("../{}", &name["@parent.".len()..]))
+    } else {
+        None
+    }
+}
+
+/// `@root` refers to the data of the whole render, from any depth of blocks
+/// and partials. Returns the path of `@root` and `@root.foo` in it as `this`
+/// and `foo`.
+fn root_path(name: &str) -> Option<&str> {
+    if name == "@root" {
+        Some("this")
+    } else if name.starts_with("@root.") || name.starts_with("@root/") {
+        Some(&name["@root.".len()..])
+    } else {
+        None
+    }
+}
+
+fn navigate_root(rc: &RenderContext, path: &str) -> Option<Json> {
+    Context::borrowed(rc.root_data())
+        .navigate_opt_limited(".", &VecDeque::new(), path, rc.max_path_segments)
+        .cloned()
+}
+
+/// Call the helper of a subexpression, keeping the `Json` type of the value it
+/// returns from `call_inner`. Output written by a helper is returned as string.
+///
+/// Returns `None` if the helper is not defined.
+fn expand_helper_subexpression(se: &Subexpression,
+                               registry: &Registry,
+                               rc: &mut RenderContext)
+                               -> Result<Option<Json>, RenderError> {
+    let ht = HelperTemplate::from(se);
+    let helper = try!(Helper::from_template(&ht, registry, rc));
+
+    let local_helper = rc.get_local_helper(&ht.name);
+    let d: &HelperDef = match local_helper {
+        Some(ref d) => &***d,
+        None => {
+            match registry.get_helper(&ht.name) {
+                Some(d) => &**d,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let start = rc.profile_start();
+    let result = match try!(d.call_inner(&helper, registry, rc)) {
+        Some(value) => value,
+        None => {
+            let mut local_writer = StringWriter::new();
+            {
+                let mut local_rc = rc.derive();
+                local_rc.writer = &mut local_writer;
+                // disable html escape for subexpression
+                local_rc.disable_escape = true;
+                // only a helper overriding `call` writes output here
+                helper.call_inner_done.set(true);
+                try!(d.call(&helper, registry, &mut local_rc));
+            }
+            Json::String(try!(local_writer.into_string()))
+        }
+    };
+
+    if let Some(start) = start {
+        rc.profile_record(&ht.name, start);
+    }
+    Ok(Some(result))
+}
+
+impl Renderable for Template {
+    fn render(&self, registry: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        rc.current_template = self.name.clone();
+        let parent_position = rc.position;
+        let iter = self.elements.iter();
+        let mut idx = 0;
+        for t in iter {
+            if rc.is_halted() {
+                break;
+            }
+            if let Some(&TemplateMapping(line, col, _)) =
+                self.mapping.as_ref().and_then(|m| m.get(idx)) {
+                rc.position = Some((line, col));
+            }
+            try!(t.render(registry, rc).map_err(|mut e| {
+                // add line/col number if the template has mapping data
+                if e.line_no.is_none() {
                     if let Some(ref mapping) = self.mapping {
-                        if let Some(&TemplateMapping(line, col)) = mapping.get(idx) {
+                        if let Some(&TemplateMapping(line, col, _)) = mapping.get(idx) {
                             e.line_no = Some(line);
                             e.column_no = Some(col);
 
@@ -612,14 +1428,52 @@ impl Renderable for Template {
                     e.template_name = self.name.clone();
                 }
 
+                if e.root_template.is_none() {
+                    e.root_template = rc.root_template.clone();
+                }
+
                 e
             }));
             idx = idx + 1;
         }
+        rc.position = parent_position;
         Ok(())
     }
 }
 
+impl Template {
+    /// Render this template with some data into a string
+    ///
+    /// This sets up the `Context` and `RenderContext` internally, so a compiled
+    /// template can be rendered without registering it.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use handlebars::{Handlebars, Template};
+    ///
+    /// let registry = Handlebars::new();
+    /// let template = Template::compile("hello {{this}}").unwrap();
+    ///
+    /// let output = template.render_to_string(&registry, &"world".to_owned()).unwrap();
+    /// assert_eq!(output, "hello world");
+    /// ```
+    pub fn render_to_string<T>(&self, registry: &Registry, data: &T) -> Result<String, RenderError>
+        where T: ToJson
+    {
+        let mut sw = StringWriter::new();
+        {
+            let mut ctx = Context::wraps(data);
+            let mut local_helpers = HashMap::new();
+            let mut rc = RenderContext::new(&mut ctx, &mut local_helpers, &mut sw);
+            rc.root_template = self.name.clone();
+            rc.set_max_path_segments(registry.get_max_path_segments());
+            try!(self.render(registry, &mut rc));
+        }
+        Ok(try!(sw.into_string()))
+    }
+}
+
 impl Evaluable for Template {
     fn eval(&self, registry: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
         let iter = self.elements.iter();
@@ -628,7 +1482,7 @@ impl Evaluable for Template {
             try!(t.eval(registry, rc).map_err(|mut e| {
                 if e.line_no.is_none() {
                     if let Some(ref mapping) = self.mapping {
-                        if let Some(&TemplateMapping(line, col)) = mapping.get(idx) {
+                        if let Some(&TemplateMapping(line, col, _)) = mapping.get(idx) {
                             e.line_no = Some(line);
                             e.column_no = Some(col);
 
@@ -645,6 +1499,92 @@ impl Evaluable for Template {
     }
 }
 
+/// Build the cache key of a memoized helper call from its name and resolved arguments
+fn memoize_key(h: &Helper) -> String {
+    let mut key = h.name().to_owned();
+    for p in h.params() {
+        key.push_str(&format!(" {}", p.value()));
+    }
+    for (k, v) in h.hash() {
+        key.push_str(&format!(" {}={}", k, v.value()));
+    }
+    key
+}
+
+/// Call a helper whose output is cached for the rest of current render
+fn call_memoized(d: &HelperDef,
+                 h: &Helper,
+                 registry: &Registry,
+                 rc: &mut RenderContext)
+                 -> Result<(), RenderError> {
+    let key = memoize_key(h);
+    let output = match rc.get_memoized_output(&key) {
+        Some(output) => output,
+        None => {
+            let mut sw = StringWriter::new();
+            {
+                let mut local_rc = rc.derive();
+                local_rc.writer = &mut sw;
+                try!(d.call(h, registry, &mut local_rc));
+            }
+            let output = try!(sw.into_string());
+            rc.set_memoized_output(key, output.clone());
+            output
+        }
+    };
+
+    try!(rc.writer.write(output.into_bytes().as_ref()));
+    Ok(())
+}
+
+/// Check if the value of a path expression exists when strict mode of registry
+/// is enabled
+fn check_strict_path(param: &Parameter,
+                     registry: &Registry,
+                     rc: &RenderContext)
+                     -> Result<(), RenderError> {
+    if let Parameter::Name(ref name) = *param {
+        let path = parent_alias(name).unwrap_or_else(|| name.to_owned());
+        let found = match root_path(name) {
+            Some(p) => navigate_root(rc, p).is_some(),
+            None => {
+                rc.get_local_var(name).is_some() || rc.evaluate_in_block_context(&path).is_some() ||
+                match rc.base_value_for(&path) {
+                    Some(base) => {
+                        base.navigate_opt_limited(".",
+                                                  &VecDeque::new(),
+                                                  &path,
+                                                  rc.max_path_segments)
+                            .is_some()
+                    }
+                    None => {
+                        rc.context()
+                            .navigate_opt_limited(rc.get_path(),
+                                                  rc.get_local_path_root(),
+                                                  &path,
+                                                  rc.max_path_segments)
+                            .is_some()
+                    }
+                }
+            }
+        };
+        if registry.is_strict_mode() && !found {
+            return Err(RenderError::new(format!("Variable {:?} not found in strict mode", name)));
+        }
+    }
+    Ok(())
+}
+
+/// Render value of an expression, numbers are formatted with the number locale
+/// of registry
+fn render_value(value: &Json, registry: &Registry) -> String {
+    if value.is_number() {
+        registry.format_number(value)
+    } else {
+        value.render()
+    }
+}
+
 impl Renderable for TemplateElement {
     fn render(&self, registry: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
         debug!("rendering {:?}, {:?}", self, rc);
@@ -654,45 +1594,66 @@ impl Renderable for TemplateElement {
                 Ok(())
             }
             Expression(ref v) => {
+                try!(check_strict_path(v, registry, rc));
                 let context_json = try!(v.expand(registry, rc));
-                let rendered = context_json.value.render();
+                let rendered = render_value(&context_json.value, registry);
 
-                let output = if !rc.disable_escape {
-                    registry.get_escape_fn()(&rendered)
-                } else {
-                    rendered
-                };
+                let output = rc.escape(registry, rendered);
                 try!(rc.writer.write(output.into_bytes().as_ref()));
                 Ok(())
             }
             HTMLExpression(ref v) => {
+                try!(check_strict_path(v, registry, rc));
                 let context_json = try!(v.expand(registry, rc));
-                let rendered = context_json.value.render();
+                let rendered = render_value(&context_json.value, registry);
                 try!(rc.writer.write(rendered.into_bytes().as_ref()));
                 Ok(())
             }
             HelperExpression(ref ht) |
             HelperBlock(ref ht) => {
                 let helper = try!(Helper::from_template(ht, registry, rc));
-                if let Some(ref d) = rc.get_local_helper(&ht.name) {
+                let start = rc.profile_start();
+                let result = if let Some(ref d) = rc.get_local_helper(&ht.name) {
                     d.call(&helper, registry, rc)
+                } else if !ht.block && registry.is_memoized_helper(&ht.name) {
+                    match registry.get_helper(&ht.name) {
+                        Some(d) => call_memoized(&**d, &helper, registry, rc),
+                        None => Err(RenderError::from_kind(RenderErrorKind::HelperNotFound(ht.name.clone()))),
+                    }
                 } else {
-                    registry.get_helper(&ht.name)
+                    match registry.get_helper(&ht.name)
                         .or(registry.get_helper(if ht.block {
                                                     "blockHelperMissing"
                                                 } else {
                                                     "helperMissing"
-                                                }))
-                        .ok_or(RenderError::new(format!("Helper not defined: {:?}", ht.name)))
-                        .and_then(|d| d.call(&helper, registry, rc))
+                                                })) {
+                        Some(d) => d.call(&helper, registry, rc),
+                        None if !ht.block && ht.params.len() == 1 && ht.hash.is_empty() &&
+                                registry.is_helper_path_fallback_enabled() => {
+                            // treat `{{foo bar}}` as `{{foo}}`
+                            Expression(Parameter::Name(ht.name.clone())).render(registry, rc)
+                        }
+                        None => Err(RenderError::from_kind(RenderErrorKind::HelperNotFound(ht.name.clone()))),
+                    }
+                };
+
+                if let Some(start) = start {
+                    rc.profile_record(&ht.name, start);
                 }
+                result
             }
             DirectiveExpression(_) |
             DirectiveBlock(_) => self.eval(registry, rc),
             #[cfg(not(feature="partial_legacy"))]
             PartialExpression(ref dt) | PartialBlock(ref dt) => {
-                Directive::from_template(dt, registry, rc)
-                    .and_then(|di| partial::expand_partial(&di, registry, rc))
+                Directive::from_template(dt, registry, rc).and_then(|di| {
+                    let start = rc.profile_start();
+                    let result = partial::expand_partial(&di, registry, rc);
+                    if let Some(start) = start {
+                        rc.profile_record(&format!("> {}", di.name()), start);
+                    }
+                    result
+                })
             }
             _ => Ok(()),
         }
@@ -708,7 +1669,8 @@ impl Evaluable for TemplateElement {
                     match registry.get_decorator(&di.name) {
                         Some(d) => (**d).call(&di, registry, rc),
                         None => {
-                            Err(RenderError::new(format!("Directive not defined: {:?}", dt.name)))
+                            Err(RenderError::from_kind(RenderErrorKind::DirectiveNotFound(di.name
+                                .clone())))
                         }
                     }
                 })
@@ -730,7 +1692,7 @@ fn test_raw_string() {
 
         raw_string.render(&r, &mut rc).ok().unwrap();
     }
-    assert_eq!(sw.to_string(), "<h1>hello world</h1>".to_string());
+    assert_eq!(sw.into_string().unwrap(), "<h1>hello world</h1>".to_string());
 }
 
 #[test]
@@ -750,7 +1712,7 @@ fn test_expression() {
         element.render(&r, &mut rc).ok().unwrap();
     }
 
-    assert_eq!(sw.to_string(), "&lt;p&gt;&lt;/p&gt;".to_string());
+    assert_eq!(sw.into_string().unwrap(), "&lt;p&gt;&lt;/p&gt;".to_string());
 }
 
 #[test]
@@ -769,7 +1731,7 @@ fn test_html_expression() {
         element.render(&r, &mut rc).ok().unwrap();
     }
 
-    assert_eq!(sw.to_string(), value.to_string());
+    assert_eq!(sw.into_string().unwrap(), value.to_string());
 }
 
 #[test]
@@ -808,7 +1770,20 @@ fn test_template() {
         template.render(&r, &mut rc).ok().unwrap();
     }
 
-    assert_eq!(sw.to_string(), "<h1>world</h1>".to_string());
+    assert_eq!(sw.into_string().unwrap(), "<h1>world</h1>".to_string());
+}
+
+#[test]
+fn test_template_render_to_string() {
+    let mut r = Registry::new();
+    assert!(r.register_template_string("t1", "<p>{{this}}</p>").is_ok());
+
+    let t = Template::compile("<h1>{{hello}}</h1>{{> t1 hello}}").ok().unwrap();
+    let mut m: HashMap<String, String> = HashMap::new();
+    m.insert("hello".to_string(), "world".to_string());
+
+    assert_eq!(t.render_to_string(&r, &m).unwrap(),
+               "<h1>world</h1><p>world</p>".to_string());
 }
 
 #[test]
@@ -845,14 +1820,14 @@ fn test_render_subexpression() {
     m.insert("const".to_string(), "truthy".to_string());
 
     {
-        if let Err(e) = r.template_renderw("<h1>{{#if (const)}}{{(hello)}}{{/if}}</h1>",
-                                           &m,
-                                           &mut sw) {
+        if let Err(e) = r.render_template_to_write("<h1>{{#if (const)}}{{(hello)}}{{/if}}</h1>",
+                                                   &m,
+                                                   &mut sw) {
             panic!("{}", e);
         }
     }
 
-    assert_eq!(sw.to_string(), "<h1>world</h1>".to_string());
+    assert_eq!(sw.into_string().unwrap(), "<h1>world</h1>".to_string());
 }
 
 #[test]
@@ -880,12 +1855,54 @@ fn test_render_subexpression_issue_115() {
     m.insert("a".to_string(), "123".to_string());
 
     {
-        if let Err(e) = r.template_renderw("{{format (format a)}}", &m, &mut sw) {
+        if let Err(e) = r.render_template_to_write("{{format (format a)}}", &m, &mut sw) {
             panic!("{}", e);
         }
     }
 
-    assert_eq!(sw.to_string(), "123".to_string());
+    assert_eq!(sw.into_string().unwrap(), "123".to_string());
+}
+
+#[test]
+fn test_render_error_kind() {
+    use std::io::ErrorKind;
+
+    let mut r = Registry::new();
+    let m: HashMap<String, String> = HashMap::new();
+
+    assert!(r.register_template_string("t0", "{{missing 1}}").is_ok());
+    assert!(r.register_template_string("t1", "{{#if}}{{/if}}").is_ok());
+
+    match *r.render("t0", &m).unwrap_err().kind() {
+        RenderErrorKind::HelperNotFound(ref name) => assert_eq!(name, "missing"),
+        ref k => panic!("Unexpected error kind {:?}", k),
+    }
+    match *r.render("t1", &m).unwrap_err().kind() {
+        RenderErrorKind::Other(ref desc) => assert_eq!(desc, "Param not found for helper \"if\""),
+        ref k => panic!("Unexpected error kind {:?}", k),
+    }
+    match *r.render("t2", &m).unwrap_err().kind() {
+        RenderErrorKind::TemplateNotFound(ref name) => assert_eq!(name, "t2"),
+        ref k => panic!("Unexpected error kind {:?}", k),
+    }
+
+    let e = RenderError::from(IOError::new(ErrorKind::BrokenPipe, "closed"));
+    assert_eq!(e.desc, "IO Error: closed");
+    assert_eq!(format!("{}", e), "IO Error: closed");
+    assert_eq!(format!("{}", error::Error::cause(&e).unwrap()), "closed");
+    let source = error::Error::source(&e).and_then(|s| s.downcast_ref::<IOError>()).unwrap();
+    assert_eq!(source.kind(), ErrorKind::BrokenPipe);
+    assert!(error::Error::source(&RenderError::new("no source")).is_none());
+    match *e.clone().kind() {
+        RenderErrorKind::Io(ref io) => assert_eq!(io.kind(), ErrorKind::BrokenPipe),
+        ref k => panic!("Unexpected error kind {:?}", k),
+    }
+}
+
+#[test]
+fn test_render_error_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<RenderError>();
 }
 
 #[test]
@@ -906,6 +1923,512 @@ fn test_render_error_line_no() {
     }
 }
 
+#[test]
+fn test_render_context_error() {
+    let mut r = Registry::new();
+    r.register_helper("fail",
+                      Box::new(|_: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        Err(rc.error("bad input"))
+    }));
+    r.register_helper("check",
+                      Box::new(|h: &Helper,
+                                r: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        if let Some(t) = h.template() {
+            try!(t.render(r, rc));
+        }
+        // position of the block itself, after its content is rendered
+        Err(rc.error("check failed"))
+    }));
+
+    assert!(r.register_template_string("t0", "<h1>\n  {{fail 1}}</h1>").is_ok());
+    assert!(r.register_template_string("t1", "\n{{#check}}\n  {{name}}\n{{/check}}").is_ok());
+
+    let m: HashMap<String, String> = HashMap::new();
+    let e = r.render("t0", &m).unwrap_err();
+    assert_eq!(e.desc, "bad input");
+    assert_eq!(e.template_name, Some("t0".to_owned()));
+    assert_eq!((e.line_no, e.column_no), (Some(2), Some(3)));
+
+    let e = r.render("t1", &m).unwrap_err();
+    assert_eq!(e.desc, "check failed");
+    assert_eq!((e.line_no, e.column_no), (Some(2), Some(1)));
+}
+
+#[test]
+fn test_render_error_in_partial() {
+    let mut r = Registry::new();
+    let m: HashMap<String, String> = HashMap::new();
+
+    assert!(r.register_template_string("page", "<table>\n{{> row}}</table>").is_ok());
+    assert!(r.register_template_string("row", "<tr>{{#each}}{{/each}}</tr>").is_ok());
+
+    let e = r.render("page", &m).unwrap_err();
+    assert_eq!(e.template_name, Some("row".to_owned()));
+    assert_eq!(e.root_template, Some("page".to_owned()));
+    assert_eq!(format!("{}", e),
+               "Error in partial \"row\" (while rendering \"page\") line 1, col 5: Param not \
+                found for helper \"each\"");
+
+    let e = r.render("row", &m).unwrap_err();
+    assert_eq!(format!("{}", e),
+               "Error rendering \"row\" line 1, col 5: Param not found for helper \"each\"");
+}
+
+#[test]
+fn test_helper_hash_iter() {
+    let mut r = Registry::new();
+    r.register_helper("attrs",
+                      Box::new(|h: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let keys: Vec<&str> = h.hash_keys().collect();
+        let mut output = format!("{}:{}|", h.hash_len(), keys.join(","));
+        for (key, value) in h.hash_iter() {
+            output.push_str(&format!(" {}=\"{}\"", key, value.value().render()));
+        }
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }));
+
+    assert!(r.register_template_string("t0", "{{attrs name=\"x\" class=\"btn\" id=1}}").is_ok());
+    assert!(r.register_template_string("t1", "{{attrs 1}}").is_ok());
+
+    let m: HashMap<String, String> = HashMap::new();
+    assert_eq!(r.render("t0", &m).unwrap(),
+               "3:class,id,name| class=\"btn\" id=\"1\" name=\"x\"");
+    assert_eq!(r.render("t1", &m).unwrap(), "0:|");
+}
+
+#[test]
+fn test_helper_hash_pairs() {
+    let mut r = Registry::new();
+    r.register_helper("attrs",
+                      Box::new(|h: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let mut output = String::new();
+        for (key, value) in h.hash_pairs() {
+            output.push_str(&format!(" {}=\"{}\"", key, value.value().render()));
+        }
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }));
+
+    assert!(r.register_template_string("t0", "{{attrs name=\"x\" class=\"btn\" id=1}}").is_ok());
+    assert!(r.register_template_string("t1", "{{attrs z=1 a=2 z=3}}").is_ok());
+    assert!(r.register_template_string("t2", "{{attrs a=(attrs y=1 x=2)}}").is_ok());
+
+    let m: HashMap<String, String> = HashMap::new();
+    assert_eq!(r.render("t0", &m).unwrap(),
+               " name=\"x\" class=\"btn\" id=\"1\"");
+    assert_eq!(r.render("t1", &m).unwrap(), " z=\"3\" a=\"2\"");
+    assert_eq!(r.render("t2", &m).unwrap(), " a=\" y=\"1\" x=\"2\"\"");
+}
+
+#[test]
+fn test_helper_missing_name() {
+    let mut r = Registry::new();
+    r.register_helper("helperMissing",
+                      Box::new(|h: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let output = format!("[missing {}/{}]", h.name(), h.params().len());
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }));
+    r.register_helper("blockHelperMissing",
+                      Box::new(|h: &Helper,
+                                r: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let output = format!("[missing block {}:{}]", h.name(), try!(h.render_template(r, rc)));
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }));
+
+    let m: BTreeMap<String, String> = BTreeMap::new();
+    assert_eq!(r.render_template("{{foo 1 2}}|{{#bar}}x{{/bar}}", &m).unwrap(),
+               "[missing foo/2]|[missing block bar:x]");
+}
+
+#[test]
+fn test_helper_current_path() {
+    let mut r = Registry::new();
+    r.register_helper("where",
+                      Box::new(|_: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let name = rc.block_param_value("t.name").map(|v| v.render()).unwrap_or_default();
+        let output = format!("[{} {}]", rc.current_path(), name);
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }));
+
+    let mut m: BTreeMap<String, Vec<BTreeMap<String, String>>> = BTreeMap::new();
+    m.insert("teams".to_owned(),
+             vec![btreemap! {"name".to_string() => "a".to_string()},
+                  btreemap! {"name".to_string() => "b".to_string()}]);
+    assert_eq!(r.render_template("{{where this}}{{#each teams as |t|}}{{where t}}{{/each}}", &m)
+                   .unwrap(),
+               "[. ][teams.[0] a][teams.[1] b]");
+}
+
+#[test]
+fn test_halt() {
+    let mut r = Registry::new();
+    r.register_helper("redirect",
+                      Box::new(|h: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let output = format!("[redirect {}]", h.param(0).unwrap().value().render());
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        rc.set_halt();
+        Ok(())
+    }));
+    assert!(r.register_template_string("t0",
+                                       "a{{#each this}}{{this}}{{#if (eq this 2)}}\
+                                        {{redirect \"/login\"}}!{{/if}}{{/each}}b")
+                .is_ok());
+    assert!(r.register_template_string("t1", "x{{> p}}y").is_ok());
+    assert!(r.register_template_string("p", "1{{redirect \"/\"}}2").is_ok());
+
+    assert_eq!(r.render("t0", &vec![1, 2, 3]).unwrap(), "a12[redirect /login]");
+    assert_eq!(r.render("t0", &vec![1]).unwrap(), "a1b");
+    assert_eq!(r.render("t1", &()).unwrap(), "x1[redirect /]");
+}
+
+#[test]
+fn test_current_context_value() {
+    let mut r = Registry::new();
+    r.register_helper("inspect",
+                      Box::new(|h: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let label = h.param(0).map(|p| p.value().render()).unwrap_or_default();
+        let output = format!("[{} {}]", label, rc.current_context_value());
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }));
+
+    let mut m: BTreeMap<String, Vec<BTreeMap<String, String>>> = BTreeMap::new();
+    m.insert("teams".to_owned(),
+             vec![btreemap! {"name".to_string() => "a".to_string()}]);
+    assert_eq!(r.render_template("{{#each teams}}{{inspect \"team\"}}{{#with name}}\
+                                  {{inspect \"name\"}}{{/with}}{{/each}}",
+                                 &m)
+                   .unwrap(),
+               "[team {\"name\":\"a\"}][name \"a\"]");
+    assert_eq!(r.render_template("{{#each (lookup this \"teams\")}}{{inspect 0}}{{/each}}", &m)
+                   .unwrap(),
+               "[0 {\"name\":\"a\"}]");
+}
+
+#[test]
+fn test_render_partial_from_helper() {
+    use context::to_json;
+
+    let mut r = Registry::new();
+    r.register_helper("card",
+                      Box::new(|h: &Helper,
+                                r: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let name = h.param(0).map(|p| p.value().render()).unwrap_or_default();
+        try!(rc.writer.write("<div>".as_bytes()));
+        match h.param(1) {
+            Some(p) => {
+                let mut ctx = Context::wraps(p.value());
+                let mut local_rc = rc.derive_with_context(&mut ctx);
+                try!(local_rc.render_partial(&name, r));
+            }
+            None => try!(rc.render_partial(&name, r)),
+        }
+        try!(rc.writer.write("</div>".as_bytes()));
+        Ok(())
+    }));
+    assert!(r.register_template_string("user", "{{name}}").is_ok());
+    assert!(r.register_template_string("t0", "{{card \"user\"}}{{card \"user\" boss}}")
+                .is_ok());
+    assert!(r.register_template_string("t1", "{{card \"missing\"}}").is_ok());
+    r.register_partial_alias("person", "user");
+    assert!(r.register_template_string("t2", "{{card \"person\"}}").is_ok());
+
+    let mut m: BTreeMap<String, Json> = BTreeMap::new();
+    m.insert("name".to_owned(), to_json(&"a".to_owned()));
+    m.insert("boss".to_owned(),
+             to_json(&btreemap! {"name".to_string() => "b".to_string()}));
+    assert_eq!(r.render("t0", &m).unwrap(), "<div>a</div><div>b</div>");
+    assert_eq!(r.render("t1", &m).unwrap(), "<div></div>");
+    assert_eq!(r.render("t2", &m).unwrap(), "<div>a</div>");
+
+    r.set_strict_mode(true);
+    assert_eq!(r.render("t1", &m).unwrap_err().desc,
+               "Partial not found: \"missing\"");
+}
+
+#[test]
+fn test_context_json_accessors() {
+    use context::to_json;
+
+    let value = |v: Json| {
+        ContextJson {
+            path: None,
+            value: Rc::new(v),
+        }
+    };
+
+    let s = value(to_json(&"a".to_owned()));
+    assert_eq!(s.as_str(), Some("a"));
+    assert_eq!(s.as_i64(), None);
+    assert_eq!(s.as_bool(), None);
+
+    let n = value(to_json(&-3));
+    assert_eq!(n.as_i64(), Some(-3));
+    assert_eq!(n.as_f64(), Some(-3.0));
+    assert_eq!(n.as_str(), None);
+    assert_eq!(value(to_json(&1.5)).as_i64(), None);
+
+    assert_eq!(value(to_json(&true)).as_bool(), Some(true));
+    assert_eq!(value(to_json(&0)).as_bool(), None);
+
+    let list = value(to_json(&vec![1, 2]));
+    assert_eq!(list.as_array().map(|l| l.len()), Some(2));
+    assert!(value(Json::Null).as_array().is_none());
+}
+
+#[test]
+fn test_helper_checked_params() {
+    let mut r = Registry::new();
+    r.register_helper("greet",
+                      Box::new(|h: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let name = try!(h.param_checked(1, "greet")).value().render();
+        let greeting = try!(h.hash_get_checked("greeting", "greet")).value().render();
+        try!(rc.writer.write(format!("{} {}", greeting, name).into_bytes().as_ref()));
+        Ok(())
+    }));
+
+    assert!(r.register_template_string("t0", "{{greet 1 \"a\" greeting=\"hi\"}}").is_ok());
+    assert!(r.register_template_string("t1", "{{greet 1 greeting=\"hi\"}}").is_ok());
+    assert!(r.register_template_string("t2", "{{greet 1 \"a\"}}").is_ok());
+
+    assert_eq!(r.render("t0", &()).unwrap(), "hi a");
+    assert_eq!(r.render("t1", &()).unwrap_err().desc,
+               "Param 1 not found for helper \"greet\"");
+    assert_eq!(r.render("t2", &()).unwrap_err().desc,
+               "Hash param \"greeting\" not found for helper \"greet\"");
+}
+
+#[test]
+fn test_render_inverse_with_locals() {
+    use context::to_json;
+
+    let mut r = Registry::new();
+    r.register_helper("list",
+                      Box::new(|h: &Helper,
+                                r: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let items = h.param(0).and_then(|p| p.as_array()).cloned();
+        let reason = match items {
+            Some(ref l) if !l.is_empty() => {
+                for item in l.iter() {
+                    try!(rc.writer.write(item.render().into_bytes().as_ref()));
+                }
+                return Ok(());
+            }
+            Some(_) => "empty",
+            None => "missing",
+        };
+
+        let mut locals = BTreeMap::new();
+        locals.insert("reason".to_owned(), to_json(&reason.to_owned()));
+        match h.inverse() {
+            Some(t) => rc.render_with_locals(t, r, &locals),
+            None => Ok(()),
+        }
+    }));
+    assert!(r.register_template_string("t0",
+                                       "{{#list items}}{{else}}no items: {{@reason}} \
+                                        {{@index}}{{/list}}|{{@reason}}")
+                .is_ok());
+
+    let mut m: BTreeMap<String, Json> = BTreeMap::new();
+    assert_eq!(r.render("t0", &m).unwrap(), "no items: missing |");
+    m.insert("items".to_owned(), to_json(&Vec::<i32>::new()));
+    assert_eq!(r.render("t0", &m).unwrap(), "no items: empty |");
+    m.insert("items".to_owned(), to_json(&vec![1, 2]));
+    assert_eq!(r.render("t0", &m).unwrap(), "12|");
+
+    // locals of enclosing blocks are kept
+    assert!(r.register_template_string("t1",
+                                       "{{#each this}}{{#list items}}{{else}}{{@index}}:\
+                                        {{@reason}};{{/list}}{{/each}}")
+                .is_ok());
+    let data = vec![BTreeMap::<String, Json>::new(), m];
+    assert_eq!(r.render("t1", &data).unwrap(), "0:missing;12");
+}
+
+#[test]
+fn test_root_data() {
+    use context::to_json;
+
+    let mut r = Registry::new();
+    assert!(r.register_template_string("t0",
+                                       "{{#each items}}{{#with this}}{{name}}@{{@root.site}} \
+                                        {{/with}}{{/each}}")
+                .is_ok());
+    assert!(r.register_template_string("t1", "{{#each (lookup @root \"items\")}}{{name}}{{/each}}")
+                .is_ok());
+    assert!(r.register_template_string("t2", "{{> card name=\"c\"}}").is_ok());
+    assert!(r.register_template_string("card", "{{name}}@{{@root/site}}{{@root.missing}}")
+                .is_ok());
+
+    let mut data: BTreeMap<String, Json> = BTreeMap::new();
+    data.insert("site".to_owned(), to_json(&"s".to_owned()));
+    data.insert("items".to_owned(),
+                to_json(&vec![btreemap! {"name".to_string() => "a".to_string()},
+                              btreemap! {"name".to_string() => "b".to_string()}]));
+    assert_eq!(r.render("t0", &data).unwrap(), "a@s b@s ");
+    assert_eq!(r.render("t1", &data).unwrap(), "ab");
+    assert_eq!(r.render("t2", &data).unwrap(), "c@s");
+    assert_eq!(r.render_at("t0", &data, "items.[0]").unwrap(), "");
+
+    r.set_strict_mode(true);
+    assert_eq!(r.render("t2", &data).unwrap_err().desc,
+               "Variable \"@root.missing\" not found in strict mode");
+}
+
+#[test]
+fn test_negative_array_index() {
+    let r = Registry::new();
+    let mut m: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    m.insert("titles".to_owned(), vec!["a".to_owned(), "b".to_owned()]);
+
+    assert_eq!(r.render_template("{{titles.[-1]}}|{{titles.[-2]}}|{{titles.[-3]}}", &m)
+                   .unwrap(),
+               "b|a|");
+    assert_eq!(r.render_template("{{titles[-1]}}", &m).unwrap(), "b");
+}
+
+#[test]
+fn test_helper_param_rendered() {
+    let mut r = Registry::new();
+    r.register_helper("show",
+                      Box::new(|h: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let output = format!("{:?}|{:?}", h.param_rendered(0), h.param_rendered(1));
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }));
+    r.register_escape_fn(::escape::no_escape);
+
+    assert!(r.register_template_string("t0", "{{show a}}").is_ok());
+    assert!(r.register_template_string("t1", "{{show 1 true}}").is_ok());
+
+    let mut m: BTreeMap<String, String> = BTreeMap::new();
+    m.insert("a".to_owned(), "<b>".to_owned());
+    assert_eq!(r.render("t0", &m).unwrap(), "Some(\"<b>\")|None");
+    assert_eq!(r.render("t1", &m).unwrap(), "Some(\"1\")|Some(\"true\")");
+}
+
+#[test]
+fn test_helper_render_branches() {
+    let mut r = Registry::new();
+    r.register_helper("shout",
+                      Box::new(|h: &Helper,
+                                r: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        let output = format!("{}|{}",
+                             try!(h.render_template(r, rc)).to_uppercase(),
+                             try!(h.render_inverse(r, rc)).to_lowercase());
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }));
+
+    assert!(r.register_template_string("t0", "{{#shout}}hi {{name}}{{else}}BYE{{/shout}}").is_ok());
+    assert!(r.register_template_string("t1", "{{#shout}}hi{{/shout}}").is_ok());
+    assert!(r.register_template_string("t2", "{{shout name}}").is_ok());
+
+    let mut m: BTreeMap<String, String> = BTreeMap::new();
+    m.insert("name".to_owned(), "Ning".to_owned());
+    assert_eq!(r.render("t0", &m).unwrap(), "HI NING|bye");
+    assert_eq!(r.render("t1", &m).unwrap(), "HI|");
+    assert_eq!(r.render("t2", &m).unwrap(), "|");
+}
+
+#[test]
+fn test_invalid_utf8_output() {
+    let mut r = Registry::new();
+    r.register_helper("bytes",
+                      Box::new(|_: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        try!(rc.writer.write(&[0x68, 0x69, 0xff, 0xfe]));
+        Ok(())
+    }));
+
+    assert!(r.register_template_string("t0", "{{bytes 1}}").is_ok());
+    assert!(r.register_template_string("t1", "{{#if (bytes 1)}}yes{{/if}}").is_ok());
+
+    let m: HashMap<String, String> = HashMap::new();
+    assert_eq!(r.render("t0", &m).unwrap_err().desc,
+               "Invalid UTF-8 in rendered output");
+    assert_eq!(r.render("t1", &m).unwrap_err().desc,
+               "Invalid UTF-8 in rendered output");
+}
+
+#[test]
+fn test_repeated_path_params() {
+    let mut r = Registry::new();
+    r.register_helper("same",
+                      Box::new(|h: &Helper,
+                                _: &Registry,
+                                rc: &mut RenderContext|
+                                -> Result<(), RenderError> {
+        use std::ptr;
+
+        // values of the same path are shared, not only equal
+        let first = h.param(0).unwrap();
+        let same = h.params().iter().all(|p| ptr::eq(p.value(), first.value())) &&
+                   h.hash().values().all(|v| ptr::eq(v.value(), first.value()));
+        let output = format!("{}:{}", same, first.value().as_array().map_or(0, |a| a.len()));
+        try!(rc.writer.write(output.into_bytes().as_ref()));
+        Ok(())
+    }));
+
+    assert!(r.register_template_string("t0", "{{same list list a=list b=list}}").is_ok());
+    assert!(r.register_template_string("t1", "{{#each this}}{{same list other}}{{/each}}").is_ok());
+
+    let list: Vec<u64> = (0..10000).collect();
+    let mut m: HashMap<String, Vec<u64>> = HashMap::new();
+    m.insert("list".to_string(), list.clone());
+    assert_eq!(r.render("t0", &m).unwrap(), "true:10000");
+
+    let mut m2: HashMap<String, Vec<u64>> = HashMap::new();
+    m2.insert("list".to_string(), list);
+    m2.insert("other".to_string(), vec![1]);
+    let data = vec![m2];
+    assert_eq!(r.render("t1", &data).unwrap(), "false:10000");
+}
+
 #[test]
 #[cfg(not(feature="partial_legacy"))]
 fn test_partial_failback_render() {
@@ -918,3 +2441,24 @@ fn test_partial_failback_render() {
     let r = r.render("child", &true).expect("should work");
     assert_eq!(r, "<html>content</html>");
 }
+
+#[test]
+#[cfg(not(feature="partial_legacy"))]
+fn test_missing_partial_failback_render() {
+    let mut r = Registry::new();
+
+    assert!(r.register_template_string("child", "<html>{{#> layout}}default{{/layout}}</html>")
+                .is_ok());
+    assert!(r.register_template_string("bare", "<html>{{> layout}}</html>").is_ok());
+
+    assert_eq!(r.render("child", &true).unwrap(), "<html>default</html>");
+    assert_eq!(r.render("bare", &true).unwrap(), "<html></html>");
+
+    r.set_strict_mode(true);
+    assert_eq!(r.render("child", &true).unwrap(), "<html>default</html>");
+    let e = r.render("bare", &true).unwrap_err();
+    match *e.kind() {
+        RenderErrorKind::PartialNotFound(ref name) => assert_eq!(name, "layout"),
+        _ => panic!("PartialNotFound expected"),
+    }
+}
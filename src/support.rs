@@ -1,5 +1,6 @@
 pub mod str {
     use std::io::{Write, Result};
+    use std::string::FromUtf8Error;
 
     pub struct StringWriter {
         buf: Vec<u8>,
@@ -10,12 +11,9 @@ pub mod str {
             StringWriter { buf: Vec::with_capacity(8 * 1024) }
         }
 
-        pub fn to_string(self) -> String {
-            if let Ok(s) = String::from_utf8(self.buf) {
-                s
-            } else {
-                String::new()
-            }
+        /// Returns written content, or error if it's not valid UTF-8
+        pub fn into_string(self) -> ::std::result::Result<String, FromUtf8Error> {
+            String::from_utf8(self.buf)
         }
     }
 
@@ -44,8 +42,106 @@ pub mod str {
             let _ = sw.write("hello".to_owned().into_bytes().as_ref());
             let _ = sw.write("world".to_owned().into_bytes().as_ref());
 
-            let s = sw.to_string();
+            let s = sw.into_string().unwrap();
             assert_eq!(s, "helloworld".to_string());
         }
+
+        #[test]
+        fn test_string_writer_invalid_utf8() {
+            let mut sw = StringWriter::new();
+
+            let _ = sw.write("hello".as_bytes());
+            let _ = sw.write(&[0xff, 0xfe]);
+
+            assert!(sw.into_string().is_err());
+        }
+    }
+}
+
+pub mod line_ending {
+    use std::io::{Write, Result};
+
+    use registry::LineEnding;
+
+    /// A `Write` adapter converting line endings written through it
+    pub struct LineEndingWriter<'a> {
+        inner: &'a mut Write,
+        line_ending: LineEnding,
+        pending_cr: bool,
+    }
+
+    impl<'a> LineEndingWriter<'a> {
+        pub fn new(inner: &'a mut Write, line_ending: LineEnding) -> LineEndingWriter<'a> {
+            LineEndingWriter {
+                inner: inner,
+                line_ending: line_ending,
+                pending_cr: false,
+            }
+        }
+
+        fn push_line_ending(&self, out: &mut Vec<u8>) {
+            match self.line_ending {
+                LineEnding::CrLf => out.extend_from_slice(b"\r\n"),
+                _ => out.push(b'\n'),
+            }
+        }
+    }
+
+    impl<'a> Write for LineEndingWriter<'a> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            if self.line_ending == LineEnding::Preserve {
+                return self.inner.write(buf);
+            }
+
+            let mut out = Vec::with_capacity(buf.len());
+            for b in buf {
+                // `\r` may be the first half of a `\r\n` split between two writes
+                if self.pending_cr {
+                    self.pending_cr = false;
+                    if *b == b'\n' {
+                        self.push_line_ending(&mut out);
+                        continue;
+                    }
+                    out.push(b'\r');
+                }
+
+                match *b {
+                    b'\r' => self.pending_cr = true,
+                    b'\n' => self.push_line_ending(&mut out),
+                    _ => out.push(*b),
+                }
+            }
+            try!(self.inner.write_all(&out));
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            if self.pending_cr {
+                self.pending_cr = false;
+                try!(self.inner.write_all(b"\r"));
+            }
+            self.inner.flush()
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use registry::LineEnding;
+        use support::line_ending::LineEndingWriter;
+        use support::str::StringWriter;
+        use std::io::Write;
+
+        #[test]
+        fn test_line_ending_writer() {
+            let mut sw = StringWriter::new();
+            {
+                let mut w = LineEndingWriter::new(&mut sw, LineEnding::CrLf);
+                w.write_all(b"a\r").unwrap();
+                w.write_all(b"\nb\n").unwrap();
+                w.write_all(b"c\r").unwrap();
+                w.flush().unwrap();
+            }
+            assert_eq!(sw.into_string().unwrap(), "a\r\nb\r\nc\r".to_owned());
+        }
     }
 }
@@ -2,6 +2,7 @@ use std::slice::Iter;
 use std::iter::Peekable;
 use std::convert::From;
 use std::collections::{BTreeMap, VecDeque};
+use std::ops::Range;
 use pest::prelude::*;
 
 #[cfg(all(feature = "rustc_ser_type", not(feature = "serde_type")))]
@@ -17,8 +18,13 @@ use error::{TemplateError, TemplateErrorReason};
 
 use self::TemplateElement::*;
 
+/// Position of a template element in source: the line and column where it
+/// starts, and its byte range
+///
+/// The range of a block covers everything from its opening tag to its closing
+/// tag. Whitespace omitted with `~` is still part of the text it's trimmed from.
 #[derive(PartialEq, Clone, Debug)]
-pub struct TemplateMapping(pub usize, pub usize);
+pub struct TemplateMapping(pub usize, pub usize, pub Range<usize>);
 
 /// A handlebars template
 #[derive(PartialEq, Clone, Debug)]
@@ -33,6 +39,7 @@ pub struct Subexpression {
     pub name: String,
     pub params: Vec<Parameter>,
     pub hash: BTreeMap<String, Parameter>,
+    pub hash_order: Vec<String>,
 }
 
 impl Subexpression {
@@ -56,6 +63,8 @@ impl Subexpression {
 pub enum BlockParam {
     Single(Parameter),
     Pair((Parameter, Parameter)),
+    /// three or more block params, like `as |a b c|`
+    List(Vec<Parameter>),
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -63,6 +72,8 @@ pub struct ExpressionSpec {
     pub name: Parameter,
     pub params: Vec<Parameter>,
     pub hash: BTreeMap<String, Parameter>,
+    /// hash keys in the order they are written in the template
+    pub hash_order: Vec<String>,
     pub block_param: Option<BlockParam>,
     pub omit_pre_ws: bool,
     pub omit_pro_ws: bool,
@@ -80,6 +91,8 @@ pub struct HelperTemplate {
     pub name: String,
     pub params: Vec<Parameter>,
     pub hash: BTreeMap<String, Parameter>,
+    /// hash keys in the order they are written in the template
+    pub hash_order: Vec<String>,
     pub block_param: Option<BlockParam>,
     pub template: Option<Template>,
     pub inverse: Option<Template>,
@@ -92,6 +105,7 @@ impl<'a> From<&'a Subexpression> for HelperTemplate {
             name: s.name.clone(),
             params: s.params.clone(),
             hash: s.hash.clone(),
+            hash_order: s.hash_order.clone(),
             block_param: None,
             template: None,
             inverse: None,
@@ -106,6 +120,7 @@ pub struct Directive {
     pub params: Vec<Parameter>,
     pub hash: BTreeMap<String, Parameter>,
     pub template: Option<Template>,
+    pub indent: Option<String>,
 }
 
 impl Parameter {
@@ -137,17 +152,66 @@ impl Template {
         }
     }
 
-    fn push_element(&mut self, e: TemplateElement, line: usize, col: usize) {
+    fn push_element(&mut self, e: TemplateElement, line: usize, col: usize, span: Range<usize>) {
         self.elements.push(e);
         if let Some(ref mut maps) = self.mapping {
-            maps.push(TemplateMapping(line, col));
+            maps.push(TemplateMapping(line, col, span));
         }
     }
 
+    /// Push a block, which is mapped when its opening tag is parsed
+    fn push_block(&mut self, e: TemplateElement, end: usize) {
+        self.elements.push(e);
+        if let Some(&mut TemplateMapping(_, _, ref mut span)) =
+            self.mapping.as_mut().and_then(|m| m.last_mut()) {
+            span.end = end;
+        }
+    }
+
+    /// Returns the byte range of the element at `idx` in the source, if the
+    /// template is compiled with mapping
+    ///
+    /// ```
+    /// use handlebars::Template;
+    ///
+    /// let source = "Hello {{#if name}}{{name}}{{/if}}!";
+    /// let t = Template::compile2(source, true).unwrap();
+    /// assert_eq!(&source[t.element_span(1).unwrap()], "{{#if name}}{{name}}{{/if}}");
+    /// ```
+    pub fn element_span(&self, idx: usize) -> Option<Range<usize>> {
+        self.mapping.as_ref().and_then(|m| m.get(idx)).map(|m| m.2.clone())
+    }
+
     pub fn compile<S: AsRef<str>>(source: S) -> Result<Template, TemplateError> {
         Template::compile2(source, false)
     }
 
+    /// Returns true if the template has only text and comments, so its output
+    /// doesn't depend on data
+    pub fn is_static(&self) -> bool {
+        for e in &self.elements {
+            match *e {
+                RawString(_) | Comment(_) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Returns output of a static template, `None` if the template is not static
+    pub fn static_output(&self) -> Option<String> {
+        if !self.is_static() {
+            return None;
+        }
+        let mut output = String::new();
+        for e in &self.elements {
+            if let RawString(ref text) = *e {
+                output.push_str(text);
+            }
+        }
+        Some(output)
+    }
+
     #[inline]
     fn parse_subexpression<'a>(source: &'a str,
                                it: &mut Peekable<Iter<Token<Rule>>>,
@@ -159,6 +223,7 @@ impl Template {
                                             name: name,
                                             params: espec.params,
                                             hash: espec.hash,
+                                            hash_order: espec.hash_order,
                                         }))
         } else {
             // line/col no
@@ -245,19 +310,21 @@ impl Template {
                              -> Result<BlockParam, TemplateError> {
         let p1_name = it.next().unwrap();
         // identifier
-        let p1 = source[p1_name.start..p1_name.end].to_owned();
+        let mut names = vec![Parameter::Name(source[p1_name.start..p1_name.end].to_owned())];
 
-        let p2 = it.peek().and_then(|p2_name| if p2_name.end <= limit {
-                                        Some(source[p2_name.start..p2_name.end].to_owned())
-                                    } else {
-                                        None
-                                    });
+        while it.peek().map(|p_name| p_name.end <= limit) == Some(true) {
+            let p_name = it.next().unwrap();
+            names.push(Parameter::Name(source[p_name.start..p_name.end].to_owned()));
+        }
 
-        if p2.is_some() {
-            it.next();
-            Ok(BlockParam::Pair((Parameter::Name(p1), Parameter::Name(p2.unwrap()))))
-        } else {
-            Ok(BlockParam::Single(Parameter::Name(p1)))
+        match names.len() {
+            1 => Ok(BlockParam::Single(names.remove(0))),
+            2 => {
+                let p2 = names.pop().unwrap();
+                let p1 = names.pop().unwrap();
+                Ok(BlockParam::Pair((p1, p2)))
+            }
+            _ => Ok(BlockParam::List(names)),
         }
     }
 
@@ -268,6 +335,7 @@ impl Template {
                             -> Result<ExpressionSpec, TemplateError> {
         let mut params: Vec<Parameter> = Vec::new();
         let mut hashes: BTreeMap<String, Parameter> = BTreeMap::new();
+        let mut hash_order: Vec<String> = Vec::new();
         let mut omit_pre_ws = false;
         let mut omit_pro_ws = false;
         let mut block_param = None;
@@ -301,6 +369,9 @@ impl Template {
                 }
                 Rule::hash => {
                     let (key, value) = try!(Template::parse_hash(source, it.by_ref(), end));
+                    if !hashes.contains_key(&key) {
+                        hash_order.push(key.clone());
+                    }
                     hashes.insert(key, value);
                 }
                 Rule::block_param => {
@@ -316,6 +387,7 @@ impl Template {
                name: name,
                params: params,
                hash: hashes,
+               hash_order: hash_order,
                block_param: block_param,
                omit_pre_ws: omit_pre_ws,
                omit_pro_ws: omit_pro_ws,
@@ -363,12 +435,18 @@ impl Template {
                         if token.rule == Rule::raw_block_end {
                             let text = &source[prev_end..token.start];
                             let mut t = Template::new(mapping);
-                            t.push_element(RawString(text.to_owned()), line_no, col_no);
+                            t.push_element(RawString(text.to_owned()),
+                                           line_no,
+                                           col_no,
+                                           prev_end..token.start);
                             template_stack.push_front(t);
                         } else {
                             let text = &source[prev_end..token.start];
                             let mut t = template_stack.front_mut().unwrap();
-                            t.push_element(RawString(text.to_owned()), line_no, col_no);
+                            t.push_element(RawString(text.to_owned()),
+                                           line_no,
+                                           col_no,
+                                           prev_end..token.start);
                         }
                     }
                 }
@@ -384,7 +462,10 @@ impl Template {
                             text = text.trim_left();
                         }
                         let mut t = template_stack.front_mut().unwrap();
-                        t.push_element(RawString(text.to_owned()), line_no, col_no);
+                        t.push_element(RawString(text.to_owned()),
+                                       line_no,
+                                       col_no,
+                                       prev_end..token.end);
                     }
                     Rule::helper_block_start |
                     Rule::raw_block_start |
@@ -399,6 +480,7 @@ impl Template {
                                     name: exp.name.as_name().unwrap(),
                                     params: exp.params,
                                     hash: exp.hash,
+                                    hash_order: exp.hash_order,
                                     block_param: exp.block_param,
                                     block: true,
                                     template: None,
@@ -413,6 +495,7 @@ impl Template {
                                     params: exp.params,
                                     hash: exp.hash,
                                     template: None,
+                                    indent: None,
                                 };
                                 directive_stack.push_front(directive);
                             }
@@ -426,7 +509,7 @@ impl Template {
 
                         let mut t = template_stack.front_mut().unwrap();
                         if let Some(ref mut maps) = t.mapping {
-                            maps.push(TemplateMapping(line_no, col_no));
+                            maps.push(TemplateMapping(line_no, col_no, token.start..token.end));
                         }
                     }
                     Rule::invert_tag => {
@@ -449,7 +532,10 @@ impl Template {
                             text = text.trim_left();
                         }
                         let mut t = Template::new(mapping);
-                        t.push_element(RawString(text.to_owned()), line_no, col_no);
+                        t.push_element(RawString(text.to_owned()),
+                                       line_no,
+                                       col_no,
+                                       prev_end..token.end);
                         template_stack.push_front(t);
                     }
                     Rule::expression |
@@ -472,18 +558,19 @@ impl Template {
                             Rule::expression => {
                                 let el = Expression(exp.name);
                                 let mut t = template_stack.front_mut().unwrap();
-                                t.push_element(el, line_no, col_no);
+                                t.push_element(el, line_no, col_no, token.start..token.end);
                             }
                             Rule::html_expression => {
                                 let el = HTMLExpression(exp.name);
                                 let mut t = template_stack.front_mut().unwrap();
-                                t.push_element(el, line_no, col_no);
+                                t.push_element(el, line_no, col_no, token.start..token.end);
                             }
                             Rule::helper_expression => {
                                 let helper_template = HelperTemplate {
                                     name: exp.name.as_name().unwrap(),
                                     params: exp.params,
                                     hash: exp.hash,
+                                    hash_order: exp.hash_order,
                                     block_param: exp.block_param,
                                     block: false,
                                     template: None,
@@ -491,7 +578,7 @@ impl Template {
                                 };
                                 let el = HelperExpression(helper_template);
                                 let mut t = template_stack.front_mut().unwrap();
-                                t.push_element(el, line_no, col_no);
+                                t.push_element(el, line_no, col_no, token.start..token.end);
                             }
                             Rule::directive_expression |
                             Rule::partial_expression => {
@@ -500,6 +587,7 @@ impl Template {
                                     params: exp.params,
                                     hash: exp.hash,
                                     template: None,
+                                    indent: None,
                                 };
                                 let el = if token.rule == Rule::directive_expression {
                                     DirectiveExpression(directive)
@@ -507,7 +595,7 @@ impl Template {
                                     PartialExpression(directive)
                                 };
                                 let mut t = template_stack.front_mut().unwrap();
-                                t.push_element(el, line_no, col_no);
+                                t.push_element(el, line_no, col_no, token.start..token.end);
                             }
                             Rule::helper_block_end |
                             Rule::raw_block_end => {
@@ -521,7 +609,7 @@ impl Template {
                                         h.template = Some(prev_t);
                                     }
                                     let t = template_stack.front_mut().unwrap();
-                                    t.elements.push(HelperBlock(h));
+                                    t.push_block(HelperBlock(h), token.end);
                                 } else {
                                     return Err(TemplateError::of(
                                         TemplateErrorReason::MismatchingClosedHelper(
@@ -537,9 +625,9 @@ impl Template {
                                     d.template = Some(prev_t);
                                     let t = template_stack.front_mut().unwrap();
                                     if token.rule == Rule::directive_block_end {
-                                        t.elements.push(DirectiveBlock(d));
+                                        t.push_block(DirectiveBlock(d), token.end);
                                     } else {
-                                        t.elements.push(PartialBlock(d));
+                                        t.push_block(PartialBlock(d), token.end);
                                     }
                                 } else {
                                     return Err(TemplateError::of(
@@ -551,9 +639,21 @@ impl Template {
                         }
                     }
                     Rule::hbs_comment => {
-                        let text = parser.input().slice(token.start + 3, token.end - 2);
+                        let mut text = parser.input().slice(token.start + 2, token.end - 2);
+                        if text.starts_with('~') {
+                            Template::remove_previous_whitespace(&mut template_stack);
+                            text = &text[1..];
+                        }
+                        omit_pro_ws = text.ends_with('~');
+                        if omit_pro_ws {
+                            text = &text[..text.len() - 1];
+                        }
+
                         let mut t = template_stack.front_mut().unwrap();
-                        t.push_element(Comment(text.to_owned()), line_no, col_no);
+                        t.push_element(Comment(text[1..].to_owned()),
+                                       line_no,
+                                       col_no,
+                                       token.start..token.end);
                     }
                     _ => {}
                 }
@@ -566,7 +666,10 @@ impl Template {
                     let text = &source[prev_end..source.len()];
                     let (line_no, col_no) = parser.input().line_col(prev_end);
                     let mut t = template_stack.front_mut().unwrap();
-                    t.push_element(RawString(text.to_owned()), line_no, col_no);
+                    t.push_element(RawString(text.to_owned()),
+                                   line_no,
+                                   col_no,
+                                   prev_end..source.len());
                 }
                 return Ok(template_stack.pop_front().unwrap());
             }
@@ -585,6 +688,229 @@ impl Template {
             Err(e) => Err(e.in_template(name)),
         }
     }
+
+    /// Remove lines holding nothing but a block helper tag, like `{{#if ..}}`,
+    /// `{{else}}` or `{{/if}}`
+    ///
+    /// The indentation before the tag and the line break after it are removed,
+    /// so a block rendering nothing leaves no blank line in output.
+    pub fn strip_standalone_blocks(&mut self) {
+        self.strip_standalone_lines(true, false);
+    }
+
+    /// Record indentation of partials on their own line, like `  {{> item}}`
+    ///
+    /// The indentation before the tag and the line break after it are removed
+    /// from the template, and every line of the partial's output is indented
+    /// instead. This keeps included content aligned in indentation sensitive
+    /// formats like YAML.
+    pub fn indent_standalone_partials(&mut self) {
+        self.strip_standalone_lines(false, true);
+    }
+
+    /// Strip standalone block tags, like `strip_standalone_blocks`, and indent
+    /// standalone partials, like `indent_standalone_partials`, as enabled
+    ///
+    /// Lines are found standalone in the original text of the whole template
+    /// before any is stripped, so a partial in a standalone block is still
+    /// indented when both are enabled.
+    pub fn strip_standalone_lines(&mut self, blocks: bool, partials: bool) {
+        // a line break between two tags is shared by both of them
+        let marks = self.standalone_marks(true, blocks, partials);
+        self.trim_marked(marks);
+    }
+
+    fn standalone_marks(&self, root: bool, blocks: bool, partials: bool) -> StandaloneMarks {
+        let len = self.elements.len();
+        let mut marks = StandaloneMarks {
+            trims: vec![(false, false); len],
+            indents: vec![None; len],
+            template: Vec::with_capacity(len),
+            inverse: Vec::with_capacity(len),
+        };
+        for el in &self.elements {
+            let (tpl, inv) = match *el {
+                HelperBlock(ref ht) => (ht.template.as_ref(), ht.inverse.as_ref()),
+                DirectiveBlock(ref dt) |
+                PartialBlock(ref dt) => (dt.template.as_ref(), None),
+                _ => (None, None),
+            };
+            marks.template.push(tpl.map(|t| t.standalone_marks(false, blocks, partials)));
+            marks.inverse.push(inv.map(|t| t.standalone_marks(false, blocks, partials)));
+        }
+
+        for (i, el) in self.elements.iter().enumerate() {
+            let before = if i == 0 {
+                root
+            } else {
+                ends_with_line_break(&self.elements[i - 1])
+            };
+            let after = match self.elements.get(i + 1) {
+                Some(next) => starts_with_line_break(next),
+                None => root,
+            };
+
+            match *el {
+                HelperBlock(ref ht) if blocks => {
+                    let tpl = ht.template.as_ref();
+                    let inv = ht.inverse.as_ref();
+                    let inner = inv.or(tpl);
+
+                    let open = before &&
+                               first_element(tpl).map(starts_with_line_break).unwrap_or(false);
+                    let invert = last_element(tpl).map(ends_with_line_break).unwrap_or(false) &&
+                                 first_element(inv).map(starts_with_line_break).unwrap_or(false);
+                    let close = after &&
+                                last_element(inner).map(ends_with_line_break).unwrap_or(false);
+
+                    if open && i > 0 {
+                        marks.trims[i - 1].1 = true;
+                    }
+                    if close && i + 1 < len {
+                        marks.trims[i + 1].0 = true;
+                    }
+                    if let Some(ref mut m) = marks.template[i] {
+                        if open {
+                            m.trim_first_start();
+                        }
+                        if invert || (close && inv.is_none()) {
+                            m.trim_last_end();
+                        }
+                    }
+                    if let Some(ref mut m) = marks.inverse[i] {
+                        if invert {
+                            m.trim_first_start();
+                        }
+                        if close {
+                            m.trim_last_end();
+                        }
+                    }
+                }
+                PartialExpression(_) if partials && before && after => {
+                    let indent = match i.checked_sub(1).map(|p| &self.elements[p]) {
+                        Some(&RawString(ref s)) => {
+                            s.rfind('\n').map(|n| s[n + 1..].to_owned()).unwrap_or_default()
+                        }
+                        _ => String::new(),
+                    };
+                    marks.indents[i] = Some(indent);
+                    if i > 0 {
+                        marks.trims[i - 1].1 = true;
+                    }
+                    if i + 1 < len {
+                        marks.trims[i + 1].0 = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        marks
+    }
+
+    fn trim_marked(&mut self, mut marks: StandaloneMarks) {
+        for (i, el) in self.elements.iter_mut().enumerate() {
+            let (start, end) = marks.trims[i];
+            match *el {
+                RawString(ref mut s) => trim_lines(s, start, end),
+                HelperBlock(ref mut ht) => {
+                    if let (Some(t), Some(m)) = (ht.template.as_mut(), marks.template[i].take()) {
+                        t.trim_marked(m);
+                    }
+                    if let (Some(t), Some(m)) = (ht.inverse.as_mut(), marks.inverse[i].take()) {
+                        t.trim_marked(m);
+                    }
+                }
+                DirectiveBlock(ref mut dt) |
+                PartialBlock(ref mut dt) => {
+                    if let (Some(t), Some(m)) = (dt.template.as_mut(), marks.template[i].take()) {
+                        t.trim_marked(m);
+                    }
+                }
+                PartialExpression(ref mut dt) => {
+                    if let Some(indent) = marks.indents[i].take() {
+                        dt.indent = Some(indent);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn first_element(t: Option<&Template>) -> Option<&TemplateElement> {
+    t.and_then(|t| t.elements.first())
+}
+
+fn last_element(t: Option<&Template>) -> Option<&TemplateElement> {
+    t.and_then(|t| t.elements.last())
+}
+
+fn is_line_space(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\r'
+}
+
+/// Returns true if the element is text ending with a line break and indentation
+fn ends_with_line_break(el: &TemplateElement) -> bool {
+    match *el {
+        RawString(ref s) => {
+            s.rfind('\n').map(|i| s[i + 1..].chars().all(is_line_space)).unwrap_or(false)
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if the element is text starting with spaces and a line break
+fn starts_with_line_break(el: &TemplateElement) -> bool {
+    match *el {
+        RawString(ref s) => {
+            s.find('\n').map(|i| s[..i].chars().all(is_line_space)).unwrap_or(false)
+        }
+        _ => false,
+    }
+}
+
+/// Remove the text up to and including the first line break, and the text
+/// after the last one. Both are removed if the text has a single line break.
+fn trim_lines(s: &mut String, start: bool, end: bool) {
+    let from = if start {
+        s.find('\n').map(|i| i + 1).unwrap_or(0)
+    } else {
+        0
+    };
+    let to = if end {
+        s.rfind('\n').map(|i| i + 1).unwrap_or(s.len())
+    } else {
+        s.len()
+    };
+    *s = if to > from {
+        s[from..to].to_owned()
+    } else {
+        String::new()
+    };
+}
+
+/// Lines to trim in a template and its blocks, decided before any is trimmed
+struct StandaloneMarks {
+    /// trim the first line, and the last line, of each element
+    trims: Vec<(bool, bool)>,
+    /// indentation of standalone partials
+    indents: Vec<Option<String>>,
+    template: Vec<Option<StandaloneMarks>>,
+    inverse: Vec<Option<StandaloneMarks>>,
+}
+
+impl StandaloneMarks {
+    fn trim_first_start(&mut self) {
+        if let Some(t) = self.trims.first_mut() {
+            t.0 = true;
+        }
+    }
+
+    fn trim_last_end(&mut self) {
+        if let Some(t) = self.trims.last_mut() {
+            t.1 = true;
+        }
+    }
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -773,6 +1099,14 @@ fn test_white_space_omitter() {
         }
         _ => unreachable!(),
     }
+
+    let t3 = Template::compile("a \n {{~! note ~}} \n b {{! kept }} c".to_string()).ok().unwrap();
+    assert_eq!(t3.elements.len(), 5);
+    assert_eq!(t3.elements[0], RawString("a".to_string()));
+    assert_eq!(t3.elements[1], Comment(" note ".to_string()));
+    assert_eq!(t3.elements[2], RawString("b ".to_string()));
+    assert_eq!(t3.elements[3], Comment(" kept ".to_string()));
+    assert_eq!(t3.elements[4], RawString(" c".to_string()));
 }
 
 #[test]
@@ -864,9 +1198,9 @@ fn test_template_mapping() {
         Ok(t) => {
             if let Some(ref mapping) = t.mapping {
                 assert_eq!(mapping.len(), t.elements.len());
-                assert_eq!(mapping[0], TemplateMapping(1, 1));
-                assert_eq!(mapping[1], TemplateMapping(2, 3));
-                assert_eq!(mapping[3], TemplateMapping(3, 1));
+                assert_eq!(mapping[0], TemplateMapping(1, 1, 0..8));
+                assert_eq!(mapping[1], TemplateMapping(2, 3, 8..18));
+                assert_eq!(mapping[3], TemplateMapping(3, 1, 19..46));
             } else {
                 panic!("should contains mapping");
             }
@@ -875,6 +1209,31 @@ fn test_template_mapping() {
     }
 }
 
+#[test]
+fn test_element_span() {
+    let source = "{{! note }}\n{{#each list}}{{#if this}}{{this}}{{/if}}{{/each}}\n{{> p}}";
+    let t = Template::compile2(source, true).unwrap();
+    let spans: Vec<&str> = (0..t.elements.len())
+        .map(|i| &source[t.element_span(i).unwrap()])
+        .collect();
+    assert_eq!(spans,
+               vec!["{{! note }}",
+                    "\n",
+                    "{{#each list}}{{#if this}}{{this}}{{/if}}{{/each}}",
+                    "\n",
+                    "{{> p}}"]);
+
+    if let HelperBlock(ref each) = t.elements[2] {
+        let inner = each.template.as_ref().unwrap();
+        assert_eq!(&source[inner.element_span(0).unwrap()],
+                   "{{#if this}}{{this}}{{/if}}");
+    } else {
+        panic!("should be a helper block");
+    }
+
+    assert_eq!(Template::compile(source).unwrap().element_span(0), None);
+}
+
 #[test]
 fn test_whitespace_elements() {
     let c = Template::compile("  {{elem}}\n\t{{#if true}} \
@@ -919,6 +1278,21 @@ fn test_block_param() {
         }
         Err(e) => panic!("{}", e),
     }
+
+    match Template::compile("{{#split name as |first middle last|}}{{first}}{{/split}}") {
+        Ok(t) => {
+            if let HelperBlock(ref ht) = t.elements[0] {
+                assert_eq!(ht.params, vec![Parameter::Name("name".to_owned())]);
+                assert_eq!(ht.block_param,
+                           Some(BlockParam::List(vec![Parameter::Name("first".to_owned()),
+                                                      Parameter::Name("middle".to_owned()),
+                                                      Parameter::Name("last".to_owned())])));
+            } else {
+                panic!("Helper block expected");
+            }
+        }
+        Err(e) => panic!("{}", e),
+    }
 }
 
 #[test]
@@ -978,3 +1352,67 @@ fn test_directive() {
         }
     }
 }
+
+#[test]
+fn test_static_template() {
+    let t = Template::compile("<footer>\n{{! copyright }}(c) 2017</footer>").ok().unwrap();
+    assert!(t.is_static());
+    assert_eq!(t.static_output(), Some("<footer>\n(c) 2017</footer>".to_owned()));
+
+    let t = Template::compile("<footer>{{year}}</footer>").ok().unwrap();
+    assert!(!t.is_static());
+    assert_eq!(t.static_output(), None);
+}
+
+#[test]
+#[cfg(not(feature="partial_legacy"))]
+fn test_indent_standalone_partials() {
+    let mut t = Template::compile("<ul>\n  {{> item}}\n</ul>{{> item}}").ok().unwrap();
+    t.indent_standalone_partials();
+
+    assert_eq!(t.elements[0], RawString("<ul>\n".to_owned()));
+    assert_eq!(t.elements[2], RawString("</ul>".to_owned()));
+    match (&t.elements[1], &t.elements[3]) {
+        (&PartialExpression(ref d0), &PartialExpression(ref d1)) => {
+            assert_eq!(d0.indent, Some("  ".to_owned()));
+            assert_eq!(d1.indent, None);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_strip_standalone_blocks() {
+    let source = "<ul>\n  {{#each items}}\n  <li>{{this}}</li>\n  {{else}}\n  none\n  {{/each}}\n\
+                  </ul>";
+    let mut t = Template::compile(source).ok().unwrap();
+    t.strip_standalone_blocks();
+
+    assert_eq!(t.elements.len(), 3);
+    assert_eq!(t.elements[0], RawString("<ul>\n".to_owned()));
+    assert_eq!(t.elements[2], RawString("</ul>".to_owned()));
+    match t.elements[1] {
+        HelperBlock(ref h) => {
+            let tpl = h.template.as_ref().unwrap();
+            assert_eq!(tpl.elements[0], RawString("  <li>".to_owned()));
+            assert_eq!(tpl.elements[2], RawString("</li>\n".to_owned()));
+            assert_eq!(h.inverse.as_ref().unwrap().elements[0],
+                       RawString("  none\n".to_owned()));
+        }
+        _ => unreachable!(),
+    }
+
+    // tags sharing a line with other content are kept as they are
+    let source = "a {{#if b}}\nc\n{{/if}} d";
+    let mut t = Template::compile(source).ok().unwrap();
+    t.strip_standalone_blocks();
+    assert_eq!(t.elements[0], RawString("a ".to_owned()));
+    assert_eq!(t.elements[2], RawString(" d".to_owned()));
+    match t.elements[1] {
+        HelperBlock(ref h) => {
+            assert_eq!(h.template.as_ref().unwrap().elements[0],
+                       RawString("\nc\n".to_owned()));
+        }
+        _ => unreachable!(),
+    }
+}